@@ -151,6 +151,11 @@ no-usage-stats = No usage statistics available. Select a device to view usage st
 usage-statistics = Usage Statistics:
 
 # Settings
+wallpaper-resolution = Wallpaper Resolution:
+wallpaper-resolution-auto = Auto (fit screen)
+wallpaper-resolution-uhd = UHD (3840x2160)
+wallpaper-resolution-1080p = 1080p (1920x1080)
+wallpaper-resolution-portrait = Portrait (1080x1920)
 display-size = Display Size:
 color-mode = Color Mode:
 light-mode = ☀️ Light
@@ -269,12 +274,19 @@ tray-blacklist-current = Blacklist Current Image
 tray-blacklist-with-title = Blacklist "{ $title }"
 tray-random-favorite = Random Favorite Wallpaper
 tray-no-wallpaper = No wallpaper set
+tray-previous = Previous Wallpaper
+tray-history = Wallpaper History
+tray-rate = Rate Current Image
+tray-rate-clear = No Rating
+tray-undo = Undo Last Keep/Blacklist
+tray-redo = Redo Keep/Blacklist
 tray-quit = Quit
 
 # Main UI (bingtray.rs)
 status-fetching-all = Fetching Bing images and historical data...
 status-fetching-images = Fetching Bing images...
 status-downloading-history = Downloading historical data...
+status-importing-history = Importing historical images ({ $current }/{ $total })
 status-loading-page = Loading more images (page { $page })...
 status-loading-high-res = Loading high resolution image...
 image-title-label = Title: { $title }
@@ -290,3 +302,44 @@ status-blacklisted = Blacklisted
 status-unprocessed = Unprocessed
 switch-favorite = Favorite
 switch-blacklist = Blacklist
+
+# Wallpaper history
+history-title = Wallpaper History
+history-empty = No wallpapers set yet
+history-shown-for = shown { $duration }
+history-set-again = Set Again
+
+# Search
+search = Search
+search-hint = Title, place or photographer
+search-no-results = No images match "{ $query }"
+
+# Ratings
+rating = Rating
+
+# Tags and collections
+tags = Tags
+tag-hint = Add tag
+collections = Collections
+collection-add = Add to collection
+collection-new-hint = New collection
+collection-create = Create
+collection-rotate = Rotate from this collection
+
+# Countries
+country-any = Any country
+country-rotate = Rotate from this country
+
+# Rotation schedule
+schedule = Automatic Rotation:
+schedule-every = Every
+schedule-minutes = minutes
+schedule-hours = hours
+schedule-at = At:
+schedule-add-time = Add time
+schedule-remove-time = Remove this time
+schedule-on-login = On login
+schedule-pool = From:
+schedule-pool-new = New images
+schedule-pool-favorites = Favorites
+schedule-hint = Runs while the tray or the daemon is running
@@ -151,6 +151,11 @@ no-usage-stats = 사용량 통계가 없습니다. 사용량 통계를 보려면
 usage-statistics = 사용량 통계:
 
 # Settings
+wallpaper-resolution = 배경화면 해상도:
+wallpaper-resolution-auto = 자동 (화면에 맞춤)
+wallpaper-resolution-uhd = UHD (3840x2160)
+wallpaper-resolution-1080p = 1080p (1920x1080)
+wallpaper-resolution-portrait = 세로 (1080x1920)
 display-size = 화면 크기:
 color-mode = 색상 모드:
 light-mode = ☀️ 라이트
@@ -269,12 +274,19 @@ tray-blacklist-current = 현재 이미지 차단
 tray-blacklist-with-title = "{ $title }" 차단
 tray-random-favorite = 랜덤 즐겨찾기 배경화면
 tray-no-wallpaper = 배경화면 미설정
+tray-previous = 이전 배경화면
+tray-history = 배경화면 기록
+tray-rate = 현재 이미지 평가
+tray-rate-clear = 평가 없음
+tray-undo = 마지막 보관/차단 실행 취소
+tray-redo = 보관/차단 다시 실행
 tray-quit = 종료
 
 # Main UI (bingtray.rs)
 status-fetching-all = Bing 이미지 및 기록 데이터 가져오는 중...
 status-fetching-images = Bing 이미지 가져오는 중...
 status-downloading-history = 기록 데이터 다운로드 중...
+status-importing-history = 기록 이미지 가져오는 중 ({ $current }/{ $total })
 status-loading-page = 더 많은 이미지 로드 중 (페이지 { $page })...
 status-loading-high-res = 고해상도 이미지 로드 중...
 image-title-label = 제목: { $title }
@@ -290,3 +302,44 @@ status-blacklisted = 차단된 항목
 status-unprocessed = 미처리
 switch-favorite = 즐겨찾기
 switch-blacklist = 차단
+
+# Wallpaper history
+history-title = 배경화면 기록
+history-empty = 아직 설정한 배경화면이 없습니다
+history-shown-for = { $duration } 표시됨
+history-set-again = 다시 설정
+
+# Search
+search = 검색
+search-hint = 제목, 장소 또는 사진작가
+search-no-results = "{ $query }"와(과) 일치하는 이미지가 없습니다
+
+# Ratings
+rating = 평점
+
+# Tags and collections
+tags = 태그
+tag-hint = 태그 추가
+collections = 컬렉션
+collection-add = 컬렉션에 추가
+collection-new-hint = 새 컬렉션
+collection-create = 만들기
+collection-rotate = 이 컬렉션에서 순환
+
+# Countries
+country-any = 모든 국가
+country-rotate = 이 국가에서 순환
+
+# Rotation schedule
+schedule = 자동 순환:
+schedule-every = 매
+schedule-minutes = 분
+schedule-hours = 시간
+schedule-at = 시각:
+schedule-add-time = 시각 추가
+schedule-remove-time = 이 시각 삭제
+schedule-on-login = 로그인 시
+schedule-pool = 대상:
+schedule-pool-new = 새 이미지
+schedule-pool-favorites = 즐겨찾기
+schedule-hint = 트레이나 데몬이 실행 중일 때 동작합니다
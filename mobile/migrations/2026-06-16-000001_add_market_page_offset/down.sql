@@ -0,0 +1,2 @@
+-- Rollback: remove page_offset column
+ALTER TABLE market_codes DROP COLUMN page_offset;
@@ -0,0 +1,3 @@
+-- Add page_offset column to market_codes table
+-- Tracks how far each market's HPImageArchive has been walked (idx offset)
+ALTER TABLE market_codes ADD COLUMN page_offset INTEGER NOT NULL DEFAULT 0;
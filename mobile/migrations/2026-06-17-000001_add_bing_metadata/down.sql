@@ -0,0 +1,8 @@
+-- Remove Bing metadata columns
+DROP INDEX IF EXISTS idx_bing_images_fullstartdate;
+ALTER TABLE bing_images DROP COLUMN quiz;
+ALTER TABLE bing_images DROP COLUMN hsh;
+ALTER TABLE bing_images DROP COLUMN urlbase;
+ALTER TABLE bing_images DROP COLUMN enddate;
+ALTER TABLE bing_images DROP COLUMN fullstartdate;
+ALTER TABLE bing_images DROP COLUMN startdate;
@@ -0,0 +1,11 @@
+-- Add the Bing HPImageArchive metadata that was previously thrown away
+-- startdate/enddate are YYYYMMDD, fullstartdate is YYYYMMDDHHMM (publication time)
+ALTER TABLE bing_images ADD COLUMN startdate TEXT;
+ALTER TABLE bing_images ADD COLUMN fullstartdate TEXT;
+ALTER TABLE bing_images ADD COLUMN enddate TEXT;
+ALTER TABLE bing_images ADD COLUMN urlbase TEXT;
+ALTER TABLE bing_images ADD COLUMN hsh TEXT;
+ALTER TABLE bing_images ADD COLUMN quiz TEXT;
+
+-- Images are listed newest publication first
+CREATE INDEX idx_bing_images_fullstartdate ON bing_images(fullstartdate);
@@ -0,0 +1,5 @@
+-- Remove perceptual hash columns
+DROP INDEX IF EXISTS idx_bing_images_canonical_id;
+DROP INDEX IF EXISTS idx_bing_images_phash;
+ALTER TABLE bing_images DROP COLUMN canonical_id;
+ALTER TABLE bing_images DROP COLUMN phash;
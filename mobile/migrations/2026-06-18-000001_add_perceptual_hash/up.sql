@@ -0,0 +1,7 @@
+-- Perceptual hash (dHash, 16 hex digits) computed once the image bytes are cached
+ALTER TABLE bing_images ADD COLUMN phash TEXT;
+-- Near-duplicates point at the row they duplicate; NULL for canonical rows
+ALTER TABLE bing_images ADD COLUMN canonical_id INTEGER;
+
+CREATE INDEX idx_bing_images_phash ON bing_images(phash);
+CREATE INDEX idx_bing_images_canonical_id ON bing_images(canonical_id);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_wallpaper_history_set_at;
+DROP INDEX IF EXISTS idx_wallpaper_history_url;
+DROP TABLE wallpaper_history;
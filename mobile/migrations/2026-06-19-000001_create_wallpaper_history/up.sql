@@ -0,0 +1,15 @@
+-- Every wallpaper that was set, newest last
+CREATE TABLE wallpaper_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url TEXT NOT NULL,
+    set_at INTEGER NOT NULL,
+    -- Who set it: cli, tray, gui or scheduler
+    source TEXT NOT NULL,
+    -- Monitor the wallpaper was set on; NULL for all monitors
+    monitor TEXT,
+    -- Crop applied (CropCoords JSON); NULL for the full image
+    crop_coords TEXT
+);
+
+CREATE INDEX idx_wallpaper_history_url ON wallpaper_history(url);
+CREATE INDEX idx_wallpaper_history_set_at ON wallpaper_history(set_at);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_status_log_url;
+DROP TABLE status_log;
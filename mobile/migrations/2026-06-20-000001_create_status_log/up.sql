@@ -0,0 +1,18 @@
+-- Append-only log of image status transitions, newest last
+CREATE TABLE status_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url TEXT NOT NULL,
+    old_status TEXT NOT NULL,
+    new_status TEXT NOT NULL,
+    changed_at INTEGER NOT NULL,
+    -- change, undo or redo
+    action TEXT NOT NULL,
+    -- Who made the change: cli, tray, gui or scheduler; NULL for automatic changes (imports)
+    source TEXT,
+    -- For undo and redo: the change entry that is reverted or reapplied
+    reverts_id INTEGER,
+    -- Wallpaper this step replaced; NULL when the wallpaper stayed
+    replaced_wallpaper_url TEXT
+);
+
+CREATE INDEX idx_status_log_url ON status_log(url);
@@ -0,0 +1,42 @@
+-- Full-text index over image titles, copyrights and locations, kept in sync with
+-- bing_images by the triggers below (rowid = bing_images.id)
+CREATE VIRTUAL TABLE bing_images_fts USING fts5(
+    title,
+    copyright,
+    -- Place part of the copyright: "Hnausapollur, Iceland (© ...)" -> "Hnausapollur, Iceland"
+    location,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO bing_images_fts (rowid, title, copyright, location)
+SELECT
+    id,
+    title,
+    COALESCE(copyright, ''),
+    CASE WHEN instr(copyright, ' (') > 0 THEN substr(copyright, 1, instr(copyright, ' (') - 1) ELSE '' END
+FROM bing_images;
+
+CREATE TRIGGER bing_images_fts_insert AFTER INSERT ON bing_images BEGIN
+    INSERT INTO bing_images_fts (rowid, title, copyright, location)
+    VALUES (
+        new.id,
+        new.title,
+        COALESCE(new.copyright, ''),
+        CASE WHEN instr(new.copyright, ' (') > 0 THEN substr(new.copyright, 1, instr(new.copyright, ' (') - 1) ELSE '' END
+    );
+END;
+
+CREATE TRIGGER bing_images_fts_update AFTER UPDATE OF title, copyright ON bing_images BEGIN
+    DELETE FROM bing_images_fts WHERE rowid = old.id;
+    INSERT INTO bing_images_fts (rowid, title, copyright, location)
+    VALUES (
+        new.id,
+        new.title,
+        COALESCE(new.copyright, ''),
+        CASE WHEN instr(new.copyright, ' (') > 0 THEN substr(new.copyright, 1, instr(new.copyright, ' (') - 1) ELSE '' END
+    );
+END;
+
+CREATE TRIGGER bing_images_fts_delete AFTER DELETE ON bing_images BEGIN
+    DELETE FROM bing_images_fts WHERE rowid = old.id;
+END;
@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS bing_images_delete_links;
+DROP INDEX IF EXISTS idx_collection_images_image_id;
+DROP TABLE collection_images;
+DROP TABLE collections;
+DROP INDEX IF EXISTS idx_image_tags_tag_id;
+DROP TABLE image_tags;
+DROP TABLE tags;
@@ -0,0 +1,39 @@
+-- Free-form labels, many per image
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE image_tags (
+    image_id INTEGER NOT NULL REFERENCES bing_images(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (image_id, tag_id)
+);
+
+CREATE INDEX idx_image_tags_tag_id ON image_tags(tag_id);
+
+-- User-curated groups of images, which rotation can be restricted to
+CREATE TABLE collections (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE collection_images (
+    collection_id INTEGER NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+    image_id INTEGER NOT NULL REFERENCES bing_images(id) ON DELETE CASCADE,
+    added_at INTEGER NOT NULL,
+    PRIMARY KEY (collection_id, image_id)
+);
+
+CREATE INDEX idx_collection_images_image_id ON collection_images(image_id);
+
+-- Foreign keys are not enforced on every connection, so deleting an image
+-- removes its links explicitly
+CREATE TRIGGER bing_images_delete_links AFTER DELETE ON bing_images BEGIN
+    DELETE FROM image_tags WHERE image_id = old.id;
+    DELETE FROM collection_images WHERE image_id = old.id;
+END;
@@ -0,0 +1,2 @@
+-- Remove star rating column
+ALTER TABLE bing_images DROP COLUMN rating;
@@ -0,0 +1,2 @@
+-- Star rating (0 = unrated, 1-5 stars), weights favorite rotation
+ALTER TABLE bing_images ADD COLUMN rating INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Remove place and country columns
+DROP INDEX IF EXISTS idx_bing_images_country;
+ALTER TABLE bing_images DROP COLUMN country;
+ALTER TABLE bing_images DROP COLUMN place;
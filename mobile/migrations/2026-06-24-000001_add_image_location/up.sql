@@ -0,0 +1,7 @@
+-- Place and country parsed from the copyright (see src/location.rs)
+-- place is NULL until parsed and '' when the copyright names no place;
+-- country is an ISO 3166-1 code, NULL when unknown
+ALTER TABLE bing_images ADD COLUMN place TEXT;
+ALTER TABLE bing_images ADD COLUMN country TEXT;
+
+CREATE INDEX idx_bing_images_country ON bing_images(country);
@@ -156,10 +156,9 @@ fn parse_bing_response(json: &str) -> Result<Vec<BingImage>, String> {
 
             BingImage {
                 url: full_url,
-                title: img.title,
-                copyright: img.copyright,
-                copyright_link: img.copyright_link,
+                ..img
             }
+            .normalize_title()
         })
         .collect();
 
@@ -195,5 +194,26 @@ mod tests {
         assert_eq!(images.len(), 1);
         assert_eq!(images[0].title, "Test Title");
         assert!(images[0].url.starts_with("https://www.bing.com/"));
+        assert_eq!(images[0].fullstartdate.as_deref(), Some("202602270800"));
+        assert_eq!(images[0].enddate.as_deref(), Some("20260228"));
+        assert_eq!(images[0].urlbase.as_deref(), Some("/th?id=OHR.BingWallpaper_EN-US1234567890"));
+        assert_eq!(images[0].hsh.as_deref(), Some("abc123"));
+        assert_eq!(images[0].copyright_link.as_deref(), Some("https://example.com"));
+    }
+
+    #[test]
+    fn test_parse_bing_response_title_from_copyright() {
+        let json = r#"{
+            "images": [
+                {
+                    "url": "/th?id=OHR.BingWallpaper_JA-JP1234567890_1920x1080.jpg",
+                    "copyright": "Kiyomizu-dera, Kyoto (© Someone)",
+                    "title": "Info"
+                }
+            ]
+        }"#;
+
+        let images = parse_bing_response(json).unwrap();
+        assert_eq!(images[0].title, "Kiyomizu-dera, Kyoto");
     }
 }
@@ -21,6 +21,7 @@ use egui_material3::material_symbol::{
     ICON_SETTINGS,
     ICON_MENU,
     ICON_INFO,
+    ICON_SEARCH,
     ICON_STAR,
     ICON_STAR_OUTLINE,
     ICON_BLOCK,
@@ -49,6 +50,10 @@ static MENU_NEXT_MARKET: AtomicBool = AtomicBool::new(false);
 static MENU_KEEP_CURRENT: AtomicBool = AtomicBool::new(false);
 static MENU_BLACKLIST_CURRENT: AtomicBool = AtomicBool::new(false);
 static MENU_RANDOM_FAVORITE: AtomicBool = AtomicBool::new(false);
+static MENU_PREVIOUS: AtomicBool = AtomicBool::new(false);
+static MENU_HISTORY: AtomicBool = AtomicBool::new(false);
+static MENU_UNDO: AtomicBool = AtomicBool::new(false);
+static MENU_REDO: AtomicBool = AtomicBool::new(false);
 static MENU_INSTALL: AtomicBool = AtomicBool::new(false);
 static MENU_QUIT: AtomicBool = AtomicBool::new(false);
 
@@ -59,6 +64,8 @@ enum CarouselFilter {
     Favorite,
     Blacklisted,
     Unprocessed,
+    Collection(i32),
+    Country(&'static str),
 }
 
 impl CarouselFilter {
@@ -68,15 +75,23 @@ impl CarouselFilter {
             CarouselFilter::Favorite => Some(crate::db::ImageStatus::KeepFavorite),
             CarouselFilter::Blacklisted => Some(crate::db::ImageStatus::Blacklisted),
             CarouselFilter::Unprocessed => Some(crate::db::ImageStatus::Unprocessed),
+            CarouselFilter::Collection(_) | CarouselFilter::Country(_) => None,
         }
     }
 
+    /// Whether search results can be narrowed by this filter (collections and countries can not)
+    fn is_searchable(&self) -> bool {
+        !matches!(self, CarouselFilter::Collection(_) | CarouselFilter::Country(_))
+    }
+
     fn label(&self) -> &'static str {
         match self {
             CarouselFilter::All => "All",
             CarouselFilter::Favorite => "Favorites",
             CarouselFilter::Blacklisted => "Blacklisted",
             CarouselFilter::Unprocessed => "Unprocessed",
+            CarouselFilter::Collection(_) => "Collection",
+            CarouselFilter::Country(_) => "Country",
         }
     }
 }
@@ -234,6 +249,9 @@ pub struct BingtrayApp {
     // User mismatch warning
     #[cfg_attr(feature = "serde", serde(skip))]
     user_mismatch_warning: Option<String>,
+    // Database open/migration failure, shown instead of aborting
+    #[cfg_attr(feature = "serde", serde(skip))]
+    database_error: Option<String>,
     // Menu state
     #[cfg_attr(feature = "serde", serde(skip))]
     menu_open: bool,
@@ -309,6 +327,13 @@ pub struct BingtrayApp {
     carousel_total_count: Option<usize>,
     #[cfg_attr(feature = "serde", serde(skip))]
     carousel_loading: bool,
+    // Search box text and the search the carousel shows (None = no search)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    carousel_search_open: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    carousel_search: String,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    carousel_search_query: Option<String>,
 
     // Progressive carousel loading state
     #[cfg_attr(feature = "serde", serde(skip))]
@@ -333,6 +358,42 @@ pub struct BingtrayApp {
     crop_coords: Option<crate::viewmodel::CropCoords>,
     #[cfg_attr(feature = "serde", serde(skip))]
     show_crop_selector: bool,
+
+    // Historical import progress (current, total) while it runs
+    #[cfg_attr(feature = "serde", serde(skip))]
+    import_progress: Option<(usize, usize)>,
+
+    // Wallpaper history dialog
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history_dialog_open: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history: Option<crate::viewmodel::history::HistoryPage>,
+
+    // Countries with images and the ones rotation is limited to
+    #[cfg_attr(feature = "serde", serde(skip))]
+    countries: Option<Vec<crate::viewmodel::countries::CountrySummary>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rotation_countries: Vec<String>,
+
+    // Star rating of the main panel image
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rating_url: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rating: Option<i32>,
+
+    // Tags and collections (groups of the main panel image, new tag/collection inputs)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    collections: Option<Vec<crate::viewmodel::collections::CollectionSummary>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rotation_collection_id: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    image_groups_url: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    image_groups: Option<crate::viewmodel::collections::ImageGroups>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    new_tag: String,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    new_collection: String,
 }
 
 impl Default for BingtrayApp {
@@ -402,10 +463,27 @@ impl Default for BingtrayApp {
         // Database initialization removed - now handled by ViewModel
 
         // Initialize ViewModel with async mode for GUI
-        let viewmodel = config.as_ref().and_then(|c| {
-            crate::viewmodel::ViewModel::new_async(c.config_dir.join("bingtray.db")).ok()
+        let mut database_error = None;
+        let viewmodel = config.as_ref().and_then(|_| {
+            match crate::db::get_database_path().and_then(crate::viewmodel::ViewModel::new_async) {
+                Ok(viewmodel) => Some(viewmodel),
+                Err(e) => {
+                    log::error!("Failed to open database: {:#}", e);
+                    database_error = Some(format!("Database unavailable: {:#}", e));
+                    None
+                }
+            }
         });
 
+        // Wallpaper resolution lives in the database, shared with the tray and CLI
+        let mut settings = Settings::default();
+        if let Some(setting) = viewmodel.as_ref().and_then(|vm| vm.get_resolution_setting_sync().ok()) {
+            settings.image_resolution = setting.as_str().to_string();
+        }
+        if let Some(schedule) = viewmodel.as_ref().and_then(|vm| vm.get_rotation_schedule_sync().ok()) {
+            settings.rotation_schedule = schedule;
+        }
+
         Self {
             title: "BingtrayApp Window".to_owned(),
             title_bar: false,
@@ -466,6 +544,7 @@ impl Default for BingtrayApp {
             // tray_logic: CalcBingimage::new().ok(), // DEPRECATED - use ViewModel
             viewmodel,
             user_mismatch_warning,
+            database_error,
             // Menu state
             menu_open: false,
             menu_anchor_rect: None,
@@ -475,7 +554,7 @@ impl Default for BingtrayApp {
             bing_loading_status: String::new(),
             bing_loading_progress: None,
             // Settings
-            settings: Settings::default(),
+            settings,
             // Dialog states
             dlg_settings: DlgSettings::default(),
             dlg_about: crate::dlg_about_stt::DlgAbout::default(),
@@ -512,6 +591,9 @@ impl Default for BingtrayApp {
             carousel_current_page: 0,
             carousel_total_count: None,
             carousel_loading: false,
+            carousel_search_open: false,
+            carousel_search: String::new(),
+            carousel_search_query: None,
 
             // Progressive carousel loading state
             carousel_all_images: HashMap::new(),
@@ -526,12 +608,33 @@ impl Default for BingtrayApp {
             main_image_loading: false,
             crop_coords: None,
             show_crop_selector: false,
+            import_progress: None,
+            history_dialog_open: false,
+            history: None,
+            countries: None,
+            rotation_countries: Vec::new(),
+            rating_url: None,
+            rating: None,
+            collections: None,
+            rotation_collection_id: None,
+            image_groups_url: None,
+            image_groups: None,
+            new_tag: String::new(),
+            new_collection: String::new(),
         }
     }
 }
 
 impl eframe::App for BingtrayApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Without a ScreenSizeProvider (desktop), use the monitor size egui reports
+        if self.screen_size_provider.is_none() && crate::viewmodel::resolution::screen_size().is_none() {
+            if let Some(monitor_size) = ctx.input(|i| i.viewport().monitor_size) {
+                let physical = monitor_size * ctx.pixels_per_point();
+                crate::viewmodel::resolution::set_screen_size(physical.x as i32, physical.y as i32);
+            }
+        }
+
         // Poll ViewModel events
         if let Some(ref viewmodel) = self.viewmodel {
             for event in viewmodel.poll_events() {
@@ -548,6 +651,11 @@ impl eframe::App for BingtrayApp {
                     }
                     ViewModelEvent::WallpaperSet { success } => {
                         log::info!("ViewModel: Wallpaper set: {}", success);
+
+                        // Show the new entry
+                        if success && self.history_dialog_open {
+                            self.load_history_page(0);
+                        }
                     }
                     ViewModelEvent::StatusUpdated { url, status } => {
                         log::info!("ViewModel: Status updated for {}: {:?}", url, status);
@@ -569,58 +677,18 @@ impl eframe::App for BingtrayApp {
                         log::info!("Carousel page {} loaded: {} images, {} total",
                                    page, images.len(), total_count);
 
-                        // Convert BingImage to CarouselImage with proper thumbnail URLs
-                        let carousel_images: Vec<CarouselImage> = images.into_iter()
-                            .map(|img| {
-                                // Generate base URL (original database URL)
-                                let base_url = if img.url.starts_with("http") {
-                                    img.url.clone()
-                                } else {
-                                    format!("https://bing.com{}", img.url)
-                                };
+                        // Results of a search that was replaced meanwhile would mix in
+                        if self.carousel_search_query.is_none() {
+                            self.append_carousel_page(page, images, total_count);
+                        }
+                    }
 
-                                // Create thumbnail and full URLs with size parameters
-                                let separator = if base_url.contains('?') { "&" } else { "?" };
-                                let thumbnail_url = format!("{}{}w=320&h=240", base_url, separator);
-                                let full_url = format!("{}{}w=1920&h=1080", base_url, separator);
-
-                                CarouselImage {
-                                    title: img.title.clone(),
-                                    copyright: img.copyright.clone().unwrap_or_default(),
-                                    copyright_link: img.copyright_link.clone().unwrap_or_default(),
-                                    base_url,  // Store original URL
-                                    thumbnail_url,
-                                    full_url,
-                                    image_bytes: None,  // Loaded on demand
-                                    status: Some(img.status),
-                                }
-                            })
-                            .collect();
+                    ViewModelEvent::SearchResultsLoaded { query, page, images, total_count } => {
+                        log::info!("Search page {} for {:?} loaded: {} images, {} total",
+                                   page, query, images.len(), total_count);
 
-                        // For progressive loading, append to all_images instead of page cache
-                        self.carousel_all_images
-                            .entry(self.carousel_filter)
-                            .or_insert_with(Vec::new)
-                            .extend(carousel_images);
-
-                        self.carousel_total_count = Some(total_count);
-
-                        // Check if we need to load more pages
-                        let current_loaded = self.carousel_all_images
-                            .get(&self.carousel_filter)
-                            .map(|v| v.len())
-                            .unwrap_or(0);
-
-                        if current_loaded < total_count {
-                            // Load next page
-                            let next_page = page + 1;
-                            log::info!("Auto-loading next page {}: {}/{} images loaded",
-                                      next_page, current_loaded, total_count);
-                            self.load_carousel_page(next_page);
-                        } else {
-                            log::info!("All {} images loaded for filter {:?}",
-                                      current_loaded, self.carousel_filter);
-                            self.carousel_loading = false;
+                        if self.carousel_search_query.as_deref() == Some(query.as_str()) {
+                            self.append_carousel_page(page, images, total_count);
                         }
                     }
 
@@ -645,19 +713,99 @@ impl eframe::App for BingtrayApp {
                     ViewModelEvent::CropCoordsSaved { url } => {
                         log::info!("Crop coords saved for: {}", url);
                     }
+
+                    ViewModelEvent::ImportProgress { current, total } => {
+                        log::info!("ViewModel: Historical import: {}/{}", current, total);
+                        self.import_progress = Some((current, total));
+                    }
+                    ViewModelEvent::ImportComplete { added, cancelled } => {
+                        log::info!("ViewModel: Historical import {}: {} new images",
+                                   if cancelled { "cancelled" } else { "complete" }, added);
+                        self.import_progress = None;
+
+                        // The carousel was loaded before the import, show the new rows
+                        if added > 0 {
+                            self.countries = None;
+                            self.reset_carousel();
+                        }
+                    }
+                    ViewModelEvent::HistoryPageLoaded { history } => {
+                        log::info!("ViewModel: History page {} loaded ({} entries)", history.page, history.total);
+                        self.history = Some(history);
+                    }
+                    ViewModelEvent::UndoApplied { outcome } => {
+                        let Some(outcome) = outcome else {
+                            log::info!("ViewModel: Nothing to undo/redo");
+                            continue;
+                        };
+                        log::info!("ViewModel: {} {}: now {:?}", outcome.action.as_str(), outcome.url, outcome.status);
+
+                        // The status changed and the wallpaper may have been put back
+                        self.reset_carousel();
+                        if self.history_dialog_open {
+                            self.load_history_page(0);
+                        }
+                    }
+                    ViewModelEvent::CountriesLoaded { countries, rotation_countries } => {
+                        log::info!("ViewModel: {} countries loaded", countries.len());
+                        self.countries = Some(countries);
+                        self.rotation_countries = rotation_countries;
+                    }
+                    ViewModelEvent::RatingLoaded { url, rating } => {
+                        if self.rating_url.as_deref() == Some(url.as_str()) {
+                            self.rating = Some(rating);
+                        }
+                    }
+                    ViewModelEvent::ImageGroupsLoaded { url, groups } => {
+                        if self.image_groups_url.as_deref() == Some(url.as_str()) {
+                            self.image_groups = Some(groups);
+                        }
+                    }
+                    ViewModelEvent::CollectionsLoaded { collections, rotation_collection_id } => {
+                        log::info!("ViewModel: {} collections loaded", collections.len());
+                        self.dlg_settings.collections = collections
+                            .iter()
+                            .map(|summary| (summary.collection.id, summary.collection.name.clone()))
+                            .collect();
+                        self.collections = Some(collections);
+                        self.rotation_collection_id = rotation_collection_id;
+
+                        // Membership may have changed, collection pages are reloaded when shown
+                        self.carousel_all_images.retain(|filter, _| !matches!(filter, CarouselFilter::Collection(_)));
+                        if matches!(self.carousel_filter, CarouselFilter::Collection(_)) {
+                            self.carousel_loaded_images.clear();
+                            self.carousel_next_batch_index = 0;
+                            self.carousel_last_scroll_center = 0;
+                            self.load_carousel_page(0);
+                        }
+                    }
+                    ViewModelEvent::LegacyImportComplete { report } => {
+                        log::info!("ViewModel: Imported files of an older version: {}", report.summary());
+
+                        // Statuses changed, reload the carousel
+                        self.reset_carousel();
+                    }
                 }
             }
         }
 
         // Load initial carousel page if not loaded
-        if let Some(ref viewmodel) = self.viewmodel {
-            if !self.carousel_all_images.contains_key(&self.carousel_filter) && !self.carousel_loading {
-                log::info!("Loading initial carousel page");
-                self.carousel_loading = true;
-                viewmodel.send_command(crate::viewmodel::ViewModelCommand::LoadCarouselPage {
-                    filter: self.carousel_filter.to_image_status(),
-                    page: 0,
-                }).ok();
+        if self.viewmodel.is_some() && !self.carousel_all_images.contains_key(&self.carousel_filter) && !self.carousel_loading {
+            log::info!("Loading initial carousel page");
+            self.load_carousel_page(0);
+        }
+
+        // Load collections and countries for the filter bar once
+        if self.collections.is_none() {
+            if let Some(ref viewmodel) = self.viewmodel {
+                self.collections = Some(Vec::new());
+                viewmodel.send_command(crate::viewmodel::ViewModelCommand::LoadCollections).ok();
+            }
+        }
+        if self.countries.is_none() {
+            if let Some(ref viewmodel) = self.viewmodel {
+                self.countries = Some(Vec::new());
+                viewmodel.send_command(crate::viewmodel::ViewModelCommand::LoadCountries).ok();
             }
         }
 
@@ -744,6 +892,8 @@ impl eframe::App for BingtrayApp {
 
         // Check for settings toggle
         if SETTINGS_TOGGLE.swap(false, Ordering::Relaxed) {
+            let interval = self.settings.rotation_schedule.interval_minutes;
+            self.dlg_settings.schedule_in_hours = interval == 0 || interval % 60 == 0;
             self.dlg_settings.open = true;
         }
 
@@ -752,6 +902,15 @@ impl eframe::App for BingtrayApp {
             self.dlg_about.open();
         }
 
+        // Check for search toggle (closing the search box shows all images again)
+        if SEARCH_TOGGLE.swap(false, Ordering::Relaxed) {
+            self.carousel_search_open = !self.carousel_search_open;
+            if !self.carousel_search_open {
+                self.carousel_search.clear();
+                self.apply_carousel_search();
+            }
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             let _theme = self.get_theme();
             self.ui(ui);
@@ -763,6 +922,19 @@ impl eframe::App for BingtrayApp {
         if self.dlg_settings.save_clicked {
             self.dlg_settings.save_clicked = false; // Reset flag after processing
             // TODO: save settings to file
+            if let Some(ref viewmodel) = self.viewmodel {
+                match crate::viewmodel::resolution::ResolutionSetting::from_str(&self.settings.image_resolution) {
+                    Some(setting) => {
+                        if let Err(e) = viewmodel.set_resolution_setting_sync(setting) {
+                            log::error!("Failed to save wallpaper resolution: {}", e);
+                        }
+                    }
+                    None => log::warn!("Unknown wallpaper resolution: {}", self.settings.image_resolution),
+                }
+                if let Err(e) = viewmodel.set_rotation_schedule_sync(&self.settings.rotation_schedule) {
+                    log::error!("Failed to save rotation schedule: {}", e);
+                }
+            }
             log::info!("Settings saved");
         }
         if let Some(theme_name) = self.dlg_settings.theme_to_apply.take() {
@@ -783,6 +955,15 @@ impl eframe::App for BingtrayApp {
         // Show install dialog (desktop only)
         #[cfg(not(target_os = "android"))]
         self.show_install_dialog(ctx);
+
+        self.show_history_dialog(ctx);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Stop at the next checkpoint, the import resumes on the next launch
+        if let Some(ref viewmodel) = self.viewmodel {
+            viewmodel.cancel_import();
+        }
     }
 }
 
@@ -825,6 +1006,11 @@ impl BingtrayApp {
         // Desktop-only menu actions (wallpaper operations)
         #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
         {
+            // The app was launched again while this window is open
+            if crate::control::take_open_gui_request() {
+                ui.ctx().send_viewport_cmd(egui::ViewportCommand::Focus);
+            }
+
             if MENU_CACHE_DIR.swap(false, Ordering::Relaxed) {
                 info!("Opening cache directory");
                 if let Err(e) = self.open_cache_directory() {
@@ -861,6 +1047,34 @@ impl BingtrayApp {
             }
         }
 
+        if MENU_PREVIOUS.swap(false, Ordering::Relaxed) {
+            info!("Setting previous wallpaper");
+            if let Some(ref viewmodel) = self.viewmodel {
+                viewmodel.send_command(crate::viewmodel::ViewModelCommand::SetPreviousWallpaper).ok();
+            }
+        }
+
+        if MENU_UNDO.swap(false, Ordering::Relaxed) {
+            info!("Undoing last status change");
+            if let Some(ref viewmodel) = self.viewmodel {
+                viewmodel.send_command(crate::viewmodel::ViewModelCommand::UndoLastAction).ok();
+            }
+        }
+
+        if MENU_REDO.swap(false, Ordering::Relaxed) {
+            info!("Redoing status change");
+            if let Some(ref viewmodel) = self.viewmodel {
+                viewmodel.send_command(crate::viewmodel::ViewModelCommand::Redo).ok();
+            }
+        }
+
+        if MENU_HISTORY.swap(false, Ordering::Relaxed) {
+            info!("Opening wallpaper history");
+            self.menu_open = false;
+            self.history_dialog_open = true;
+            self.load_history_page(0);
+        }
+
         #[cfg(not(target_os = "android"))]
         if MENU_INSTALL.swap(false, Ordering::Relaxed) {
             info!("Install/Uninstall action");
@@ -910,6 +1124,46 @@ impl BingtrayApp {
         // ==================== NEW CAROUSEL (ViewModel-driven) ====================
         ui.add_space(10.0);
 
+        // ==================== HISTORICAL IMPORT PROGRESS ====================
+        if let Some((current, total)) = self.import_progress {
+            ui.horizontal(|ui| {
+                ui.add_space(10.0);
+                ui.label(tr!("status-importing-history", { current: current, total: total }));
+                ui.add(egui::ProgressBar::new(current as f32 / total.max(1) as f32).desired_width(200.0));
+                if ui.button(tr!("cancel")).clicked() {
+                    if let Some(ref viewmodel) = self.viewmodel {
+                        viewmodel.cancel_import();
+                    }
+                }
+            });
+        }
+
+        // ==================== CAROUSEL SEARCH BOX ====================
+            if self.carousel_search_open {
+                ui.horizontal(|ui| {
+                    ui.add_space(10.0);
+                    ui.label(format!("{}", tr!("search")));
+
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.carousel_search)
+                            .hint_text(format!("{}", tr!("search-hint")))
+                            .desired_width(280.0),
+                    );
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        self.apply_carousel_search();
+                    }
+
+                    if ui.button(format!("{}", tr!("search"))).clicked() {
+                        self.apply_carousel_search();
+                    }
+                    if !self.carousel_search.is_empty() && ui.button("✕").clicked() {
+                        self.carousel_search.clear();
+                        self.apply_carousel_search();
+                    }
+                });
+                ui.add_space(5.0);
+            }
+
         // ==================== NEW CAROUSEL FILTER BAR ====================
             ui.horizontal(|ui| {
                 ui.add_space(10.0);
@@ -947,8 +1201,78 @@ impl BingtrayApp {
                     self.change_filter(CarouselFilter::Unprocessed);
                 }
 
+                // Collection filters
+                let collections = self.collections.clone().unwrap_or_default();
+                for summary in &collections {
+                    let filter = CarouselFilter::Collection(summary.collection.id);
+                    if ui.selectable_label(
+                        self.carousel_filter == filter,
+                        format!("{} ({})", summary.collection.name, summary.image_count)
+                    ).clicked() {
+                        self.change_filter(filter);
+                    }
+                }
+
+                // Country filter
+                let countries = self.countries.clone().unwrap_or_default();
+                if !countries.is_empty() {
+                    let selected = match self.carousel_filter {
+                        CarouselFilter::Country(code) => crate::location::country(code)
+                            .map_or_else(|| code.to_string(), |country| country.name.to_string()),
+                        _ => format!("{}", tr!("country-any")),
+                    };
+                    egui::ComboBox::from_id_salt("carousel_country_filter")
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            if ui.selectable_label(false, format!("{}", tr!("country-any"))).clicked() {
+                                self.change_filter(CarouselFilter::All);
+                            }
+                            for summary in &countries {
+                                let Some(country) = crate::location::country(&summary.code) else {
+                                    continue;
+                                };
+                                let filter = CarouselFilter::Country(country.code);
+                                if ui.selectable_label(
+                                    self.carousel_filter == filter,
+                                    format!("{} ({})", summary.name, summary.image_count)
+                                ).clicked() {
+                                    self.change_filter(filter);
+                                }
+                            }
+                        });
+                }
+
                 ui.add_space(10.0);
 
+                // Random favorite rotation can be limited to the selected countries
+                if let CarouselFilter::Country(code) = self.carousel_filter {
+                    let mut rotate = self.rotation_countries.iter().any(|country| country == code);
+                    if ui.checkbox(&mut rotate, format!("{}", tr!("country-rotate"))).changed() {
+                        let mut countries = self.rotation_countries.clone();
+                        countries.retain(|country| country != code);
+                        if rotate {
+                            countries.push(code.to_string());
+                        }
+                        if let Some(ref viewmodel) = self.viewmodel {
+                            viewmodel.send_command(crate::viewmodel::ViewModelCommand::SetRotationCountries {
+                                countries,
+                            }).ok();
+                        }
+                    }
+                }
+
+                // Random favorite rotation can be restricted to the selected collection
+                if let CarouselFilter::Collection(id) = self.carousel_filter {
+                    let mut rotate = self.rotation_collection_id == Some(id);
+                    if ui.checkbox(&mut rotate, format!("{}", tr!("collection-rotate"))).changed() {
+                        if let Some(ref viewmodel) = self.viewmodel {
+                            viewmodel.send_command(crate::viewmodel::ViewModelCommand::SetRotationCollection {
+                                collection_id: rotate.then_some(id),
+                            }).ok();
+                        }
+                    }
+                }
+
                 // Show count of loaded images for current filter
                 if let Some(images) = self.carousel_all_images.get(&self.carousel_filter) {
                     ui.label(format!("({} images)", images.len()));
@@ -958,6 +1282,12 @@ impl BingtrayApp {
                 if self.carousel_loading {
                     ui.spinner();
                 }
+
+                if let Some(query) = &self.carousel_search_query {
+                    if self.carousel_total_count == Some(0) && !self.carousel_loading {
+                        ui.label(format!("{}", tr!("search-no-results", { query: query.clone() })));
+                    }
+                }
             });
 
             ui.add_space(5.0);
@@ -1284,6 +1614,10 @@ impl BingtrayApp {
                         ui.label(&main_image.copyright);
                     }
 
+                    // Rating, tags and collections
+                    self.show_rating(ui, &main_image.base_url);
+                    self.show_image_groups(ui, &main_image.base_url);
+
                     // Wallpaper buttons
                     ui.horizontal(|ui| {
                         // Normal wallpaper button
@@ -1291,9 +1625,13 @@ impl BingtrayApp {
                             if let Some(bytes) = &main_image.image_bytes {
                                 let image_data = bytes.clone();
                                 let setter = self.wallpaper_setter.clone();
+                                let history_pool = self.history_pool();
+                                let url = main_image.base_url.clone();
                                 std::thread::spawn(move || {
                                     if let Some(setter) = setter {
-                                        let _ = setter.set_wallpaper_from_bytes(&image_data);
+                                        if let Ok(true) = setter.set_wallpaper_from_bytes(&image_data) {
+                                            record_gui_wallpaper(history_pool, &url, None);
+                                        }
                                     }
                                 });
                             }
@@ -1407,9 +1745,14 @@ impl BingtrayApp {
                                 };
 
                                 let setter = self.wallpaper_setter.clone();
+                                let history_pool = self.history_pool();
+                                let url = main_image.base_url.clone();
+                                let crop_coords = crop_coords_from_rect(bytes, crop_rect);
                                 std::thread::spawn(move || {
                                     if let Some(setter) = setter {
-                                        let _ = setter.set_wallpaper_from_bytes(&final_image_data);
+                                        if let Ok(true) = setter.set_wallpaper_from_bytes(&final_image_data) {
+                                            record_gui_wallpaper(history_pool, &url, crop_coords);
+                                        }
                                     }
                                 });
                             }
@@ -2212,6 +2555,8 @@ impl BingtrayApp {
                         if !bytes.is_empty() {
                             let image_data = bytes.clone();
                             let setter = self.wallpaper_setter.clone();
+                            let history_pool = self.history_pool();
+                            let url = main_image.base_url.clone();
                             info!("Starting wallpaper setting with {} bytes", image_data.len());
                             // Start wallpaper setting in background thread using bytes directly
                             std::thread::spawn(move || {
@@ -2224,6 +2569,7 @@ impl BingtrayApp {
                                 match result {
                                     Ok(true) => {
                                         log::info!("BingtrayApp: Wallpaper setting from bytes completed successfully");
+                                        record_gui_wallpaper(history_pool, &url, None);
                                     }
                                     Ok(false) => {
                                         log::error!("BingtrayApp: Wallpaper setting from bytes failed");
@@ -2464,6 +2810,10 @@ impl BingtrayApp {
                                 image_data
                             };
                             
+                            let history_pool = self.history_pool();
+                            let url = main_image.base_url.clone();
+                            let crop_coords = crop_coords_from_rect(bytes, crop_rect);
+
                             // Start wallpaper setting in background thread using pre-cropped bytes
                             std::thread::spawn(move || {
                                 log::info!("BingtrayApp: Starting wallpaper setting with pre-cropped image bytes");
@@ -2475,6 +2825,7 @@ impl BingtrayApp {
                                 match result {
                                     Ok(true) => {
                                         log::info!("BingtrayApp: Cropped wallpaper setting completed successfully");
+                                        record_gui_wallpaper(history_pool, &url, crop_coords);
                                     }
                                     Ok(false) => {
                                         log::error!("BingtrayApp: Cropped wallpaper setting failed");
@@ -2587,6 +2938,14 @@ impl BingtrayApp {
         } // end if !use_new_carousel
 
         // Display user mismatch warning (persistent)
+        if let Some(error) = &self.database_error {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.colored_label(ui.visuals().error_fg_color, "⚠ ");
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            });
+        }
+
         if let Some(warning) = &self.user_mismatch_warning {
             ui.separator();
             ui.horizontal(|ui| {
@@ -2716,19 +3075,16 @@ impl BingtrayApp {
         ctx.set_visuals(visuals);
     }
 
+    // Helper: pool for recording wallpapers set from the main panel
+    fn history_pool(&self) -> Option<crate::db::DbPool> {
+        self.viewmodel.as_ref().map(|viewmodel| viewmodel.pool().clone())
+    }
+
     // Helper to get database connection
     #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
-    fn get_db_connection(&self) -> anyhow::Result<diesel::SqliteConnection> {
-        use diesel::Connection;
+    fn get_db_connection(&self) -> anyhow::Result<crate::db::PooledConnection> {
         let db_path = crate::db::get_database_path()?;
-        let mut conn = diesel::SqliteConnection::establish(&db_path.to_string_lossy())?;
-
-        // Run migrations
-        use diesel_migrations::MigrationHarness;
-        conn.run_pending_migrations(crate::db::MIGRATIONS)
-            .map_err(|e| anyhow::anyhow!("Migration failed: {}", e))?;
-
-        Ok(conn)
+        crate::db::DbPool::shared(&db_path)?.get()
     }
 
     // Menu action: Open cache directory
@@ -2768,18 +3124,12 @@ impl BingtrayApp {
         use crate::viewmodel::commands::download_and_set_next_wallpaper_sync;
 
         let mut conn = self.get_db_connection()?;
-        let result = download_and_set_next_wallpaper_sync(&mut conn)?;
+        let result = download_and_set_next_wallpaper_sync(&mut conn, crate::db::WallpaperSource::Gui)?;
         info!("Set next wallpaper: {}", result.title);
 
         // Reload carousel to show newly downloaded images
-        if let Some(ref viewmodel) = self.viewmodel {
-            self.carousel_pages.clear();
-            self.carousel_loading = true;
-            viewmodel.send_command(crate::viewmodel::ViewModelCommand::LoadCarouselPage {
-                filter: self.carousel_filter.to_image_status(),
-                page: 0,
-            }).ok();
-        }
+        self.carousel_pages.clear();
+        self.load_carousel_page(0);
 
         Ok(())
     }
@@ -2790,7 +3140,7 @@ impl BingtrayApp {
         use crate::viewmodel::commands::keep_current_wallpaper_sync;
 
         let mut conn = self.get_db_connection()?;
-        if let Some(title) = keep_current_wallpaper_sync(&mut conn)? {
+        if let Some(title) = keep_current_wallpaper_sync(&mut conn, crate::db::WallpaperSource::Gui)? {
             info!("Kept current wallpaper: {}", title);
             Ok(())
         } else {
@@ -2804,7 +3154,7 @@ impl BingtrayApp {
         use crate::viewmodel::commands::blacklist_current_wallpaper_sync;
 
         let mut conn = self.get_db_connection()?;
-        if let Some(title) = blacklist_current_wallpaper_sync(&mut conn)? {
+        if let Some(title) = blacklist_current_wallpaper_sync(&mut conn, crate::db::WallpaperSource::Gui)? {
             info!("Blacklisted current wallpaper: {}", title);
             Ok(())
         } else {
@@ -2818,7 +3168,7 @@ impl BingtrayApp {
         use crate::viewmodel::commands::set_random_favorite_wallpaper_sync;
 
         let mut conn = self.get_db_connection()?;
-        if let Some(title) = set_random_favorite_wallpaper_sync(&mut conn)? {
+        if let Some(title) = set_random_favorite_wallpaper_sync(&mut conn, crate::db::WallpaperSource::Gui)? {
             info!("Set random favorite: {}", title);
             Ok(())
         } else {
@@ -2871,6 +3221,14 @@ impl BingtrayApp {
 
         log::info!("Changing filter from {:?} to {:?}", self.carousel_filter, new_filter);
 
+        // Collections and countries are not searched, selecting one ends the search
+        if !new_filter.is_searchable() && self.carousel_search_query.is_some() {
+            self.carousel_filter = new_filter;
+            self.carousel_search.clear();
+            self.apply_carousel_search();
+            return;
+        }
+
         // Save current scroll position for old filter
         self.carousel_scroll_positions.insert(
             self.carousel_filter,
@@ -2904,6 +3262,209 @@ impl BingtrayApp {
         }
     }
 
+    /// Star rating of the main panel image; clicking the current rating clears it
+    fn show_rating(&mut self, ui: &mut egui::Ui, url: &str) {
+        use crate::viewmodel::ViewModelCommand;
+
+        let Some(ref viewmodel) = self.viewmodel else {
+            return;
+        };
+        if self.rating_url.as_deref() != Some(url) {
+            self.rating_url = Some(url.to_string());
+            self.rating = None;
+            viewmodel.send_command(ViewModelCommand::LoadRating { url: url.to_string() }).ok();
+        }
+        let rating = self.rating.unwrap_or(0);
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{}:", tr!("rating")));
+            for stars in 1..=crate::db::models::MAX_RATING {
+                let star = if stars <= rating { "★" } else { "☆" };
+                if ui.small_button(star).clicked() {
+                    let rating = if stars == rating { 0 } else { stars };
+                    viewmodel.send_command(ViewModelCommand::SetRating { url: url.to_string(), rating }).ok();
+                }
+            }
+        });
+    }
+
+    /// Tags and collections of the main panel image, with inputs to change them
+    fn show_image_groups(&mut self, ui: &mut egui::Ui, url: &str) {
+        use crate::viewmodel::ViewModelCommand;
+
+        if self.viewmodel.is_none() {
+            return;
+        }
+        let mut commands = Vec::new();
+        if self.image_groups_url.as_deref() != Some(url) {
+            self.image_groups_url = Some(url.to_string());
+            self.image_groups = None;
+            commands.push(ViewModelCommand::LoadImageGroups { url: url.to_string() });
+        }
+        let groups = self.image_groups.clone().unwrap_or_default();
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label(format!("{}:", tr!("tags")));
+            for tag in &groups.tags {
+                if ui.small_button(format!("#{} ✕", tag.name)).clicked() {
+                    commands.push(ViewModelCommand::UntagImage { url: url.to_string(), tag_id: tag.id });
+                }
+            }
+
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.new_tag)
+                    .hint_text(format!("{}", tr!("tag-hint")))
+                    .desired_width(100.0),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if (submitted || ui.small_button("+").clicked()) && !self.new_tag.trim().is_empty() {
+                let tag = std::mem::take(&mut self.new_tag);
+                commands.push(ViewModelCommand::TagImage { url: url.to_string(), tag });
+            }
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label(format!("{}:", tr!("collections")));
+            for collection in &groups.collections {
+                if ui.small_button(format!("{} ✕", collection.name)).clicked() {
+                    commands.push(ViewModelCommand::RemoveFromCollection {
+                        url: url.to_string(),
+                        collection_id: collection.id,
+                    });
+                }
+            }
+
+            ui.menu_button(format!("{}", tr!("collection-add")), |ui| {
+                for summary in self.collections.iter().flatten() {
+                    if groups.collections.iter().any(|collection| collection.id == summary.collection.id) {
+                        continue;
+                    }
+                    if ui.button(&summary.collection.name).clicked() {
+                        commands.push(ViewModelCommand::AddToCollection {
+                            url: url.to_string(),
+                            collection_id: summary.collection.id,
+                        });
+                        ui.close();
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_collection)
+                            .hint_text(format!("{}", tr!("collection-new-hint")))
+                            .desired_width(120.0),
+                    );
+                    if ui.button(format!("{}", tr!("collection-create"))).clicked() && !self.new_collection.trim().is_empty() {
+                        let name = std::mem::take(&mut self.new_collection);
+                        commands.push(ViewModelCommand::CreateCollection { name });
+                    }
+                });
+            });
+        });
+
+        if let Some(ref viewmodel) = self.viewmodel {
+            for command in commands {
+                viewmodel.send_command(command).ok();
+            }
+        }
+    }
+
+    /// Show the results of the search box text in the carousel (all images when empty)
+    fn apply_carousel_search(&mut self) {
+        let query = Some(self.carousel_search.trim().to_string()).filter(|query| !query.is_empty());
+        if query == self.carousel_search_query {
+            return;
+        }
+        log::info!("Carousel search changed to {:?}", query);
+        self.carousel_search_query = query;
+
+        // Search covers all images, not one collection or country
+        if self.carousel_search_query.is_some() && !self.carousel_filter.is_searchable() {
+            self.carousel_filter = CarouselFilter::All;
+        }
+
+        // Every cached page belongs to the previous search
+        self.reset_carousel();
+    }
+
+    /// Drop every loaded carousel page and load the first one again
+    fn reset_carousel(&mut self) {
+        self.carousel_all_images.clear();
+        self.carousel_pages.clear();
+        self.carousel_total_count = None;
+        self.carousel_loaded_images.clear();
+        self.carousel_next_batch_index = 0;
+        self.carousel_last_scroll_center = 0;
+        self.carousel_scroll_offset = 0.0;
+        self.carousel_loading = false;
+        self.load_carousel_page(0);
+    }
+
+    /// Add a loaded carousel (or search) page, then load the next one until all are loaded
+    fn append_carousel_page(&mut self, page: usize, images: Vec<crate::db::BingImage>, total_count: usize) {
+        // Convert BingImage to CarouselImage with proper thumbnail URLs
+        let carousel_images: Vec<CarouselImage> = images.into_iter()
+            .map(|img| {
+                // Generate base URL (original database URL)
+                let base_url = if img.url.starts_with("http") || crate::viewmodel::local_source::is_local_url(&img.url) {
+                    img.url.clone()
+                } else {
+                    format!("https://bing.com{}", img.url)
+                };
+
+                // Create thumbnail and full URLs with size parameters
+                // (local files have no resizing endpoint and are loaded as-is)
+                let (thumbnail_url, full_url) = if crate::viewmodel::local_source::is_local_url(&base_url) {
+                    (base_url.clone(), base_url.clone())
+                } else {
+                    let separator = if base_url.contains('?') { "&" } else { "?" };
+                    (
+                        format!("{}{}w=320&h=240", base_url, separator),
+                        format!("{}{}w=1920&h=1080", base_url, separator),
+                    )
+                };
+
+                CarouselImage {
+                    title: img.title.clone(),
+                    copyright: img.copyright.clone().unwrap_or_default(),
+                    copyright_link: img.copyright_link.clone().unwrap_or_default(),
+                    base_url,  // Store original URL
+                    thumbnail_url,
+                    full_url,
+                    image_bytes: None,  // Loaded on demand
+                    status: Some(img.status),
+                }
+            })
+            .collect();
+
+        // For progressive loading, append to all_images instead of page cache
+        self.carousel_all_images
+            .entry(self.carousel_filter)
+            .or_insert_with(Vec::new)
+            .extend(carousel_images);
+
+        self.carousel_total_count = Some(total_count);
+
+        // Check if we need to load more pages
+        let current_loaded = self.carousel_all_images
+            .get(&self.carousel_filter)
+            .map(|v| v.len())
+            .unwrap_or(0);
+
+        if current_loaded < total_count {
+            // Load next page
+            let next_page = page + 1;
+            log::info!("Auto-loading next page {}: {}/{} images loaded",
+                      next_page, current_loaded, total_count);
+            self.load_carousel_page(next_page);
+        } else {
+            log::info!("All {} images loaded for filter {:?}",
+                      current_loaded, self.carousel_filter);
+            self.carousel_loading = false;
+        }
+    }
+
     /// Load a carousel page via ViewModel
     fn load_carousel_page(&mut self, page: usize) {
         if let Some(ref viewmodel) = self.viewmodel {
@@ -2913,10 +3474,25 @@ impl BingtrayApp {
             self.carousel_current_page = page;
 
             let filter_status = self.carousel_filter.to_image_status();
-            viewmodel.send_command(crate::viewmodel::ViewModelCommand::LoadCarouselPage {
-                filter: filter_status,
-                page,
-            }).ok();
+            let command = match (&self.carousel_search_query, self.carousel_filter) {
+                (Some(query), _) => crate::viewmodel::ViewModelCommand::Search {
+                    query: query.clone(),
+                    filter: filter_status,
+                    page,
+                },
+                (None, CarouselFilter::Collection(collection_id)) => {
+                    crate::viewmodel::ViewModelCommand::LoadCollectionPage { collection_id, page }
+                }
+                (None, CarouselFilter::Country(country)) => crate::viewmodel::ViewModelCommand::LoadCountryPage {
+                    country: country.to_string(),
+                    page,
+                },
+                (None, _) => crate::viewmodel::ViewModelCommand::LoadCarouselPage {
+                    filter: filter_status,
+                    page,
+                },
+            };
+            viewmodel.send_command(command).ok();
         }
     }
 }
@@ -2951,6 +3527,43 @@ fn open_directory(path: &std::path::Path) -> Result<(), String> {
     Ok(())
 }
 
+/// Normalized crop of a pixel rectangle (left, top, right, bottom) in encoded image bytes
+fn crop_coords_from_rect(bytes: &[u8], rect: Option<(i32, i32, i32, i32)>) -> Option<crate::viewmodel::CropCoords> {
+    let (left, top, right, bottom) = rect?;
+    let (width, height) = image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+    let (width, height) = (width as f32, height as f32);
+
+    Some(crate::viewmodel::CropCoords {
+        x: left as f32 / width,
+        y: top as f32 / height,
+        width: (right - left) as f32 / width,
+        height: (bottom - top) as f32 / height,
+    }.clamp())
+}
+
+/// Record a wallpaper set from the main panel in the wallpaper history
+fn record_gui_wallpaper(
+    pool: Option<crate::db::DbPool>,
+    url: &str,
+    crop: Option<crate::viewmodel::CropCoords>,
+) {
+    use crate::viewmodel::history::record_wallpaper_set_sync;
+
+    let Some(pool) = pool else {
+        return;
+    };
+    let result = pool.get().and_then(|mut conn| {
+        record_wallpaper_set_sync(&mut conn, url, crate::db::WallpaperSource::Gui, None, crop.as_ref())
+    });
+    if let Err(e) = result {
+        log::warn!("Failed to record wallpaper in history: {}", e);
+    }
+}
+
 fn ui_mainpanel(
     ui: &mut egui::Ui,
     menu_anchor_rect: &mut Option<Rect>,
@@ -2967,9 +3580,9 @@ fn ui_mainpanel(
         .navigation_icon_char(ICON_MENU, || {
             MENU_TOGGLE.store(true, Ordering::Relaxed);
         })
-        // .action_icon_char(ICON_SEARCH, || {
-        //     SEARCH_TOGGLE.store(true, Ordering::Relaxed);
-        // })
+        .action_icon_char(ICON_SEARCH, || {
+            SEARCH_TOGGLE.store(true, Ordering::Relaxed);
+        })
         // .action_icon_char(ICON_NOTIFICATIONS, || println!("Interactive: Notifications"))
         // .action_icon_char(ICON_ACCOUNT_CIRCLE, || println!("Interactive: Account"))
         .action_icon_char(ICON_INFO, || {
@@ -3070,6 +3683,10 @@ impl BingtrayApp {
     }
 
     pub fn set_screen_size_provider(&mut self, provider: Arc<dyn ScreenSizeProvider + Send + Sync>) {
+        // Downloads pick the image variant that fits this screen
+        if let Ok((width, height)) = provider.get_screen_size() {
+            crate::viewmodel::resolution::set_screen_size(width, height);
+        }
         self.screen_size_provider = Some(provider);
         // Clear cached screen size to force recalculation with new provider
         self.cached_screen_size = None;
@@ -3256,6 +3873,30 @@ impl BingtrayApp {
                 .enabled(false)
         };
 
+        let previous_item = menu_item(&format!("{}", tr!("tray-previous")))
+            .leading_icon("skip_previous")
+            .on_click(|| {
+                MENU_PREVIOUS.store(true, Ordering::Relaxed);
+            });
+
+        let history_item = menu_item(&format!("{}", tr!("tray-history")))
+            .leading_icon("history")
+            .on_click(|| {
+                MENU_HISTORY.store(true, Ordering::Relaxed);
+            });
+
+        let undo_item = menu_item(&format!("{}", tr!("tray-undo")))
+            .leading_icon("undo")
+            .on_click(|| {
+                MENU_UNDO.store(true, Ordering::Relaxed);
+            });
+
+        let redo_item = menu_item(&format!("{}", tr!("tray-redo")))
+            .leading_icon("redo")
+            .on_click(|| {
+                MENU_REDO.store(true, Ordering::Relaxed);
+            });
+
         // Install/Uninstall menu item (desktop only)
         #[cfg(not(target_os = "android"))]
         let install_text = if self.install_status == crate::install_stt::InstallStatus::Installed {
@@ -3282,7 +3923,11 @@ impl BingtrayApp {
             .item(current_title_item) // Current wallpaper title
             .item(keep_current_item)
             .item(blacklist_current_item)
-            .item(random_favorite_item);
+            .item(random_favorite_item)
+            .item(previous_item)
+            .item(history_item)
+            .item(undo_item)
+            .item(redo_item);
 
         // Add install item for desktop only
         #[cfg(not(target_os = "android"))]
@@ -3808,7 +4453,7 @@ impl BingtrayApp {
                     } else {
                         // Fallback: create from file if no matching carousel image found
                         info!("No matching carousel image found for '{}', using file:// URL", filename_stem);
-                        let file_url = format!("file://{}", current_path.display());
+                        let file_url = crate::viewmodel::local_source::file_url(&current_path);
                         CarouselImage {
                             title: title.clone(),
                             copyright: String::new(),
@@ -4077,6 +4722,98 @@ impl BingtrayApp {
         }
     }
 
+    /// Request a page of the wallpaper history from the ViewModel
+    fn load_history_page(&mut self, page: usize) {
+        if let Some(ref viewmodel) = self.viewmodel {
+            viewmodel.send_command(crate::viewmodel::ViewModelCommand::LoadHistoryPage { page }).ok();
+        }
+    }
+
+    /// Show the wallpaper history dialog
+    fn show_history_dialog(&mut self, ctx: &egui::Context) {
+        use crate::viewmodel::history::{format_duration, HISTORY_PAGE_SIZE};
+
+        if !self.history_dialog_open {
+            return;
+        }
+
+        let mut close_clicked = false;
+        let mut previous_clicked = false;
+        let mut load_page = None;
+        let mut set_url = None;
+
+        egui::Window::new(tr!("history-title"))
+            .id(egui::Id::new("history_dialog"))
+            .collapsible(false)
+            .resizable(true)
+            .show(ctx, |ui| {
+                match &self.history {
+                    None => {
+                        ui.spinner();
+                    }
+                    Some(history) if history.items.is_empty() => {
+                        ui.label(tr!("history-empty"));
+                    }
+                    Some(history) => {
+                        egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                            egui::Grid::new("history_grid").striped(true).show(ui, |ui| {
+                                for item in &history.items {
+                                    let set_at = chrono::DateTime::from_timestamp(item.entry.set_at as i64, 0)
+                                        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                                        .unwrap_or_default();
+                                    ui.label(set_at);
+                                    ui.label(item.title.as_deref().unwrap_or(&item.entry.url));
+                                    ui.label(&item.entry.source);
+                                    ui.label(tr!("history-shown-for", { duration: format_duration(item.shown_secs) }));
+                                    if ui.button(tr!("history-set-again")).clicked() {
+                                        set_url = Some(item.entry.url.clone());
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                        });
+
+                        let pages = history.total.div_ceil(HISTORY_PAGE_SIZE).max(1);
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(history.page > 0, egui::Button::new("◀")).clicked() {
+                                load_page = Some(history.page - 1);
+                            }
+                            ui.label(format!("{} / {}", history.page + 1, pages));
+                            if ui.add_enabled(history.page + 1 < pages, egui::Button::new("▶")).clicked() {
+                                load_page = Some(history.page + 1);
+                            }
+                        });
+                    }
+                }
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("tray-previous")).clicked() {
+                        previous_clicked = true;
+                    }
+                    if ui.button(tr!("close")).clicked() {
+                        close_clicked = true;
+                    }
+                });
+            });
+
+        if let Some(ref viewmodel) = self.viewmodel {
+            if previous_clicked {
+                viewmodel.send_command(crate::viewmodel::ViewModelCommand::SetPreviousWallpaper).ok();
+            }
+            if let Some(url) = set_url {
+                viewmodel.send_command(crate::viewmodel::ViewModelCommand::SetWallpaper { url }).ok();
+            }
+        }
+        if let Some(page) = load_page {
+            self.load_history_page(page);
+        }
+        if close_clicked {
+            self.history_dialog_open = false;
+            self.history = None;
+        }
+    }
+
     /// Perform install or uninstall action based on current status
     #[cfg(not(target_os = "android"))]
     fn perform_install_action(&mut self) {
@@ -6,25 +6,15 @@
 //! is based on current wallpaper image on desktop.
 //!
 
+use crate::control::ControlCommand;
 use crate::viewmodel::ViewModel;
-use anyhow::{Context, Result};
+use anyhow::Result;
 use std::io::{self, Write};
 
 /// Run the CLI mode with a REPL loop
 pub fn run_cli_mode() -> Result<()> {
-    // Get platform-specific config directory
-    // Linux: ~/.config/bingtray/bingtray.db
-    // Windows: %APPDATA%\bingtray\bingtray.db
-    // macOS: ~/Library/Application Support/bingtray/bingtray.db
-    let db_path = dirs::config_dir()
-        .context("Could not determine config directory")?
-        .join("bingtray")
-        .join("bingtray.db");
-
-    // Create config directory if it doesn't exist
-    if let Some(parent) = db_path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
+    // Same database as the GUI and tray (honours --data-dir, BINGTRAY_HOME and portable mode)
+    let db_path = crate::db::get_database_path()?;
 
     let viewmodel = ViewModel::new_sync(db_path)?;
 
@@ -35,9 +25,11 @@ pub fn run_cli_mode() -> Result<()> {
     println!("═══════════════════════════════════════════════════════════");
     println!();
 
+    import_legacy_layout(&viewmodel);
+
     loop {
         // Display menu
-        print_menu();
+        print_menu(&viewmodel);
 
         // Read user input
         print!("\nEnter your choice: ");
@@ -70,7 +62,31 @@ pub fn run_cli_mode() -> Result<()> {
                 // Set Random Favorite
                 handle_set_random_favorite(&viewmodel)?;
             }
-            "5" | "q" | "quit" | "exit" => {
+            "5" => {
+                // Previous Wallpaper
+                handle_set_previous_wallpaper(&viewmodel)?;
+            }
+            "6" => {
+                // Wallpaper History
+                handle_show_history(&viewmodel)?;
+            }
+            "7" => {
+                // Undo Last Keep/Blacklist
+                handle_undo(&viewmodel)?;
+            }
+            "8" => {
+                // Redo Keep/Blacklist
+                handle_redo(&viewmodel)?;
+            }
+            "9" => {
+                // Search Images
+                handle_search(&viewmodel)?;
+            }
+            "10" => {
+                // Rate Current Wallpaper
+                handle_rate_current_wallpaper(&viewmodel)?;
+            }
+            "11" | "q" | "quit" | "exit" => {
                 println!("\nGoodbye!");
                 break;
             }
@@ -79,7 +95,7 @@ pub fn run_cli_mode() -> Result<()> {
                 continue;
             }
             _ => {
-                println!("⚠ Invalid choice. Please enter 0-5.");
+                println!("⚠ Invalid choice. Please enter 0-11.");
             }
         }
 
@@ -90,9 +106,9 @@ pub fn run_cli_mode() -> Result<()> {
 }
 
 /// Print the menu
-fn print_menu() {
+fn print_menu(viewmodel: &ViewModel) {
     // Get unprocessed count for status display
-    let unprocessed_count = get_unprocessed_count().unwrap_or(0);
+    let unprocessed_count = get_unprocessed_count(viewmodel).unwrap_or(0);
     let status = if unprocessed_count > 0 {
         format!(" ({} available)", unprocessed_count)
     } else {
@@ -106,27 +122,25 @@ fn print_menu() {
     println!("  2. Keep Current Wallpaper");
     println!("  3. Blacklist Current Wallpaper");
     println!("  4. Set Random Favorite");
-    println!("  5. Exit");
+    println!("  5. Previous Wallpaper");
+    println!("  6. Wallpaper History");
+    println!("  7. Undo Last Keep/Blacklist");
+    println!("  8. Redo Keep/Blacklist");
+    println!("  9. Search Images");
+    println!("  10. Rate Current Wallpaper");
+    println!("  11. Exit");
     println!("═══════════════════════════════════════════════════════════");
 }
 
 /// Get count of unprocessed images
-fn get_unprocessed_count() -> Result<i64> {
-    use diesel::prelude::*;
-    let db_path = dirs::config_dir()
-        .context("Could not determine config directory")?
-        .join("bingtray")
-        .join("bingtray.db");
-
-    let mut conn = diesel::SqliteConnection::establish(&db_path.to_string_lossy())?;
+fn get_unprocessed_count(viewmodel: &ViewModel) -> Result<i64> {
+    let mut conn = viewmodel.db_connection()?;
     crate::db::operations::count_by_status(&mut conn, crate::db::ImageStatus::Unprocessed)
 }
 
 /// Handle option 0: Open Cache Directory
 fn handle_open_cache_directory() -> Result<()> {
-    let cache_dir = dirs::cache_dir()
-        .context("Could not determine cache directory")?
-        .join("bingtray");
+    let cache_dir = crate::paths::data_paths()?.cache_dir;
 
     // Create cache directory if it doesn't exist
     std::fs::create_dir_all(&cache_dir)?;
@@ -153,20 +167,53 @@ fn handle_open_cache_directory() -> Result<()> {
 /// Handle option 1: Download & Set Next Wallpaper
 fn handle_download_and_set_next(viewmodel: &ViewModel) -> Result<()> {
     println!("⏳ Downloading and setting wallpaper...");
-    match viewmodel.download_and_set_next_wallpaper_sync() {
-        Ok(result) => {
+    let result = forward_or(ControlCommand::Next, || {
+        let result = viewmodel.download_and_set_next_wallpaper_sync()?;
+        // The first download fills the database older files are matched against
+        import_legacy_layout(viewmodel);
+        Ok(Some(result.title))
+    });
+    match result {
+        Ok(title) => {
             println!("✓ Wallpaper set successfully!");
-            println!("  Title: {}", result.title);
+            println!("  Title: {}", title.unwrap_or_default());
         }
         Err(e) => println!("✗ Error: {}", e),
     }
     Ok(())
 }
 
+/// Run a command in the running tray or daemon if there is one, here otherwise
+/// (so both do not change the wallpaper at the same time)
+fn forward_or(command: ControlCommand, local: impl FnOnce() -> Result<Option<String>>) -> Result<Option<String>> {
+    match crate::control::send(command) {
+        Some(result) => {
+            println!("  (Done by the running Bingtray)");
+            result
+        }
+        None => local(),
+    }
+}
+
+/// Import favorites, blacklist and markets of older releases (once)
+fn import_legacy_layout(viewmodel: &ViewModel) {
+    match viewmodel.import_legacy_layout_once() {
+        Ok(Some(report)) => {
+            println!("✓ Imported files of an older version: {}", report.summary());
+            for unmatched in &report.unmatched {
+                println!("  Not matched: {}", unmatched);
+            }
+            println!();
+        }
+        Ok(None) => {}
+        Err(e) => println!("✗ Failed to import files of an older version: {}\n", e),
+    }
+}
+
 /// Handle option 2: Keep Current Wallpaper
 fn handle_keep_current_wallpaper(viewmodel: &ViewModel) -> Result<()> {
     println!("⏳ Marking current wallpaper as favorite...");
-    match viewmodel.keep_current_wallpaper_sync() {
+    match forward_or(ControlCommand::Keep, || viewmodel.keep_current_wallpaper_sync()) {
         Ok(Some(title)) => {
             println!("✓ Kept: \"{}\"", title);
         }
@@ -182,7 +229,7 @@ fn handle_keep_current_wallpaper(viewmodel: &ViewModel) -> Result<()> {
 /// Handle option 3: Blacklist Current Wallpaper
 fn handle_blacklist_current_wallpaper(viewmodel: &ViewModel) -> Result<()> {
     println!("⏳ Blacklisting current wallpaper...");
-    match viewmodel.blacklist_current_wallpaper_sync() {
+    match forward_or(ControlCommand::Blacklist, || viewmodel.blacklist_current_wallpaper_sync()) {
         Ok(Some(title)) => {
             println!("✓ Blacklisted: \"{}\"", title);
         }
@@ -198,7 +245,7 @@ fn handle_blacklist_current_wallpaper(viewmodel: &ViewModel) -> Result<()> {
 /// Handle option 4: Set Random Favorite
 fn handle_set_random_favorite(viewmodel: &ViewModel) -> Result<()> {
     println!("⏳ Setting random favorite wallpaper...");
-    match viewmodel.set_random_favorite_wallpaper_sync() {
+    match forward_or(ControlCommand::RandomFavorite, || viewmodel.set_random_favorite_wallpaper_sync()) {
         Ok(Some(title)) => {
             println!("✓ Set favorite: \"{}\"", title);
         }
@@ -210,3 +257,182 @@ fn handle_set_random_favorite(viewmodel: &ViewModel) -> Result<()> {
     }
     Ok(())
 }
+
+/// Handle option 5: Previous Wallpaper
+fn handle_set_previous_wallpaper(viewmodel: &ViewModel) -> Result<()> {
+    println!("⏳ Going back to the previous wallpaper...");
+    let result = forward_or(ControlCommand::Previous, || {
+        Ok(viewmodel.set_previous_wallpaper_sync()?.map(|result| result.title))
+    });
+    match result {
+        Ok(Some(title)) => {
+            println!("✓ Wallpaper set successfully!");
+            println!("  Title: {}", title);
+        }
+        Ok(None) => println!("⚠ No earlier wallpaper in history"),
+        Err(e) => println!("✗ Error: {}", e),
+    }
+    Ok(())
+}
+
+/// Handle option 6: Wallpaper History (n = older, p = newer, Enter = back)
+fn handle_show_history(viewmodel: &ViewModel) -> Result<()> {
+    use crate::viewmodel::history::{format_duration, HISTORY_PAGE_SIZE};
+
+    let mut page = 0;
+    loop {
+        let history = viewmodel.get_history_page_sync(page)?;
+        if history.items.is_empty() {
+            println!("⚠ No wallpapers set yet");
+            return Ok(());
+        }
+
+        println!();
+        for item in &history.items {
+            let set_at = chrono::DateTime::from_timestamp(item.entry.set_at as i64, 0)
+                .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            println!(
+                "  {}  {:<9} {:>8}  {}",
+                set_at,
+                item.entry.source,
+                format_duration(item.shown_secs),
+                item.title.as_deref().unwrap_or(&item.entry.url)
+            );
+        }
+
+        let pages = history.total.div_ceil(HISTORY_PAGE_SIZE);
+        print!("\nPage {}/{} - [n]ext (older), [p]revious (newer), Enter to return: ", page + 1, pages);
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        match input.trim() {
+            "n" if page + 1 < pages => page += 1,
+            "p" if page > 0 => page -= 1,
+            "n" | "p" => {}
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// Handle option 7: Undo Last Keep/Blacklist
+fn handle_undo(viewmodel: &ViewModel) -> Result<()> {
+    println!("⏳ Undoing the last status change...");
+    match viewmodel.undo_last_action_sync() {
+        Ok(Some(outcome)) => print_undo_outcome(&outcome),
+        Ok(None) => println!("⚠ Nothing to undo"),
+        Err(e) => println!("✗ Error: {}", e),
+    }
+    Ok(())
+}
+
+/// Handle option 8: Redo Keep/Blacklist
+fn handle_redo(viewmodel: &ViewModel) -> Result<()> {
+    println!("⏳ Redoing the last undone status change...");
+    match viewmodel.redo_sync() {
+        Ok(Some(outcome)) => print_undo_outcome(&outcome),
+        Ok(None) => println!("⚠ Nothing to redo"),
+        Err(e) => println!("✗ Error: {}", e),
+    }
+    Ok(())
+}
+
+fn print_undo_outcome(outcome: &crate::viewmodel::undo::UndoOutcome) {
+    println!("✓ \"{}\" is {} again", outcome.title, outcome.status.as_str());
+    if let Some(wallpaper) = &outcome.wallpaper {
+        println!("  Wallpaper: {}", wallpaper.title);
+    }
+}
+
+/// Handle option 9: Search Images (n = next page, p = previous page, number = set as wallpaper)
+fn handle_search(viewmodel: &ViewModel) -> Result<()> {
+    print!("Search titles, places and photographers: ");
+    io::stdout().flush()?;
+
+    let mut query = String::new();
+    io::stdin().read_line(&mut query)?;
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(());
+    }
+
+    let mut page = 0;
+    loop {
+        let results = viewmodel.search_sync(query, None, page)?;
+        if results.images.is_empty() {
+            println!("⚠ No images match \"{}\"", query);
+            return Ok(());
+        }
+
+        println!();
+        for (index, image) in results.images.iter().enumerate() {
+            println!("  {:>2}. [{}] {}", index + 1, image.status, image.title);
+            if let Some(copyright) = &image.copyright {
+                println!("      {}", copyright);
+            }
+        }
+
+        let pages = results.page_count();
+        print!(
+            "\nPage {}/{} ({} results) - [n]ext, [p]revious, number to set as wallpaper, Enter to return: ",
+            page + 1,
+            pages,
+            results.total_count
+        );
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        match input.trim() {
+            "n" if page + 1 < pages => page += 1,
+            "p" if page > 0 => page -= 1,
+            "n" | "p" => {}
+            choice => {
+                let Some(image) = choice
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|number| results.images.get(number.wrapping_sub(1)))
+                else {
+                    return Ok(());
+                };
+
+                println!("⏳ Setting wallpaper...");
+                match viewmodel.set_wallpaper_sync(&image.url) {
+                    Ok(true) => println!("✓ Wallpaper set: \"{}\"", image.title),
+                    Ok(false) => println!("✗ Failed to set wallpaper"),
+                    Err(e) => println!("✗ Error: {}", e),
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Handle option 10: Rate Current Wallpaper
+fn handle_rate_current_wallpaper(viewmodel: &ViewModel) -> Result<()> {
+    use crate::db::models::{rating_stars, MAX_RATING};
+
+    let Some(current) = viewmodel.get_current_wallpaper_rating_sync()? else {
+        println!("⚠ No matching wallpaper found in database");
+        println!("  (Current wallpaper may not be from BingTray)");
+        return Ok(());
+    };
+
+    print!("Current rating {} - new rating (0-{}, 0 clears): ", rating_stars(current), MAX_RATING);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let Some(rating) = input.trim().parse::<i32>().ok().filter(|rating| (0..=MAX_RATING).contains(rating)) else {
+        println!("⚠ Rating must be a number from 0 to {}", MAX_RATING);
+        return Ok(());
+    };
+
+    match viewmodel.rate_current_wallpaper_sync(rating) {
+        Ok(Some(title)) => println!("✓ Rated \"{}\": {}", title, rating_stars(rating)),
+        Ok(None) => println!("⚠ No matching wallpaper found in database"),
+        Err(e) => println!("✗ Error: {}", e),
+    }
+    Ok(())
+}
@@ -0,0 +1,397 @@
+//! Non-interactive CLI subcommands (desktop only)
+//!
+//! `bingtray <command> [--json]` runs one command and exits, for scripts and
+//! cron jobs; without a command the terminal gets the menu REPL (`crate::cli`).
+//! Results go to stdout (as JSON with `--json`), errors to stderr, and the
+//! exit code tells them apart (see `EXIT_OK` and the following constants).
+
+use std::fmt;
+
+use anyhow::Result;
+use serde_json::json;
+
+use crate::control::{self, ControlCommand};
+use crate::db::{operations, BingImage, ImageStatus};
+use crate::viewmodel::{ViewModel, WallpaperSetResult};
+
+/// The command succeeded
+pub const EXIT_OK: i32 = 0;
+/// The command failed (download, database or desktop errors)
+pub const EXIT_FAILURE: i32 = 1;
+/// Unknown command or invalid arguments
+pub const EXIT_USAGE: i32 = 2;
+/// Nothing to act on: no such image, no favorites, or the wallpaper is not from Bingtray
+pub const EXIT_NOTHING: i32 = 3;
+
+pub const USAGE: &str = "\
+Usage: bingtray [OPTIONS] [COMMAND] [--json]
+
+Commands (without one, a terminal gets the interactive menu):
+  next                      Set the next new image
+  keep                      Keep the current wallpaper as a favorite
+  blacklist                 Blacklist the current wallpaper and set the next one
+  favorite random           Set a kept wallpaper
+  list [--status STATUS]    List images (STATUS: unprocessed, keepfavorite, blacklisted)
+  show <ID|URL>             Show an image
+  set <ID|URL>              Set an image as wallpaper
+  search <QUERY> [--status STATUS]
+                            Search titles, places and photographers
+  stats                     Count images by status, history entries, countries and collections
+  help                      Show this help
+
+Options:
+  --json                    Print results (and errors) as JSON
+  --gui, --tray, --daemon   Run the app, the tray or the headless daemon
+  --data-dir PATH, --portable, --log [FILE]
+
+Exit codes: 0 success, 1 failure, 2 invalid arguments, 3 nothing to act on";
+
+/// A subcommand with its arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subcommand {
+    Next,
+    Keep,
+    Blacklist,
+    FavoriteRandom,
+    List { status: Option<ImageStatus> },
+    Show { image: String },
+    Set { image: String },
+    Search { query: String, status: Option<ImageStatus> },
+    Stats,
+    Help,
+}
+
+impl Subcommand {
+    /// Whether the command downloads or sets images (and needs files of older releases imported first)
+    pub fn changes_wallpaper(&self) -> bool {
+        matches!(
+            self,
+            Subcommand::Next
+                | Subcommand::Keep
+                | Subcommand::Blacklist
+                | Subcommand::FavoriteRandom
+                | Subcommand::Set { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub command: Subcommand,
+    pub json: bool,
+}
+
+/// Subcommand of the command line, None when there is none (run a mode)
+///
+/// Mode and data location flags are left to `main`; the error is a usage message.
+pub fn parse_args(args: &[String]) -> Result<Option<Invocation>, String> {
+    let mut words: Vec<&str> = Vec::new();
+    let mut json = false;
+    let mut help = false;
+    let mut status = None;
+
+    let mut iter = args.iter().skip(1).peekable();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--help" | "-h" => help = true,
+            "--status" => {
+                let value = iter.next().ok_or("--status needs a value")?;
+                status = Some(parse_status(value)?);
+            }
+            // Values are read by `main` (the log file is optional)
+            "--data-dir" | "--log" => {
+                iter.next_if(|value| !value.starts_with("--"));
+            }
+            _ if arg.starts_with("--status=") => status = Some(parse_status(&arg["--status=".len()..])?),
+            _ if arg.starts_with("--") => {}
+            _ => words.push(arg),
+        }
+    }
+
+    let command = match words.as_slice() {
+        [] if help => Subcommand::Help,
+        [] => return Ok(None),
+        ["help"] => Subcommand::Help,
+        ["next"] => Subcommand::Next,
+        ["keep"] => Subcommand::Keep,
+        ["blacklist"] => Subcommand::Blacklist,
+        ["favorite", "random"] => Subcommand::FavoriteRandom,
+        ["favorite", ..] => return Err("Usage: bingtray favorite random".to_string()),
+        ["list"] => Subcommand::List { status: status.take() },
+        ["show", image] => Subcommand::Show { image: image.to_string() },
+        ["set", image] => Subcommand::Set { image: image.to_string() },
+        ["show" | "set", ..] => return Err(format!("Usage: bingtray {} <ID|URL>", words[0])),
+        ["search", query @ ..] if !query.is_empty() => Subcommand::Search {
+            query: query.join(" "),
+            status: status.take(),
+        },
+        ["search"] => return Err("Usage: bingtray search <QUERY>".to_string()),
+        ["stats"] => Subcommand::Stats,
+        [name, ..] if is_command(name) => return Err(format!("Unexpected arguments for {}", name)),
+        [name, ..] => return Err(format!("Unknown command: {}", name)),
+    };
+    if status.is_some() {
+        return Err("--status only applies to list and search".to_string());
+    }
+
+    Ok(Some(Invocation { command, json }))
+}
+
+fn is_command(name: &str) -> bool {
+    matches!(name, "help" | "next" | "keep" | "blacklist" | "list" | "stats")
+}
+
+fn parse_status(value: &str) -> Result<ImageStatus, String> {
+    ImageStatus::from_str(value)
+        .ok_or_else(|| format!("Unknown status {:?} (unprocessed, keepfavorite or blacklisted)", value))
+}
+
+/// Result of a command, printed as `text` or as `json`
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub json: serde_json::Value,
+    pub text: String,
+}
+
+/// Nothing to act on (exit code `EXIT_NOTHING`)
+#[derive(Debug)]
+pub struct NothingToDo(pub String);
+
+impl fmt::Display for NothingToDo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for NothingToDo {}
+
+/// Exit code of a failed command
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    if error.is::<NothingToDo>() {
+        EXIT_NOTHING
+    } else {
+        EXIT_FAILURE
+    }
+}
+
+/// Run a subcommand, print its result and return the exit code
+pub fn run(invocation: &Invocation) -> i32 {
+    if invocation.command == Subcommand::Help {
+        println!("{}", USAGE);
+        return EXIT_OK;
+    }
+
+    let result = crate::db::get_database_path()
+        .and_then(ViewModel::new_sync)
+        .and_then(|viewmodel| {
+            if invocation.command.changes_wallpaper() {
+                import_legacy_layout(&viewmodel);
+            }
+            execute(&viewmodel, &invocation.command)
+        });
+
+    match result {
+        Ok(output) if invocation.json => {
+            println!("{}", serde_json::to_string_pretty(&output.json).unwrap_or_default());
+            EXIT_OK
+        }
+        Ok(output) => {
+            print!("{}", output.text);
+            EXIT_OK
+        }
+        Err(e) => {
+            if invocation.json {
+                println!("{}", json!({ "error": e.to_string() }));
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            exit_code(&e)
+        }
+    }
+}
+
+/// Import files of older releases before picking images, reported in the log only (stdout is for results)
+fn import_legacy_layout(viewmodel: &ViewModel) {
+    match viewmodel.import_legacy_layout_once() {
+        Ok(Some(report)) => log::info!("Imported files of an older version: {}", report.summary()),
+        Ok(None) => {}
+        Err(e) => log::error!("Failed to import files of an older version: {}", e),
+    }
+}
+
+/// Run a subcommand
+///
+/// Commands changing the wallpaper are forwarded to the running tray or
+/// daemon when there is one (see `crate::control`).
+pub fn execute(viewmodel: &ViewModel, command: &Subcommand) -> Result<Output> {
+    match command {
+        Subcommand::Next => {
+            let result = match control::send(ControlCommand::Next) {
+                Some(result) => {
+                    result?;
+                    current_wallpaper(viewmodel)?
+                }
+                None => viewmodel.download_and_set_next_wallpaper_sync()?,
+            };
+            wallpaper_set(&result)
+        }
+        Subcommand::FavoriteRandom => {
+            forward_or(ControlCommand::RandomFavorite, || viewmodel.set_random_favorite_wallpaper_sync())?
+                .ok_or_else(|| NothingToDo("No favorites available".to_string()))?;
+            wallpaper_set(&current_wallpaper(viewmodel)?)
+        }
+        Subcommand::Keep => mark_current(viewmodel, ControlCommand::Keep, || viewmodel.keep_current_wallpaper_sync()),
+        Subcommand::Blacklist => {
+            mark_current(viewmodel, ControlCommand::Blacklist, || viewmodel.blacklist_current_wallpaper_sync())
+        }
+        Subcommand::List { status } => {
+            let statuses = match status {
+                Some(status) => vec![*status],
+                None => vec![ImageStatus::Unprocessed, ImageStatus::KeepFavorite, ImageStatus::Blacklisted],
+            };
+            let mut images = Vec::new();
+            for status in statuses {
+                images.extend(viewmodel.get_images_by_status_sync(status)?);
+            }
+            Ok(image_list(&images))
+        }
+        Subcommand::Show { image } => {
+            let image = find_image(viewmodel, image)?;
+            Ok(Output { json: json!(image), text: image_details(&image) })
+        }
+        Subcommand::Set { image } => {
+            let image = find_image(viewmodel, image)?;
+            forward_or(ControlCommand::Set(image.url.clone()), || match viewmodel.set_wallpaper_sync(&image.url)? {
+                true => Ok(Some(image.title.clone())),
+                false => anyhow::bail!("Failed to set wallpaper"),
+            })?;
+            wallpaper_set(&WallpaperSetResult { title: image.title, url: image.url })
+        }
+        Subcommand::Search { query, status } => {
+            let mut images = Vec::new();
+            for page in 0.. {
+                let results = viewmodel.search_sync(query, *status, page)?;
+                let last_page = page + 1 >= results.page_count();
+                images.extend(results.images);
+                if last_page {
+                    break;
+                }
+            }
+            Ok(image_list(&images))
+        }
+        Subcommand::Stats => stats(viewmodel),
+        Subcommand::Help => Ok(Output { json: json!({ "usage": USAGE }), text: format!("{}\n", USAGE) }),
+    }
+}
+
+/// Run a command in the running tray or daemon if there is one, here otherwise
+fn forward_or(command: ControlCommand, local: impl FnOnce() -> Result<Option<String>>) -> Result<Option<String>> {
+    control::send(command).unwrap_or_else(local)
+}
+
+/// Keep or blacklist the current wallpaper, the result is the image marked
+fn mark_current(
+    viewmodel: &ViewModel,
+    command: ControlCommand,
+    local: impl FnOnce() -> Result<Option<String>>,
+) -> Result<Output> {
+    let not_ours = || NothingToDo("The current wallpaper is not from Bingtray".to_string());
+    let url = viewmodel.get_current_desktop_wallpaper_url_sync()?.ok_or_else(not_ours)?;
+    forward_or(command, local)?.ok_or_else(not_ours)?;
+
+    let image = operations::get_image(&mut *viewmodel.db_connection()?, &url)?.ok_or_else(not_ours)?;
+    let text = format!("{}\t{}\n", image.status, image.title);
+    Ok(Output { json: json!(image), text })
+}
+
+/// The wallpaper set last (by this or the running instance)
+fn current_wallpaper(viewmodel: &ViewModel) -> Result<WallpaperSetResult> {
+    let url = viewmodel
+        .get_current_desktop_wallpaper_url_sync()?
+        .ok_or_else(|| anyhow::anyhow!("The wallpaper was set but cannot be found"))?;
+    let title = operations::get_image(&mut *viewmodel.db_connection()?, &url)?
+        .map(|image| image.title)
+        .unwrap_or_default();
+    Ok(WallpaperSetResult { title, url })
+}
+
+fn wallpaper_set(result: &WallpaperSetResult) -> Result<Output> {
+    Ok(Output {
+        json: json!(result),
+        text: format!("{}\n{}\n", result.title, result.url),
+    })
+}
+
+/// Image by row id or URL
+fn find_image(viewmodel: &ViewModel, id_or_url: &str) -> Result<BingImage> {
+    let mut conn = viewmodel.db_connection()?;
+    let image = match id_or_url.parse::<i32>() {
+        Ok(id) => operations::get_image_by_id(&mut conn, id)?,
+        Err(_) => operations::get_image(&mut conn, id_or_url)?,
+    };
+    image.ok_or_else(|| NothingToDo(format!("No image {}", id_or_url)).into())
+}
+
+/// One image per line: id, status and title separated by tabs
+fn image_list(images: &[BingImage]) -> Output {
+    let text = images
+        .iter()
+        .map(|image| format!("{}\t{}\t{}\n", image.id, image.status, image.title))
+        .collect();
+    Output { json: json!(images), text }
+}
+
+fn image_details(image: &BingImage) -> String {
+    let mut lines = vec![
+        format!("id: {}", image.id),
+        format!("title: {}", image.title),
+        format!("status: {}", image.status),
+        format!("rating: {}", crate::db::models::rating_stars(image.rating)),
+        format!("market: {}", image.market_code),
+        format!("url: {}", image.url),
+    ];
+    let optional = [
+        ("copyright", &image.copyright),
+        ("place", &image.place),
+        ("country", &image.country),
+        ("date", &image.startdate),
+    ];
+    for (name, value) in optional {
+        if let Some(value) = value.as_deref().filter(|value| !value.is_empty()) {
+            lines.push(format!("{}: {}", name, value));
+        }
+    }
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+fn stats(viewmodel: &ViewModel) -> Result<Output> {
+    let mut conn = viewmodel.db_connection()?;
+    let unprocessed = operations::count_by_status(&mut conn, ImageStatus::Unprocessed)?;
+    let kept = operations::count_by_status(&mut conn, ImageStatus::KeepFavorite)?;
+    let blacklisted = operations::count_by_status(&mut conn, ImageStatus::Blacklisted)?;
+    let history = operations::count_history_entries(&mut conn)?;
+    let countries = operations::count_images_by_country(&mut conn)?.len();
+    let collections = operations::get_collections(&mut conn)?.len();
+
+    let json = json!({
+        "images": unprocessed + kept + blacklisted,
+        "unprocessed": unprocessed,
+        "keepfavorite": kept,
+        "blacklisted": blacklisted,
+        "history_entries": history,
+        "countries": countries,
+        "collections": collections,
+    });
+    let text = format!(
+        "images: {}\nunprocessed: {}\nkeepfavorite: {}\nblacklisted: {}\nhistory entries: {}\ncountries: {}\ncollections: {}\n",
+        unprocessed + kept + blacklisted,
+        unprocessed,
+        kept,
+        blacklisted,
+        history,
+        countries,
+        collections
+    );
+    Ok(Output { json, text })
+}
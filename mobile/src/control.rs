@@ -0,0 +1,485 @@
+//! Single instance and local control (desktop only)
+//!
+//! The tray and the daemon serve commands on a local socket of the user and
+//! database (a Unix domain socket, a named pipe on Windows). A second tray or
+//! daemon finds it and exits instead of racing the first one over the
+//! database, and the CLI and launching the app again forward their actions to it.
+//!
+//! One request per connection: a command line such as "next" or "set URL",
+//! answered by "ok", "ok TITLE" or "error MESSAGE".
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::db::{DbPool, WallpaperSource};
+
+/// How long a client waits for the reply (commands may download an image)
+const REPLY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Longest request line read (`set` carries a URL)
+const MAX_REQUEST_LEN: u64 = 4096;
+
+/// How long the server waits for a client to send its command
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Command forwarded to the running instance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlCommand {
+    Next,
+    Keep,
+    Blacklist,
+    RandomFavorite,
+    Previous,
+    OpenGui,
+    /// Set the image with this URL
+    Set(String),
+}
+
+impl ControlCommand {
+    /// Command name, without its argument
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ControlCommand::Next => "next",
+            ControlCommand::Keep => "keep",
+            ControlCommand::Blacklist => "blacklist",
+            ControlCommand::RandomFavorite => "favorite",
+            ControlCommand::Previous => "previous",
+            ControlCommand::OpenGui => "gui",
+            ControlCommand::Set(_) => "set",
+        }
+    }
+
+    /// Request line, without the newline
+    pub fn to_line(&self) -> String {
+        match self {
+            ControlCommand::Set(url) => format!("set {}", url),
+            command => command.as_str().to_string(),
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        if let Some(url) = s.strip_prefix("set ").map(str::trim).filter(|url| !url.is_empty()) {
+            return Some(ControlCommand::Set(url.to_string()));
+        }
+        match s {
+            "next" => Some(ControlCommand::Next),
+            "keep" => Some(ControlCommand::Keep),
+            "blacklist" => Some(ControlCommand::Blacklist),
+            "favorite" => Some(ControlCommand::RandomFavorite),
+            "previous" => Some(ControlCommand::Previous),
+            "gui" => Some(ControlCommand::OpenGui),
+            _ => None,
+        }
+    }
+}
+
+/// Reply line of a command result (the title of the image it changed, if any)
+pub fn encode_reply(result: &Result<Option<String>>) -> String {
+    let one_line = |text: &str| text.replace(['\r', '\n'], " ");
+    match result {
+        Ok(None) => "ok\n".to_string(),
+        Ok(Some(title)) => format!("ok {}\n", one_line(title)),
+        Err(e) => format!("error {}\n", one_line(&e.to_string())),
+    }
+}
+
+/// Command result of a reply line
+pub fn decode_reply(line: &str) -> Result<Option<String>> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    match line.split_once(' ') {
+        _ if line == "ok" => Ok(None),
+        Some(("ok", title)) => Ok(Some(title.to_string())),
+        Some(("error", message)) => Err(anyhow::anyhow!("{}", message)),
+        _ => Err(anyhow::anyhow!("Unexpected reply from the running instance: {:?}", line)),
+    }
+}
+
+/// Socket (or pipe) name of the user and database
+pub fn socket_name(db_path: &Path) -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    let digest = md5::compute(format!("{}\n{}", user, db_path.display()));
+    let id = &format!("{:x}", digest)[..12];
+    imp::socket_name(&user, id)
+}
+
+/// Run a command in the running tray or daemon
+/// Returns None when no instance is running.
+pub fn send(command: ControlCommand) -> Option<Result<Option<String>>> {
+    let name = socket_name(&crate::db::get_database_path().ok()?);
+    let stream = imp::connect(&name).ok()?;
+    log::info!("Forwarding '{}' to the running instance", command.as_str());
+    Some(request(stream, command))
+}
+
+fn request(mut stream: imp::Stream, command: ControlCommand) -> Result<Option<String>> {
+    imp::set_read_timeout(&stream, REPLY_TIMEOUT);
+    stream.write_all(format!("{}\n", command.to_line()).as_bytes())?;
+    stream.flush()?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    decode_reply(&reply)
+}
+
+static OPEN_GUI: AtomicBool = AtomicBool::new(false);
+static CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a client asked to open the GUI since the last call
+pub fn take_open_gui_request() -> bool {
+    OPEN_GUI.swap(false, Ordering::Relaxed)
+}
+
+/// Whether a forwarded command changed the wallpaper since the last call (for menus showing it)
+pub fn take_changed() -> bool {
+    CHANGED.swap(false, Ordering::Relaxed)
+}
+
+/// Control server thread, stopped when dropped
+pub struct ControlServer {
+    name: String,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ControlServer {
+    /// Serve commands of this database, None when another instance already does
+    ///
+    /// `gui` tells whether this instance can open the GUI (the tray can, the daemon cannot).
+    pub fn start(pool: DbPool, gui: bool) -> Result<Option<Self>> {
+        let name = socket_name(pool.db_path());
+        let Some(listener) = imp::bind(&name)? else {
+            return Ok(None);
+        };
+        log::info!("Serving control commands on {}", name);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = std::thread::Builder::new()
+            .name("control-server".to_string())
+            .spawn(move || serve(listener, pool, gui, thread_stop))?;
+
+        Ok(Some(Self { name, stop, thread: Some(thread) }))
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Wake up the blocking accept
+        let _ = imp::connect(&self.name);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn serve(mut listener: imp::Listener, pool: DbPool, gui: bool, stop: Arc<AtomicBool>) {
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok(_) if stop.load(Ordering::Relaxed) => break,
+            Ok(stream) => {
+                if let Err(e) = handle_connection(stream, &pool, gui) {
+                    log::warn!("Control connection failed: {}", e);
+                }
+            }
+            Err(e) => {
+                log::error!("Control server failed to accept: {}", e);
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        }
+    }
+}
+
+fn handle_connection(stream: imp::Stream, pool: &DbPool, gui: bool) -> io::Result<()> {
+    // Connections are served one at a time, a silent client must not block the others
+    imp::set_read_timeout(&stream, REQUEST_TIMEOUT);
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    match (&mut reader).take(MAX_REQUEST_LEN).read_line(&mut line) {
+        Ok(_) => {}
+        Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+            log::warn!("Control client sent no command within {:?}", REQUEST_TIMEOUT);
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    }
+    if line.is_empty() {
+        return Ok(());
+    }
+
+    let result = match ControlCommand::from_str(line.trim()) {
+        Some(command) => {
+            log::info!("Control command: {}", command.to_line());
+            execute(pool, command, gui)
+        }
+        None => Err(anyhow::anyhow!("Unknown command: {}", line.trim())),
+    };
+    if let Err(e) = &result {
+        log::warn!("Control command '{}' failed: {}", line.trim(), e);
+    }
+
+    let stream = reader.get_mut();
+    stream.write_all(encode_reply(&result).as_bytes())?;
+    stream.flush()
+}
+
+/// Run a command of another process (the title of the image it set or marked)
+pub(crate) fn execute(pool: &DbPool, command: ControlCommand, gui: bool) -> Result<Option<String>> {
+    use crate::viewmodel::commands;
+
+    // Forwarded commands come from the command line
+    let source = WallpaperSource::Cli;
+    let mut conn = pool.get()?;
+    let result = match command {
+        ControlCommand::Next => {
+            commands::download_and_set_next_wallpaper_sync(&mut conn, source).map(|result| Some(result.title))
+        }
+        ControlCommand::Keep => commands::keep_current_wallpaper_sync(&mut conn, source),
+        ControlCommand::Blacklist => commands::blacklist_current_wallpaper_sync(&mut conn, source),
+        ControlCommand::RandomFavorite => commands::set_random_favorite_wallpaper_sync(&mut conn, source),
+        ControlCommand::Previous => {
+            commands::set_previous_wallpaper_sync(&mut conn, source).map(|result| result.map(|result| result.title))
+        }
+        ControlCommand::Set(url) => match commands::set_wallpaper_sync(&mut conn, &url, source)? {
+            true => Ok(Some(crate::db::operations::get_image(&mut conn, &url)?.map_or(url, |image| image.title))),
+            false => anyhow::bail!("Failed to set wallpaper"),
+        },
+        ControlCommand::OpenGui if gui => {
+            OPEN_GUI.store(true, Ordering::Relaxed);
+            return Ok(None);
+        }
+        ControlCommand::OpenGui => anyhow::bail!("The daemon has no GUI, run bingtray --gui"),
+    };
+
+    if matches!(result, Ok(Some(_))) {
+        CHANGED.store(true, Ordering::Relaxed);
+    }
+    result
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    pub type Stream = UnixStream;
+
+    /// `$XDG_RUNTIME_DIR/bingtray-ID.sock`, or `bingtray-USER-ID.sock` in the temp directory
+    pub fn socket_name(user: &str, id: &str) -> String {
+        let path = match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+            Some(dir) => PathBuf::from(dir).join(format!("bingtray-{}.sock", id)),
+            None => std::env::temp_dir().join(format!("bingtray-{}-{}.sock", user, id)),
+        };
+        path.to_string_lossy().into_owned()
+    }
+
+    pub fn connect(name: &str) -> io::Result<Stream> {
+        UnixStream::connect(name)
+    }
+
+    pub fn set_read_timeout(stream: &Stream, timeout: Duration) {
+        let _ = stream.set_read_timeout(Some(timeout));
+    }
+
+    /// Socket file removed when dropped
+    pub struct Listener {
+        listener: UnixListener,
+        path: PathBuf,
+        /// Held for as long as the socket is served
+        _lock: File,
+    }
+
+    impl Listener {
+        pub fn accept(&mut self) -> io::Result<Stream> {
+            self.listener.accept().map(|(stream, _)| stream)
+        }
+    }
+
+    impl Drop for Listener {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    /// Exclusive lock on `NAME.lock`, None while another instance holds it
+    fn lock(name: &str) -> io::Result<Option<File>> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(format!("{}.lock", name))?;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let error = io::Error::last_os_error();
+            return match error.kind() {
+                io::ErrorKind::WouldBlock => Ok(None),
+                _ => Err(error),
+            };
+        }
+        Ok(Some(file))
+    }
+
+    /// Listen on the socket, None when another instance answers on it
+    ///
+    /// The lock file makes removing a stale socket and binding one step, so two
+    /// instances starting at the same time cannot both take over the socket.
+    pub fn bind(name: &str) -> io::Result<Option<Listener>> {
+        let Some(lock) = lock(name)? else {
+            return Ok(None);
+        };
+
+        let path = PathBuf::from(name);
+        if path.exists() {
+            if connect(name).is_ok() {
+                return Ok(None);
+            }
+            // Left behind by an instance that crashed
+            log::info!("Removing stale control socket {}", name);
+            std::fs::remove_file(&path)?;
+        }
+
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            // Another instance started at the same time
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        Ok(Some(Listener { listener, path, _lock: lock }))
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::{FromRawHandle, RawHandle};
+    use std::time::Duration;
+
+    use windows_sys::Win32::Foundation::{
+        CloseHandle, GetLastError, ERROR_ACCESS_DENIED, ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED, HANDLE,
+        INVALID_HANDLE_VALUE,
+    };
+    use windows_sys::Win32::Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX};
+    use windows_sys::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, WaitNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+
+    /// How long a client waits for a free pipe instance
+    const PIPE_BUSY_WAIT_MS: u32 = 30_000;
+
+    pub type Stream = File;
+
+    /// `\\.\pipe\bingtray-USER-ID` (pipe names are shared by all users)
+    pub fn socket_name(user: &str, id: &str) -> String {
+        format!(r"\\.\pipe\bingtray-{}-{}", user, id)
+    }
+
+    fn open_pipe(name: &str) -> io::Result<Stream> {
+        std::fs::OpenOptions::new().read(true).write(true).open(name)
+    }
+
+    pub fn connect(name: &str) -> io::Result<Stream> {
+        match open_pipe(name) {
+            // The instance is running, all pipe instances are serving clients
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY as i32) => {
+                if unsafe { WaitNamedPipeW(wide(name).as_ptr(), PIPE_BUSY_WAIT_MS) } == 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                open_pipe(name)
+            }
+            result => result,
+        }
+    }
+
+    pub fn set_read_timeout(_stream: &Stream, _timeout: Duration) {
+        // Pipe reads block until the instance replies or closes the pipe
+    }
+
+    /// Pipe instance waiting for the next client
+    pub struct Listener {
+        name: String,
+        next: Option<HANDLE>,
+    }
+
+    // The handle is only used by the thread owning the listener
+    unsafe impl Send for Listener {}
+
+    impl Listener {
+        pub fn accept(&mut self) -> io::Result<Stream> {
+            let handle = match self.next.take() {
+                Some(handle) => handle,
+                None => create_pipe(&self.name, false)?,
+            };
+            let connected = unsafe { ConnectNamedPipe(handle, std::ptr::null_mut()) } != 0
+                || unsafe { GetLastError() } == ERROR_PIPE_CONNECTED;
+            if !connected {
+                let error = io::Error::last_os_error();
+                unsafe { CloseHandle(handle) };
+                return Err(error);
+            }
+
+            // Listen again before this client is served, so others never find no instance
+            match create_pipe(&self.name, false) {
+                Ok(next) => self.next = Some(next),
+                Err(e) => log::warn!("Failed to create the next control pipe instance: {}", e),
+            }
+            Ok(unsafe { File::from_raw_handle(handle as RawHandle) })
+        }
+    }
+
+    impl Drop for Listener {
+        fn drop(&mut self) {
+            if let Some(handle) = self.next.take() {
+                unsafe { CloseHandle(handle) };
+            }
+        }
+    }
+
+    /// NUL-terminated UTF-16 name
+    fn wide(name: &str) -> Vec<u16> {
+        name.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    fn create_pipe(name: &str, first: bool) -> io::Result<HANDLE> {
+        let open_mode = PIPE_ACCESS_DUPLEX | if first { FILE_FLAG_FIRST_PIPE_INSTANCE } else { 0 };
+        let handle = unsafe {
+            CreateNamedPipeW(
+                wide(name).as_ptr(),
+                open_mode,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                std::ptr::null(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(handle)
+    }
+
+    /// Create the first pipe instance, None when another instance owns the name
+    pub fn bind(name: &str) -> io::Result<Option<Listener>> {
+        match create_pipe(name, true) {
+            Ok(handle) => Ok(Some(Listener { name: name.to_string(), next: Some(handle) })),
+            Err(e) if e.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
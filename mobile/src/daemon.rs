@@ -0,0 +1,81 @@
+//! Headless daemon mode (`--daemon`, desktop only)
+//!
+//! Runs the rotation scheduler (see `crate::viewmodel::scheduler`) and keeps
+//! the image cache filled, with no GUI or tray. Meant to run as a systemd user
+//! service (`bingtray --silent --service` installs one); logs go to a file.
+//! Like the tray, it serves control commands (see `crate::control`) and, on
+//! Linux, the D-Bus service (see `crate::dbus`).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::control::ControlServer;
+use crate::db::DbPool;
+use crate::viewmodel::cache_manager::CacheManager;
+use crate::viewmodel::scheduler::Scheduler;
+use crate::viewmodel::{commands, ViewModel};
+
+/// Log file name inside the data directory when `--log` is not given
+pub const DAEMON_LOG_FILE: &str = "bingtray-daemon.log";
+
+/// How often the image cache is checked
+const REFILL_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Run until the process is stopped (SIGTERM from systemd)
+pub fn run_daemon_mode() -> Result<()> {
+    let db_path = crate::db::get_database_path()?;
+    let pool = DbPool::shared(&db_path)?;
+
+    // Single-instance check first, a second daemon must not import or download anything
+    let Some(_control) = ControlServer::start(pool.clone(), false)? else {
+        log::warn!("Another Bingtray tray or daemon is running on this database, exiting");
+        return Ok(());
+    };
+
+    let viewmodel = ViewModel::new_sync(db_path)?;
+
+    match viewmodel.import_legacy_layout_once() {
+        Ok(Some(report)) => log::info!("Imported files of an older version: {}", report.summary()),
+        Ok(None) => {}
+        Err(e) => log::error!("Failed to import files of an older version: {}", e),
+    }
+
+    match viewmodel.get_rotation_schedule_sync() {
+        Ok(schedule) if !schedule.is_enabled() => {
+            log::warn!("No rotation schedule is set, only the image cache is kept filled");
+        }
+        Ok(schedule) => log::info!("Rotation schedule: {:?}", schedule),
+        Err(e) => log::error!("Failed to load the rotation schedule: {}", e),
+    }
+
+    #[cfg(target_os = "linux")]
+    let _dbus = crate::dbus::DbusService::start(pool.clone());
+    let _scheduler = Scheduler::start(pool.clone());
+    let cache_manager = viewmodel
+        .cache_manager()
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Cache manager not available"))?;
+
+    log::info!("Daemon running");
+    loop {
+        if let Err(e) = refill_cache(&pool, &cache_manager) {
+            log::warn!("Cache refill failed: {}", e);
+        }
+        std::thread::sleep(REFILL_INTERVAL);
+    }
+}
+
+/// Download images when fewer than three new ones are cached
+fn refill_cache(pool: &DbPool, cache_manager: &Arc<CacheManager>) -> Result<()> {
+    // Source settings may have been changed in the app since the last check
+    let sources = commands::image_source_from_settings_sync(&mut *pool.get()?)?;
+    cache_manager.set_sources(Arc::new(sources));
+
+    if cache_manager.needs_refill()? {
+        let cached = cache_manager.refill_background()?;
+        log::info!("Cache refill downloaded {} images", cached);
+    }
+    Ok(())
+}
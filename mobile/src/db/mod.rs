@@ -6,45 +6,59 @@ use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 
 pub mod models;
 pub mod operations;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod pool;
 
-pub use models::{BingImage, ImageStatus, MarketCode, ConfigKv};
+pub use models::{
+    BingImage, Collection, ConfigKv, ImageStatus, MarketCode, StatusLogAction, StatusLogEntry, Tag,
+    WallpaperHistoryEntry, WallpaperSource,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use pool::{DbPool, PooledConnection};
 
 #[cfg(not(target_arch = "wasm32"))]
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
 
-/// Get database file path
+/// config_kv key holding the image cache directory of the database (set by `DbPool::open`)
+pub const IMAGE_CACHE_DIR_KEY: &str = "image_cache_dir";
+
+/// Get database file path (see `crate::paths` for how the location is picked)
 #[cfg(not(target_arch = "wasm32"))]
 pub fn get_database_path() -> anyhow::Result<std::path::PathBuf> {
-    #[cfg(target_os = "android")]
-    {
-        use std::path::PathBuf;
-        Ok(PathBuf::from("/data/data/pe.nikescar.bingtray/files/bingtray.db"))
-    }
+    Ok(crate::paths::data_paths()?.db_path())
+}
 
-    #[cfg(not(target_os = "android"))]
-    {
-        let config_dir = directories::ProjectDirs::from("com", "nikescar", "bingtray")
-            .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?
-            .config_dir()
-            .to_path_buf();
+/// Establish SQLite database connection, running pending migrations
+///
+/// Fails (instead of panicking) on an unusable path, a locked or corrupted
+/// database and on migration errors, so callers can show the error.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn establish_connection(db_path: &Path) -> anyhow::Result<SqliteConnection> {
+    let mut conn = open_connection(db_path)?;
 
-        std::fs::create_dir_all(&config_dir)?;
-        Ok(config_dir.join("bingtray.db"))
-    }
+    // Run migrations (only the first connection will actually run them)
+    conn.run_pending_migrations(MIGRATIONS)
+        .map_err(|e| anyhow::anyhow!("Failed to run database migrations on {}: {}", db_path.display(), e))?;
+
+    Ok(conn)
 }
 
-/// Establish SQLite database connection
+/// Open a connection and set the pragmas, without checking migrations
 #[cfg(not(target_arch = "wasm32"))]
-pub fn establish_connection(db_path: &Path) -> SqliteConnection {
-    let url = db_path.to_str().expect("Valid UTF-8 path");
+fn open_connection(db_path: &Path) -> anyhow::Result<SqliteConnection> {
+    use anyhow::Context;
+
+    let url = db_path
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("Database path is not valid UTF-8: {}", db_path.display()))?;
     let mut conn = SqliteConnection::establish(url)
-        .unwrap_or_else(|_| panic!("Error connecting to {}", url));
+        .with_context(|| format!("Error connecting to {}", url))?;
 
     // Set busy timeout FIRST (before any other operations)
     // This allows subsequent operations to wait instead of failing immediately
     diesel::sql_query("PRAGMA busy_timeout=30000;")
         .execute(&mut conn)
-        .expect("Failed to set busy timeout");
+        .with_context(|| format!("Failed to set busy timeout on {}", url))?;
 
     // Enable WAL mode for better concurrent access
     // Retry logic handles race conditions during rapid connection creation
@@ -55,7 +69,7 @@ pub fn establish_connection(db_path: &Path) -> SqliteConnection {
             Err(e) => {
                 retries += 1;
                 if retries >= 5 {
-                    panic!("Failed to set WAL mode after {} retries: {}", retries, e);
+                    anyhow::bail!("Failed to set WAL mode on {} after {} retries: {}", url, retries, e);
                 }
                 log::warn!("WAL mode attempt {} failed ({}), retrying...", retries, e);
                 std::thread::sleep(std::time::Duration::from_millis(100 * retries));
@@ -63,15 +77,12 @@ pub fn establish_connection(db_path: &Path) -> SqliteConnection {
         }
     }
 
-    // Run migrations (only the first connection will actually run them)
-    conn.run_pending_migrations(MIGRATIONS)
-        .expect("Failed to run database migrations");
-
-    conn
+    Ok(conn)
 }
 
 /// WASM stub
 #[cfg(target_arch = "wasm32")]
-pub fn establish_connection(_db_path: &Path) -> () {
+pub fn establish_connection(_db_path: &Path) -> anyhow::Result<()> {
     log::warn!("SQLite not available on WASM");
+    Ok(())
 }
@@ -1,6 +1,6 @@
 use diesel::prelude::*;
 
-#[derive(Debug, Clone, Queryable, Selectable)]
+#[derive(Debug, Clone, Queryable, Selectable, QueryableByName, serde::Serialize)]
 #[diesel(table_name = crate::schema::bing_images)]
 pub struct BingImage {
     pub id: i32,
@@ -15,9 +15,49 @@ pub struct BingImage {
     pub updated_at: i32,
     pub cached_at: Option<i32>,
     pub crop_coords: Option<String>,
+    /// First day the image was shown (YYYYMMDD)
+    pub startdate: Option<String>,
+    /// Publication time (YYYYMMDDHHMM)
+    pub fullstartdate: Option<String>,
+    /// Day the image was replaced (YYYYMMDD)
+    pub enddate: Option<String>,
+    /// URL without resolution suffix, e.g. "/th?id=OHR.Name_EN-US123"
+    pub urlbase: Option<String>,
+    /// Bing image hash
+    pub hsh: Option<String>,
+    /// Bing homepage quiz link
+    pub quiz: Option<String>,
+    /// Perceptual hash of the image bytes (dHash, hex)
+    pub phash: Option<String>,
+    /// Row this image is a near-duplicate of (None for canonical rows)
+    pub canonical_id: Option<i32>,
+    /// Star rating, 0 (unrated) to `MAX_RATING`
+    pub rating: i32,
+    /// Place parsed from the copyright (None until parsed, "" if there is none)
+    pub place: Option<String>,
+    /// ISO 3166-1 code of the country parsed from the copyright
+    pub country: Option<String>,
 }
 
-#[derive(Debug, Insertable)]
+/// Highest star rating
+pub const MAX_RATING: i32 = 5;
+
+/// Rating as filled and empty stars ("★★★☆☆")
+pub fn rating_stars(rating: i32) -> String {
+    let filled = rating.clamp(0, MAX_RATING) as usize;
+    format!("{}{}", "★".repeat(filled), "☆".repeat(MAX_RATING as usize - filled))
+}
+
+impl BingImage {
+    /// Publication date parsed from `startdate`
+    pub fn publication_date(&self) -> Option<chrono::NaiveDate> {
+        self.startdate
+            .as_deref()
+            .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y%m%d").ok())
+    }
+}
+
+#[derive(Debug, Default, Insertable)]
 #[diesel(table_name = crate::schema::bing_images)]
 pub struct NewBingImage<'a> {
     pub url: &'a str,
@@ -29,6 +69,12 @@ pub struct NewBingImage<'a> {
     pub status: &'a str,
     pub created_at: i32,
     pub updated_at: i32,
+    pub startdate: Option<&'a str>,
+    pub fullstartdate: Option<&'a str>,
+    pub enddate: Option<&'a str>,
+    pub urlbase: Option<&'a str>,
+    pub hsh: Option<&'a str>,
+    pub quiz: Option<&'a str>,
 }
 
 #[derive(Debug, Clone, Queryable, Selectable)]
@@ -39,6 +85,7 @@ pub struct MarketCode {
     pub last_used_at: i32,
     pub created_at: i32,
     pub updated_at: i32,
+    pub page_offset: i32,
 }
 
 #[derive(Debug, Insertable)]
@@ -69,6 +116,109 @@ pub struct NewConfigKv<'a> {
     pub updated_at: i32,
 }
 
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::wallpaper_history)]
+pub struct WallpaperHistoryEntry {
+    pub id: i32,
+    pub url: String,
+    pub set_at: i32,
+    /// `WallpaperSource` that set the wallpaper
+    pub source: String,
+    /// Monitor the wallpaper was set on (None = all monitors)
+    pub monitor: Option<String>,
+    /// Crop applied (CropCoords JSON, None = full image)
+    pub crop_coords: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::wallpaper_history)]
+pub struct NewWallpaperHistoryEntry<'a> {
+    pub url: &'a str,
+    pub set_at: i32,
+    pub source: &'a str,
+    pub monitor: Option<&'a str>,
+    pub crop_coords: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::status_log)]
+pub struct StatusLogEntry {
+    pub id: i32,
+    pub url: String,
+    pub old_status: String,
+    pub new_status: String,
+    pub changed_at: i32,
+    /// `StatusLogAction` of the entry
+    pub action: String,
+    /// `WallpaperSource` of the user action (None for automatic changes)
+    pub source: Option<String>,
+    /// Change entry an undo or redo entry reverts or reapplies
+    pub reverts_id: Option<i32>,
+    /// Wallpaper replaced by this step (None if the wallpaper stayed)
+    pub replaced_wallpaper_url: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::status_log)]
+pub struct NewStatusLogEntry<'a> {
+    pub url: &'a str,
+    pub old_status: &'a str,
+    pub new_status: &'a str,
+    pub changed_at: i32,
+    pub action: &'a str,
+    pub source: Option<&'a str>,
+    pub reverts_id: Option<i32>,
+    pub replaced_wallpaper_url: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::tags)]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+    pub created_at: i32,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::tags)]
+pub struct NewTag<'a> {
+    pub name: &'a str,
+    pub created_at: i32,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::image_tags)]
+pub struct NewImageTag {
+    pub image_id: i32,
+    pub tag_id: i32,
+    pub created_at: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::collections)]
+pub struct Collection {
+    pub id: i32,
+    pub name: String,
+    pub created_at: i32,
+    pub updated_at: i32,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::collections)]
+pub struct NewCollection<'a> {
+    pub name: &'a str,
+    pub created_at: i32,
+    pub updated_at: i32,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::collection_images)]
+pub struct NewCollectionImage {
+    pub collection_id: i32,
+    pub image_id: i32,
+    pub added_at: i32,
+}
+
 /// Image status enum
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ImageStatus {
@@ -95,3 +245,73 @@ impl ImageStatus {
         }
     }
 }
+
+/// Where a wallpaper change came from (recorded in `wallpaper_history`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallpaperSource {
+    Cli,
+    Tray,
+    Gui,
+    Scheduler,
+}
+
+impl WallpaperSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WallpaperSource::Cli => "cli",
+            WallpaperSource::Tray => "tray",
+            WallpaperSource::Gui => "gui",
+            WallpaperSource::Scheduler => "scheduler",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "cli" => Some(WallpaperSource::Cli),
+            "tray" => Some(WallpaperSource::Tray),
+            "gui" => Some(WallpaperSource::Gui),
+            "scheduler" => Some(WallpaperSource::Scheduler),
+            _ => None,
+        }
+    }
+}
+
+/// Kind of a `status_log` entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatusLogAction {
+    #[default]
+    Change,
+    Undo,
+    Redo,
+}
+
+impl StatusLogAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatusLogAction::Change => "change",
+            StatusLogAction::Undo => "undo",
+            StatusLogAction::Redo => "redo",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "change" => Some(StatusLogAction::Change),
+            "undo" => Some(StatusLogAction::Undo),
+            "redo" => Some(StatusLogAction::Redo),
+            _ => None,
+        }
+    }
+}
+
+/// How a status change is recorded in `status_log`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StatusLogDetails<'a> {
+    pub action: StatusLogAction,
+    /// Who made the change (None for automatic changes, which cannot be undone)
+    pub source: Option<WallpaperSource>,
+    /// Change entry an undo or redo reverts or reapplies
+    pub reverts_id: Option<i32>,
+    /// Wallpaper the step replaced
+    pub replaced_wallpaper_url: Option<&'a str>,
+}
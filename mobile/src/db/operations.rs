@@ -1,6 +1,8 @@
 use diesel::prelude::*;
 use anyhow::Result;
-use crate::schema::{bing_images, config_kv};
+use crate::schema::{
+    bing_images, collection_images, collections, config_kv, image_tags, market_codes, status_log, tags, wallpaper_history,
+};
 use super::models::*;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -11,45 +13,106 @@ fn current_timestamp() -> i32 {
         .as_secs() as i32
 }
 
-/// Insert or update a Bing image record
-pub fn upsert_image(conn: &mut SqliteConnection, record: &NewBingImage) -> Result<BingImage> {
-    use diesel::RunQueryDsl;
+diesel::define_sql_function! {
+    /// SQL `COALESCE` of two nullable text values
+    fn coalesce(
+        x: diesel::sql_types::Nullable<diesel::sql_types::Text>,
+        y: diesel::sql_types::Nullable<diesel::sql_types::Text>,
+    ) -> diesel::sql_types::Nullable<diesel::sql_types::Text>;
+}
 
-    // Check if URL already exists
-    let existing: Option<BingImage> = bing_images::table
-        .filter(bing_images::url.eq(record.url))
-        .first(conn)
-        .optional()?;
+/// `INSERT ... ON CONFLICT(url) DO UPDATE ... RETURNING` for one record
+fn upsert_image_row(conn: &mut SqliteConnection, record: &NewBingImage) -> QueryResult<BingImage> {
+    use diesel::upsert::excluded;
 
-    if let Some(existing_img) = existing {
-        // Update existing record but preserve status (don't overwrite user's keep/blacklist)
-        diesel::update(bing_images::table.find(existing_img.id))
-            .set((
-                bing_images::title.eq(record.title),
-                bing_images::copyright.eq(record.copyright),
-                bing_images::copyright_link.eq(record.copyright_link),
-                bing_images::market_code.eq(record.market_code),
-                bing_images::fetched_at.eq(record.fetched_at),
+    diesel::insert_into(bing_images::table)
+        .values(record)
+        .on_conflict(bing_images::url)
+        .do_update()
+        .set((
+            (
+                bing_images::title.eq(excluded(bing_images::title)),
+                bing_images::copyright.eq(excluded(bing_images::copyright)),
+                bing_images::copyright_link.eq(excluded(bing_images::copyright_link)),
+                bing_images::market_code.eq(excluded(bing_images::market_code)),
+                bing_images::fetched_at.eq(excluded(bing_images::fetched_at)),
                 // DO NOT update status - preserve user's keep/blacklist choices
                 bing_images::updated_at.eq(current_timestamp()),
-            ))
-            .execute(conn)?;
-
-        bing_images::table
-            .find(existing_img.id)
-            .first(conn)
-            .map_err(Into::into)
-    } else {
-        // Insert new record
-        diesel::insert_into(bing_images::table)
-            .values(record)
-            .execute(conn)?;
+            ),
+            // Keep known metadata when a source without it (e.g. GitHub rows) sees the image again
+            (
+                bing_images::startdate.eq(coalesce(excluded(bing_images::startdate), bing_images::startdate)),
+                bing_images::fullstartdate.eq(coalesce(excluded(bing_images::fullstartdate), bing_images::fullstartdate)),
+                bing_images::enddate.eq(coalesce(excluded(bing_images::enddate), bing_images::enddate)),
+                bing_images::urlbase.eq(coalesce(excluded(bing_images::urlbase), bing_images::urlbase)),
+                bing_images::hsh.eq(coalesce(excluded(bing_images::hsh), bing_images::hsh)),
+                bing_images::quiz.eq(coalesce(excluded(bing_images::quiz), bing_images::quiz)),
+            ),
+        ))
+        .returning(BingImage::as_returning())
+        .get_result(conn)
+        .and_then(|image| update_location(conn, image))
+}
 
-        bing_images::table
-            .order(bing_images::id.desc())
-            .first(conn)
-            .map_err(Into::into)
+/// Store the place and country parsed from the image's copyright, if they changed
+fn update_location(conn: &mut SqliteConnection, mut image: BingImage) -> QueryResult<BingImage> {
+    let location = crate::location::parse_location(&image.title, image.copyright.as_deref());
+    let country = location.country.map(str::to_string);
+    if image.place.as_deref() == Some(location.place.as_str()) && image.country == country {
+        return Ok(image);
     }
+
+    diesel::update(bing_images::table.find(image.id))
+        .set((
+            bing_images::place.eq(&location.place),
+            bing_images::country.eq(&country),
+        ))
+        .execute(conn)?;
+    image.place = Some(location.place);
+    image.country = country;
+    Ok(image)
+}
+
+/// Parse the place and country of images stored before locations were parsed
+/// Returns the number of images updated.
+pub fn backfill_locations(conn: &mut SqliteConnection) -> Result<usize> {
+    conn.transaction(|conn| {
+        let images: Vec<BingImage> = bing_images::table
+            .filter(bing_images::place.is_null())
+            .select(BingImage::as_select())
+            .load(conn)?;
+        let count = images.len();
+        for image in images {
+            update_location(conn, image)?;
+        }
+        Ok(count)
+    })
+}
+
+/// Insert or update a Bing image record
+pub fn upsert_image(conn: &mut SqliteConnection, record: &NewBingImage) -> Result<BingImage> {
+    upsert_image_row(conn, record).map_err(Into::into)
+}
+
+/// Insert or update many Bing image records in one transaction
+/// Existing rows keep their status. Returns the stored rows, in input order.
+pub fn upsert_images_batch(conn: &mut SqliteConnection, records: &[NewBingImage]) -> Result<Vec<BingImage>> {
+    conn.transaction(|conn| {
+        records
+            .iter()
+            .map(|record| upsert_image_row(conn, record))
+            .collect::<QueryResult<Vec<_>>>()
+    })
+    .map_err(Into::into)
+}
+
+/// Get which of the given URLs are already in the database
+pub fn get_existing_urls(conn: &mut SqliteConnection, urls: &[&str]) -> Result<Vec<String>> {
+    bing_images::table
+        .filter(bing_images::url.eq_any(urls))
+        .select(bing_images::url)
+        .load(conn)
+        .map_err(Into::into)
 }
 
 /// Get an image by URL
@@ -61,11 +124,28 @@ pub fn get_image(conn: &mut SqliteConnection, url: &str) -> Result<Option<BingIm
         .map_err(Into::into)
 }
 
+/// Get an image by its row id
+pub fn get_image_by_id(conn: &mut SqliteConnection, id: i32) -> Result<Option<BingImage>> {
+    bing_images::table
+        .find(id)
+        .first(conn)
+        .optional()
+        .map_err(Into::into)
+}
+
+/// Newest publication first; images without a publication date fall back to fetch time
+pub fn by_publication_date() -> (
+    diesel::helper_types::Desc<bing_images::fullstartdate>,
+    diesel::helper_types::Desc<bing_images::fetched_at>,
+) {
+    (bing_images::fullstartdate.desc(), bing_images::fetched_at.desc())
+}
+
 /// Get all images with a specific status
 pub fn get_images_by_status(conn: &mut SqliteConnection, status: ImageStatus) -> Result<Vec<BingImage>> {
     bing_images::table
         .filter(bing_images::status.eq(status.as_str()))
-        .order(bing_images::fetched_at.desc())
+        .order(by_publication_date())
         .load(conn)
         .map_err(Into::into)
 }
@@ -79,31 +159,189 @@ pub fn get_images_by_market_code(
 ) -> Result<Vec<BingImage>> {
     bing_images::table
         .filter(bing_images::market_code.eq(market_code))
-        .order(bing_images::fetched_at.desc())
+        .order(by_publication_date())
         .limit(limit)
         .offset(offset)
         .load(conn)
         .map_err(Into::into)
 }
 
-/// Update image status
-pub fn update_image_status(conn: &mut SqliteConnection, url: &str, status: ImageStatus) -> Result<()> {
+/// Get all image URLs for a market code
+pub fn get_urls_by_market_code(conn: &mut SqliteConnection, market_code: &str) -> Result<Vec<String>> {
+    bing_images::table
+        .filter(bing_images::market_code.eq(market_code))
+        .select(bing_images::url)
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Mark image bytes as available locally
+pub fn mark_image_cached(conn: &mut SqliteConnection, url: &str, cached_at: i32) -> Result<()> {
     diesel::update(bing_images::table.filter(bing_images::url.eq(url)))
-        .set((
-            bing_images::status.eq(status.as_str()),
-            bing_images::updated_at.eq(current_timestamp()),
-        ))
+        .set(bing_images::cached_at.eq(cached_at))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Mark image bytes as no longer available locally (downloaded again when needed)
+pub fn clear_image_cached(conn: &mut SqliteConnection, url: &str) -> Result<()> {
+    diesel::update(bing_images::table.filter(bing_images::url.eq(url)))
+        .set(bing_images::cached_at.eq(None::<i32>))
         .execute(conn)?;
     Ok(())
 }
 
+/// Update image status, appending the transition to `status_log` as an automatic change
+pub fn update_image_status(conn: &mut SqliteConnection, url: &str, status: ImageStatus) -> Result<()> {
+    update_image_status_logged(conn, url, status, &StatusLogDetails::default())?;
+    Ok(())
+}
+
+/// Update image status and append the transition to `status_log`
+///
+/// Returns None (and logs nothing) for unknown images and for changes that
+/// neither change the status nor replace the wallpaper.
+pub fn update_image_status_logged(
+    conn: &mut SqliteConnection,
+    url: &str,
+    status: ImageStatus,
+    details: &StatusLogDetails,
+) -> Result<Option<StatusLogEntry>> {
+    conn.transaction(|conn| {
+        let old_status: Option<String> = bing_images::table
+            .filter(bing_images::url.eq(url))
+            .select(bing_images::status)
+            .first(conn)
+            .optional()?;
+        let Some(old_status) = old_status else {
+            return Ok(None);
+        };
+
+        let unchanged = old_status == status.as_str();
+        if unchanged && details.action == StatusLogAction::Change && details.replaced_wallpaper_url.is_none() {
+            return Ok(None);
+        }
+
+        let now = current_timestamp();
+        if !unchanged {
+            diesel::update(bing_images::table.filter(bing_images::url.eq(url)))
+                .set((
+                    bing_images::status.eq(status.as_str()),
+                    bing_images::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+
+        let entry = diesel::insert_into(status_log::table)
+            .values(&NewStatusLogEntry {
+                url,
+                old_status: &old_status,
+                new_status: status.as_str(),
+                changed_at: now,
+                action: details.action.as_str(),
+                source: details.source.map(|source| source.as_str()),
+                reverts_id: details.reverts_id,
+                replaced_wallpaper_url: details.replaced_wallpaper_url,
+            })
+            .returning(StatusLogEntry::as_returning())
+            .get_result(conn)?;
+        Ok(Some(entry))
+    })
+}
+
+/// Get the `status_log` entries of user actions (with a source), oldest first
+pub fn get_user_status_log(conn: &mut SqliteConnection) -> Result<Vec<StatusLogEntry>> {
+    status_log::table
+        .filter(status_log::source.is_not_null())
+        .order(status_log::id.asc())
+        .select(StatusLogEntry::as_select())
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Get the `status_log` entries of an image, oldest first
+pub fn get_status_log_for_image(conn: &mut SqliteConnection, url: &str) -> Result<Vec<StatusLogEntry>> {
+    status_log::table
+        .filter(status_log::url.eq(url))
+        .order(status_log::id.asc())
+        .select(StatusLogEntry::as_select())
+        .load(conn)
+        .map_err(Into::into)
+}
+
 /// Delete an image by URL
 pub fn delete_image(conn: &mut SqliteConnection, url: &str) -> Result<()> {
-    diesel::delete(bing_images::table.filter(bing_images::url.eq(url)))
+    conn.transaction(|conn| {
+        let id: Option<i32> = bing_images::table
+            .filter(bing_images::url.eq(url))
+            .select(bing_images::id)
+            .first(conn)
+            .optional()?;
+
+        // Promote the oldest near-duplicate so the rest stay linked to an existing row
+        if let Some(id) = id {
+            let duplicates = get_duplicate_ids(conn, id)?;
+            if let Some((&promoted, rest)) = duplicates.split_first() {
+                set_canonical_id(conn, promoted, None)?;
+                diesel::update(bing_images::table.filter(bing_images::id.eq_any(rest)))
+                    .set(bing_images::canonical_id.eq(promoted))
+                    .execute(conn)?;
+            }
+        }
+
+        diesel::delete(bing_images::table.filter(bing_images::url.eq(url)))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+/// Store the perceptual hash of an image
+pub fn set_image_phash(conn: &mut SqliteConnection, url: &str, phash: &str) -> Result<()> {
+    diesel::update(bing_images::table.filter(bing_images::url.eq(url)))
+        .set(bing_images::phash.eq(phash))
         .execute(conn)?;
     Ok(())
 }
 
+/// Get (id, phash) of every hashed canonical image
+pub fn get_canonical_hashes(conn: &mut SqliteConnection) -> Result<Vec<(i32, String)>> {
+    bing_images::table
+        .filter(bing_images::canonical_id.is_null())
+        .filter(bing_images::phash.is_not_null())
+        .order(bing_images::id.asc())
+        .select((bing_images::id, bing_images::phash.assume_not_null()))
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Link an image to the canonical row it duplicates (None makes it canonical)
+pub fn set_canonical_id(conn: &mut SqliteConnection, id: i32, canonical_id: Option<i32>) -> Result<()> {
+    diesel::update(bing_images::table.find(id))
+        .set(bing_images::canonical_id.eq(canonical_id))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Get ids of the near-duplicates linked to a canonical image (oldest first)
+pub fn get_duplicate_ids(conn: &mut SqliteConnection, canonical_id: i32) -> Result<Vec<i32>> {
+    bing_images::table
+        .filter(bing_images::canonical_id.eq(canonical_id))
+        .order(bing_images::id.asc())
+        .select(bing_images::id)
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Get cached images that have no perceptual hash yet
+pub fn get_unhashed_cached_images(conn: &mut SqliteConnection) -> Result<Vec<BingImage>> {
+    bing_images::table
+        .filter(bing_images::cached_at.is_not_null())
+        .filter(bing_images::phash.is_null())
+        .order(bing_images::id.asc())
+        .load(conn)
+        .map_err(Into::into)
+}
+
 /// Count images by status
 pub fn count_by_status(conn: &mut SqliteConnection, status: ImageStatus) -> Result<i64> {
     bing_images::table
@@ -122,6 +360,80 @@ pub fn count_by_market_code(conn: &mut SqliteConnection, market_code: &str) -> R
         .map_err(Into::into)
 }
 
+/// Joins the `bing_images_fts` index to the images; binds the MATCH query and the status twice
+const SEARCH_FROM: &str = "FROM bing_images_fts \
+    JOIN bing_images ON bing_images.id = bing_images_fts.rowid \
+    WHERE bing_images_fts MATCH ? AND (? IS NULL OR bing_images.status = ?)";
+
+#[derive(QueryableByName)]
+struct SearchCount {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    count: i64,
+}
+
+/// FTS5 query matching every word of a user query as a prefix
+///
+/// "ice fjord" -> `"ice"* "fjord"*`. Quotes are dropped, so user input can not
+/// form FTS5 syntax. None when the query has no words.
+pub fn search_match_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| term.replace('"', ""))
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"*", term))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Search titles, copyrights and locations, best match first
+///
+/// Title matches rank above location matches, which rank above the rest of the copyright.
+pub fn search_images(
+    conn: &mut SqliteConnection,
+    query: &str,
+    status: Option<ImageStatus>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<BingImage>> {
+    use diesel::sql_types::{BigInt, Nullable, Text};
+
+    let Some(match_query) = search_match_query(query) else {
+        return Ok(Vec::new());
+    };
+    let status = status.map(|status| status.as_str());
+
+    diesel::sql_query(format!(
+        "SELECT bing_images.* {} \
+         ORDER BY bm25(bing_images_fts, 10.0, 1.0, 5.0), bing_images.fullstartdate DESC, bing_images.fetched_at DESC \
+         LIMIT ? OFFSET ?",
+        SEARCH_FROM
+    ))
+    .bind::<Text, _>(&match_query)
+    .bind::<Nullable<Text>, _>(status)
+    .bind::<Nullable<Text>, _>(status)
+    .bind::<BigInt, _>(limit)
+    .bind::<BigInt, _>(offset)
+    .load(conn)
+    .map_err(Into::into)
+}
+
+/// Count search results (see `search_images`)
+pub fn count_search_results(conn: &mut SqliteConnection, query: &str, status: Option<ImageStatus>) -> Result<i64> {
+    use diesel::sql_types::{Nullable, Text};
+
+    let Some(match_query) = search_match_query(query) else {
+        return Ok(0);
+    };
+    let status = status.map(|status| status.as_str());
+
+    let result: SearchCount = diesel::sql_query(format!("SELECT COUNT(*) AS count {}", SEARCH_FROM))
+        .bind::<Text, _>(&match_query)
+        .bind::<Nullable<Text>, _>(status)
+        .bind::<Nullable<Text>, _>(status)
+        .get_result(conn)?;
+    Ok(result.count)
+}
+
 /// Get config value by key
 pub fn get_config(conn: &mut SqliteConnection, key: &str) -> Result<Option<String>> {
     config_kv::table
@@ -169,6 +481,14 @@ pub fn get_blacklisted_urls(conn: &mut SqliteConnection) -> Result<Vec<String>>
         .map_err(Into::into)
 }
 
+/// Get the URL and Bing `hsh` of every image
+pub fn get_urls_and_hashes(conn: &mut SqliteConnection) -> Result<Vec<(String, Option<String>)>> {
+    bing_images::table
+        .select((bing_images::url, bing_images::hsh))
+        .load(conn)
+        .map_err(Into::into)
+}
+
 /// Get historical page number
 pub fn get_historical_page(conn: &mut SqliteConnection) -> Result<usize> {
     Ok(get_config(conn, "historical_page")?
@@ -205,6 +525,22 @@ pub fn should_download_manifest(conn: &mut SqliteConnection, manifest_type: &str
     }
 }
 
+/// Set the star rating of an image (0 clears it)
+/// Returns false when the image is unknown.
+pub fn set_image_rating(conn: &mut SqliteConnection, url: &str, rating: i32) -> Result<bool> {
+    if !(0..=MAX_RATING).contains(&rating) {
+        anyhow::bail!("Rating must be between 0 and {}, got {}", MAX_RATING, rating);
+    }
+
+    let updated = diesel::update(bing_images::table.filter(bing_images::url.eq(url)))
+        .set((
+            bing_images::rating.eq(rating),
+            bing_images::updated_at.eq(current_timestamp()),
+        ))
+        .execute(conn)?;
+    Ok(updated > 0)
+}
+
 /// Update crop coordinates for an image
 pub fn update_crop_coords(
     conn: &mut SqliteConnection,
@@ -238,3 +574,443 @@ pub fn get_crop_coords(
         .map_err(Into::into)
         .map(|opt| opt.flatten())
 }
+
+/// Get all market codes, least recently used first
+pub fn get_market_codes(conn: &mut SqliteConnection) -> Result<Vec<MarketCode>> {
+    market_codes::table
+        .order((market_codes::last_used_at.asc(), market_codes::id.asc()))
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Insert market codes that are not in the table yet (existing rows keep their timestamps)
+pub fn ensure_market_codes(conn: &mut SqliteConnection, codes: &[&str]) -> Result<usize> {
+    let now = current_timestamp();
+    let mut inserted = 0;
+
+    for code in codes {
+        let new_market = NewMarketCode {
+            code,
+            last_used_at: 0,
+            created_at: now,
+            updated_at: now,
+        };
+        inserted += diesel::insert_or_ignore_into(market_codes::table)
+            .values(&new_market)
+            .execute(conn)?;
+    }
+
+    Ok(inserted)
+}
+
+/// Replace the market code list, keeping timestamps of markets that stay in the list
+pub fn set_market_codes(conn: &mut SqliteConnection, codes: &[&str]) -> Result<()> {
+    conn.transaction(|conn| {
+        diesel::delete(market_codes::table.filter(market_codes::code.ne_all(codes)))
+            .execute(conn)?;
+        ensure_market_codes(conn, codes)?;
+        Ok(())
+    })
+}
+
+/// Get the least recently used market code that was not used since `used_before`
+pub fn get_next_market_code(conn: &mut SqliteConnection, used_before: i32) -> Result<Option<MarketCode>> {
+    market_codes::table
+        .filter(market_codes::last_used_at.le(used_before))
+        .order((market_codes::last_used_at.asc(), market_codes::id.asc()))
+        .first(conn)
+        .optional()
+        .map_err(Into::into)
+}
+
+/// Record that a market code was just used
+pub fn touch_market_code(conn: &mut SqliteConnection, code: &str) -> Result<()> {
+    let now = current_timestamp();
+    diesel::update(market_codes::table.filter(market_codes::code.eq(code)))
+        .set((
+            market_codes::last_used_at.eq(now),
+            market_codes::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Record an earlier market visit, keeping a more recent one
+pub fn set_market_last_used(conn: &mut SqliteConnection, code: &str, last_used_at: i32) -> Result<()> {
+    diesel::update(
+        market_codes::table
+            .filter(market_codes::code.eq(code))
+            .filter(market_codes::last_used_at.lt(last_used_at)),
+    )
+    .set((
+        market_codes::last_used_at.eq(last_used_at),
+        market_codes::updated_at.eq(current_timestamp()),
+    ))
+    .execute(conn)?;
+    Ok(())
+}
+
+/// Get a market whose archive is partially walked (0 < page_offset < drained_offset)
+pub fn get_market_in_progress(conn: &mut SqliteConnection, drained_offset: i32) -> Result<Option<MarketCode>> {
+    market_codes::table
+        .filter(market_codes::page_offset.gt(0))
+        .filter(market_codes::page_offset.lt(drained_offset))
+        .order((market_codes::last_used_at.asc(), market_codes::id.asc()))
+        .first(conn)
+        .optional()
+        .map_err(Into::into)
+}
+
+/// Set how far a market's archive has been walked
+pub fn set_market_page_offset(conn: &mut SqliteConnection, code: &str, page_offset: i32) -> Result<()> {
+    diesel::update(market_codes::table.filter(market_codes::code.eq(code)))
+        .set((
+            market_codes::page_offset.eq(page_offset),
+            market_codes::updated_at.eq(current_timestamp()),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Append a wallpaper history entry
+pub fn insert_history_entry(conn: &mut SqliteConnection, entry: &NewWallpaperHistoryEntry) -> Result<WallpaperHistoryEntry> {
+    diesel::insert_into(wallpaper_history::table)
+        .values(entry)
+        .returning(WallpaperHistoryEntry::as_returning())
+        .get_result(conn)
+        .map_err(Into::into)
+}
+
+/// Get wallpaper history entries, newest first
+pub fn get_history_entries(conn: &mut SqliteConnection, offset: i64, limit: i64) -> Result<Vec<WallpaperHistoryEntry>> {
+    wallpaper_history::table
+        .order(wallpaper_history::id.desc())
+        .offset(offset)
+        .limit(limit)
+        .select(WallpaperHistoryEntry::as_select())
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Count wallpaper history entries
+pub fn count_history_entries(conn: &mut SqliteConnection) -> Result<i64> {
+    wallpaper_history::table
+        .count()
+        .get_result(conn)
+        .map_err(Into::into)
+}
+
+/// Get the newest history entry older than `before_id` showing another image than `url`
+pub fn get_history_entry_before(
+    conn: &mut SqliteConnection,
+    before_id: i32,
+    url: &str,
+) -> Result<Option<WallpaperHistoryEntry>> {
+    wallpaper_history::table
+        .filter(wallpaper_history::id.lt(before_id))
+        .filter(wallpaper_history::url.ne(url))
+        .order(wallpaper_history::id.desc())
+        .select(WallpaperHistoryEntry::as_select())
+        .first(conn)
+        .optional()
+        .map_err(Into::into)
+}
+
+/// Get (id, url, set_at) of every history entry, oldest first
+pub fn get_history_timeline(conn: &mut SqliteConnection) -> Result<Vec<(i32, String, i32)>> {
+    wallpaper_history::table
+        .order(wallpaper_history::id.asc())
+        .select((wallpaper_history::id, wallpaper_history::url, wallpaper_history::set_at))
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Count images per country (ISO code), most images first
+pub fn count_images_by_country(conn: &mut SqliteConnection) -> Result<Vec<(String, i64)>> {
+    use diesel::dsl::count_star;
+
+    let mut counts: Vec<(Option<String>, i64)> = bing_images::table
+        .filter(bing_images::country.is_not_null())
+        .group_by(bing_images::country)
+        .select((bing_images::country, count_star()))
+        .load(conn)?;
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(counts.into_iter().filter_map(|(country, count)| Some((country?, count))).collect())
+}
+
+/// Get a page of a country's images, newest publication first
+pub fn get_images_by_country(
+    conn: &mut SqliteConnection,
+    country: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<BingImage>> {
+    bing_images::table
+        .filter(bing_images::country.eq(country))
+        .order(by_publication_date())
+        .limit(limit)
+        .offset(offset)
+        .select(BingImage::as_select())
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Count the images of a country
+pub fn count_images_in_country(conn: &mut SqliteConnection, country: &str) -> Result<i64> {
+    bing_images::table
+        .filter(bing_images::country.eq(country))
+        .count()
+        .get_result(conn)
+        .map_err(Into::into)
+}
+
+/// Get when each image was last set as wallpaper (url -> set_at)
+pub fn get_last_shown_times(conn: &mut SqliteConnection) -> Result<std::collections::HashMap<String, i32>> {
+    use diesel::dsl::max;
+
+    let rows: Vec<(String, Option<i32>)> = wallpaper_history::table
+        .group_by(wallpaper_history::url)
+        .select((wallpaper_history::url, max(wallpaper_history::set_at)))
+        .load(conn)?;
+    Ok(rows.into_iter().filter_map(|(url, set_at)| Some((url, set_at?))).collect())
+}
+
+/// Trimmed tag or collection name (an empty name is an error)
+fn group_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("Name must not be empty");
+    }
+    Ok(name)
+}
+
+fn image_id(conn: &mut SqliteConnection, url: &str) -> QueryResult<Option<i32>> {
+    bing_images::table
+        .filter(bing_images::url.eq(url))
+        .select(bing_images::id)
+        .first(conn)
+        .optional()
+}
+
+/// Get the tag called `name`, creating it if needed (names are case-insensitive)
+pub fn create_tag(conn: &mut SqliteConnection, name: &str) -> Result<Tag> {
+    let name = group_name(name)?;
+    diesel::insert_into(tags::table)
+        .values(&NewTag { name, created_at: current_timestamp() })
+        .on_conflict(tags::name)
+        .do_nothing()
+        .execute(conn)?;
+
+    tags::table
+        .filter(tags::name.eq(name))
+        .select(Tag::as_select())
+        .first(conn)
+        .map_err(Into::into)
+}
+
+/// Get all tags, by name
+pub fn get_tags(conn: &mut SqliteConnection) -> Result<Vec<Tag>> {
+    tags::table
+        .order(tags::name.asc())
+        .select(Tag::as_select())
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Rename a tag
+pub fn rename_tag(conn: &mut SqliteConnection, tag_id: i32, name: &str) -> Result<()> {
+    let name = group_name(name)?;
+    diesel::update(tags::table.find(tag_id))
+        .set(tags::name.eq(name))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Delete a tag and remove it from every image
+pub fn delete_tag(conn: &mut SqliteConnection, tag_id: i32) -> Result<()> {
+    conn.transaction(|conn| {
+        diesel::delete(image_tags::table.filter(image_tags::tag_id.eq(tag_id))).execute(conn)?;
+        diesel::delete(tags::table.find(tag_id)).execute(conn)?;
+        Ok(())
+    })
+}
+
+/// Tag an image, creating the tag if needed
+/// Returns None when the image is unknown.
+pub fn tag_image(conn: &mut SqliteConnection, url: &str, name: &str) -> Result<Option<Tag>> {
+    conn.transaction(|conn| {
+        let Some(image_id) = image_id(conn, url)? else {
+            return Ok(None);
+        };
+        let tag = create_tag(conn, name)?;
+
+        diesel::insert_or_ignore_into(image_tags::table)
+            .values(&NewImageTag { image_id, tag_id: tag.id, created_at: current_timestamp() })
+            .execute(conn)?;
+        Ok(Some(tag))
+    })
+}
+
+/// Remove a tag from an image
+pub fn untag_image(conn: &mut SqliteConnection, url: &str, tag_id: i32) -> Result<()> {
+    if let Some(image_id) = image_id(conn, url)? {
+        diesel::delete(image_tags::table.find((image_id, tag_id))).execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Get the tags of an image, by name
+pub fn get_image_tags(conn: &mut SqliteConnection, url: &str) -> Result<Vec<Tag>> {
+    image_tags::table
+        .inner_join(tags::table)
+        .inner_join(bing_images::table)
+        .filter(bing_images::url.eq(url))
+        .order(tags::name.asc())
+        .select(Tag::as_select())
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Get the images with a tag, newest publication first
+pub fn get_images_by_tag(conn: &mut SqliteConnection, tag_id: i32) -> Result<Vec<BingImage>> {
+    image_tags::table
+        .inner_join(bing_images::table)
+        .filter(image_tags::tag_id.eq(tag_id))
+        .order(by_publication_date())
+        .select(BingImage::as_select())
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Get the collection called `name`, creating it if needed (names are case-insensitive)
+pub fn create_collection(conn: &mut SqliteConnection, name: &str) -> Result<Collection> {
+    let name = group_name(name)?;
+    let now = current_timestamp();
+    diesel::insert_into(collections::table)
+        .values(&NewCollection { name, created_at: now, updated_at: now })
+        .on_conflict(collections::name)
+        .do_nothing()
+        .execute(conn)?;
+
+    collections::table
+        .filter(collections::name.eq(name))
+        .select(Collection::as_select())
+        .first(conn)
+        .map_err(Into::into)
+}
+
+/// Get all collections, by name
+pub fn get_collections(conn: &mut SqliteConnection) -> Result<Vec<Collection>> {
+    collections::table
+        .order(collections::name.asc())
+        .select(Collection::as_select())
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Get a collection by id
+pub fn get_collection(conn: &mut SqliteConnection, collection_id: i32) -> Result<Option<Collection>> {
+    collections::table
+        .find(collection_id)
+        .select(Collection::as_select())
+        .first(conn)
+        .optional()
+        .map_err(Into::into)
+}
+
+/// Rename a collection
+pub fn rename_collection(conn: &mut SqliteConnection, collection_id: i32, name: &str) -> Result<()> {
+    let name = group_name(name)?;
+    diesel::update(collections::table.find(collection_id))
+        .set((collections::name.eq(name), collections::updated_at.eq(current_timestamp())))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Delete a collection (its images stay in the database)
+pub fn delete_collection(conn: &mut SqliteConnection, collection_id: i32) -> Result<()> {
+    conn.transaction(|conn| {
+        diesel::delete(collection_images::table.filter(collection_images::collection_id.eq(collection_id)))
+            .execute(conn)?;
+        diesel::delete(collections::table.find(collection_id)).execute(conn)?;
+        Ok(())
+    })
+}
+
+/// Add an image to a collection
+/// Returns false when the image is unknown.
+pub fn add_image_to_collection(conn: &mut SqliteConnection, collection_id: i32, url: &str) -> Result<bool> {
+    let Some(image_id) = image_id(conn, url)? else {
+        return Ok(false);
+    };
+
+    let now = current_timestamp();
+    diesel::insert_or_ignore_into(collection_images::table)
+        .values(&NewCollectionImage { collection_id, image_id, added_at: now })
+        .execute(conn)?;
+    diesel::update(collections::table.find(collection_id))
+        .set(collections::updated_at.eq(now))
+        .execute(conn)?;
+    Ok(true)
+}
+
+/// Remove an image from a collection
+pub fn remove_image_from_collection(conn: &mut SqliteConnection, collection_id: i32, url: &str) -> Result<()> {
+    if let Some(image_id) = image_id(conn, url)? {
+        diesel::delete(collection_images::table.find((collection_id, image_id))).execute(conn)?;
+        diesel::update(collections::table.find(collection_id))
+            .set(collections::updated_at.eq(current_timestamp()))
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Get a page of a collection's images, newest publication first
+pub fn get_collection_images(
+    conn: &mut SqliteConnection,
+    collection_id: i32,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<BingImage>> {
+    collection_images::table
+        .inner_join(bing_images::table)
+        .filter(collection_images::collection_id.eq(collection_id))
+        .order(by_publication_date())
+        .limit(limit)
+        .offset(offset)
+        .select(BingImage::as_select())
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Get a collection's images that are not blacklisted (rotation candidates)
+pub fn get_collection_rotation_images(conn: &mut SqliteConnection, collection_id: i32) -> Result<Vec<BingImage>> {
+    collection_images::table
+        .inner_join(bing_images::table)
+        .filter(collection_images::collection_id.eq(collection_id))
+        .filter(bing_images::status.ne(ImageStatus::Blacklisted.as_str()))
+        .select(BingImage::as_select())
+        .load(conn)
+        .map_err(Into::into)
+}
+
+/// Count the images in a collection
+pub fn count_collection_images(conn: &mut SqliteConnection, collection_id: i32) -> Result<i64> {
+    collection_images::table
+        .filter(collection_images::collection_id.eq(collection_id))
+        .count()
+        .get_result(conn)
+        .map_err(Into::into)
+}
+
+/// Get the collections an image is in, by name
+pub fn get_image_collections(conn: &mut SqliteConnection, url: &str) -> Result<Vec<Collection>> {
+    collection_images::table
+        .inner_join(collections::table)
+        .inner_join(bing_images::table)
+        .filter(bing_images::url.eq(url))
+        .order(collections::name.asc())
+        .select(Collection::as_select())
+        .load(conn)
+        .map_err(Into::into)
+}
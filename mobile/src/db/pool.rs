@@ -0,0 +1,126 @@
+//! Shared SQLite connection pool
+//!
+//! Opening a connection sets pragmas and checks migrations, so connections are
+//! kept and reused instead of being opened per call. Migrations run once, when
+//! the pool is opened, as do one-time data fixes. `DbPool::shared` hands out one pool per database file,
+//! so the ViewModel, cache manager, tray and CLI of a process share connections.
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Idle connections kept per pool (more are opened on demand and closed on return)
+pub const MAX_IDLE_CONNECTIONS: usize = 4;
+
+struct PoolInner {
+    db_path: PathBuf,
+    idle: Mutex<Vec<SqliteConnection>>,
+}
+
+/// Pool of connections to one database file (cheap to clone)
+#[derive(Clone)]
+pub struct DbPool {
+    inner: Arc<PoolInner>,
+}
+
+static SHARED_POOLS: Mutex<Option<HashMap<PathBuf, DbPool>>> = Mutex::new(None);
+
+impl DbPool {
+    /// Open a database, running pending migrations
+    pub fn open(db_path: &Path) -> Result<Self> {
+        let mut conn = super::establish_connection(db_path)?;
+
+        // Images stored before locations were parsed (once per pool, a no-op once done)
+        match super::operations::backfill_locations(&mut conn) {
+            Ok(0) => {}
+            Ok(count) => log::info!("Parsed the location of {} images", count),
+            Err(e) => log::warn!("Failed to parse image locations: {}", e),
+        }
+
+        // Commands only get a connection, they look up the image cache of its database here
+        let cache_dir = crate::paths::image_cache_dir_for(db_path)?;
+        let cache_dir = cache_dir
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("Image cache path is not valid UTF-8: {}", cache_dir.display()))?;
+        super::operations::set_config(&mut conn, super::IMAGE_CACHE_DIR_KEY, cache_dir)?;
+
+        Ok(Self {
+            inner: Arc::new(PoolInner {
+                db_path: db_path.to_path_buf(),
+                idle: Mutex::new(vec![conn]),
+            }),
+        })
+    }
+
+    /// Process-wide pool for a database file, opened on first use
+    pub fn shared(db_path: &Path) -> Result<Self> {
+        let mut pools = SHARED_POOLS.lock().unwrap();
+        let pools = pools.get_or_insert_with(HashMap::new);
+
+        if let Some(pool) = pools.get(db_path) {
+            return Ok(pool.clone());
+        }
+
+        let pool = Self::open(db_path)?;
+        pools.insert(db_path.to_path_buf(), pool.clone());
+        Ok(pool)
+    }
+
+    /// Database file of this pool
+    pub fn db_path(&self) -> &Path {
+        &self.inner.db_path
+    }
+
+    /// Number of open connections waiting to be reused
+    pub fn idle_count(&self) -> usize {
+        self.inner.idle.lock().unwrap().len()
+    }
+
+    /// Take an idle connection, or open a new one
+    pub fn get(&self) -> Result<PooledConnection> {
+        let idle = self.inner.idle.lock().unwrap().pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => super::open_connection(&self.inner.db_path)?,
+        };
+
+        Ok(PooledConnection {
+            conn: Some(conn),
+            pool: self.inner.clone(),
+        })
+    }
+}
+
+/// Connection borrowed from a `DbPool`, returned to it on drop
+pub struct PooledConnection {
+    conn: Option<SqliteConnection>,
+    pool: Arc<PoolInner>,
+}
+
+impl Deref for PooledConnection {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &SqliteConnection {
+        self.conn.as_ref().expect("connection present until drop")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut SqliteConnection {
+        self.conn.as_mut().expect("connection present until drop")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            let mut idle = self.pool.idle.lock().unwrap();
+            if idle.len() < MAX_IDLE_CONNECTIONS {
+                idle.push(conn);
+            }
+        }
+    }
+}
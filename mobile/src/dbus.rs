@@ -0,0 +1,205 @@
+//! D-Bus session service (Linux, tray and daemon modes)
+//!
+//! `org.nikescar.Bingtray` at `/org/nikescar/Bingtray` runs the wallpaper
+//! commands of the tray (see `crate::control`), exposes the current wallpaper
+//! and signals when it changes, e.g. for key bindings and panel widgets:
+//!
+//! ```text
+//! busctl --user call org.nikescar.Bingtray /org/nikescar/Bingtray org.nikescar.Bingtray Next
+//! busctl --user get-property org.nikescar.Bingtray /org/nikescar/Bingtray org.nikescar.Bingtray Title
+//! ```
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::Result;
+use zbus::object_server::SignalEmitter;
+use zbus::{fdo, interface, zvariant};
+
+use crate::control::{self, ControlCommand};
+use crate::db::{operations, BingImage, DbPool};
+
+/// Well-known name, also the interface name
+pub const BUS_NAME: &str = "org.nikescar.Bingtray";
+
+pub const OBJECT_PATH: &str = "/org/nikescar/Bingtray";
+
+/// How often the current wallpaper is checked for changes (made by any mode)
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The `org.nikescar.Bingtray` interface
+pub struct BingtrayInterface {
+    pool: DbPool,
+}
+
+impl BingtrayInterface {
+    /// Run a command on a worker thread: downloads would otherwise stall the bus connection
+    async fn run(&self, command: ControlCommand, nothing_done: &str) -> fdo::Result<String> {
+        let pool = self.pool.clone();
+        match smol::unblock(move || control::execute(&pool, command, false)).await {
+            Ok(Some(title)) => Ok(title),
+            Ok(None) => Err(fdo::Error::Failed(nothing_done.to_string())),
+            Err(e) => Err(fdo::Error::Failed(e.to_string())),
+        }
+    }
+
+    fn current(&self) -> fdo::Result<Option<BingImage>> {
+        current_image(&self.pool).map_err(|e| fdo::Error::Failed(e.to_string()))
+    }
+}
+
+#[interface(name = "org.nikescar.Bingtray")]
+impl BingtrayInterface {
+    /// Set the next new image, returns its title
+    async fn next(&self) -> fdo::Result<String> {
+        self.run(ControlCommand::Next, "No new image available").await
+    }
+
+    /// Go back to the wallpaper shown before
+    async fn previous(&self) -> fdo::Result<String> {
+        self.run(ControlCommand::Previous, "No earlier wallpaper in history").await
+    }
+
+    /// Keep the current wallpaper as a favorite
+    async fn keep(&self) -> fdo::Result<String> {
+        self.run(ControlCommand::Keep, "The current wallpaper is not from Bingtray").await
+    }
+
+    /// Blacklist the current wallpaper and set the next one
+    async fn blacklist(&self) -> fdo::Result<String> {
+        self.run(ControlCommand::Blacklist, "The current wallpaper is not from Bingtray").await
+    }
+
+    /// Set a kept wallpaper
+    async fn set_random_favorite(&self) -> fdo::Result<String> {
+        self.run(ControlCommand::RandomFavorite, "No favorites available").await
+    }
+
+    /// Title of the current wallpaper ("" when it is not from Bingtray)
+    #[zbus(property)]
+    fn title(&self) -> fdo::Result<String> {
+        Ok(self.current()?.map(|image| image.title).unwrap_or_default())
+    }
+
+    #[zbus(property)]
+    fn copyright(&self) -> fdo::Result<String> {
+        Ok(self.current()?.and_then(|image| image.copyright).unwrap_or_default())
+    }
+
+    #[zbus(property)]
+    fn url(&self) -> fdo::Result<String> {
+        Ok(self.current()?.map(|image| image.url).unwrap_or_default())
+    }
+
+    /// The wallpaper was changed (by any mode)
+    #[zbus(signal)]
+    pub async fn wallpaper_changed(emitter: &SignalEmitter<'_>, title: &str, url: &str) -> zbus::Result<()>;
+}
+
+/// Image of the wallpaper Bingtray set last
+fn current_image(pool: &DbPool) -> Result<Option<BingImage>> {
+    let mut conn = pool.get()?;
+    match operations::get_config(&mut conn, "current_wallpaper_url")? {
+        Some(url) => operations::get_image(&mut conn, &url),
+        None => Ok(None),
+    }
+}
+
+/// D-Bus service, released when dropped
+pub struct DbusService {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DbusService {
+    /// Own the name on the session bus, None when there is no bus or the name is taken
+    pub fn start(pool: DbPool) -> Option<Self> {
+        // Not the builder's name request: its flags let a second instance take the name over
+        let connection = zbus::blocking::connection::Builder::session()
+            .and_then(|builder| builder.serve_at(OBJECT_PATH, BingtrayInterface { pool: pool.clone() }))
+            .and_then(|builder| builder.build())
+            .and_then(|connection| {
+                connection.request_name_with_flags(BUS_NAME, fdo::RequestNameFlags::DoNotQueue.into())?;
+                Ok(connection)
+            });
+        let connection = match connection {
+            Ok(connection) => connection,
+            Err(e) => {
+                log::warn!("D-Bus service unavailable: {}", e);
+                return None;
+            }
+        };
+        log::info!("Serving {} on the session bus", BUS_NAME);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = std::thread::Builder::new()
+            .name("dbus-watch".to_string())
+            .spawn(move || watch(connection, pool, thread_stop))
+            .map_err(|e| log::error!("Failed to start the D-Bus service: {}", e))
+            .ok()?;
+        Some(Self { stop, thread: Some(thread) })
+    }
+}
+
+impl Drop for DbusService {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Signal wallpaper changes until stopped, the connection is closed on return
+fn watch(connection: zbus::blocking::Connection, pool: DbPool, stop: Arc<AtomicBool>) {
+    let mut last_url = current_image(&pool).ok().flatten().map(|image| image.url);
+
+    while !stop.load(Ordering::Relaxed) {
+        std::thread::park_timeout(WATCH_INTERVAL);
+
+        let image = match current_image(&pool) {
+            Ok(Some(image)) => image,
+            Ok(None) => continue,
+            Err(e) => {
+                log::warn!("D-Bus service failed to read the current wallpaper: {}", e);
+                continue;
+            }
+        };
+        if last_url.as_deref() == Some(image.url.as_str()) {
+            continue;
+        }
+        if let Err(e) = emit_changed(&connection, &image) {
+            log::warn!("Failed to signal the wallpaper change: {}", e);
+        }
+        last_url = Some(image.url);
+    }
+}
+
+fn emit_changed(connection: &zbus::blocking::Connection, image: &BingImage) -> zbus::Result<()> {
+    connection.emit_signal(
+        None::<&str>,
+        OBJECT_PATH,
+        BUS_NAME,
+        "WallpaperChanged",
+        &(image.title.as_str(), image.url.as_str()),
+    )?;
+
+    let copyright = image.copyright.clone().unwrap_or_default();
+    let changed: HashMap<&str, zvariant::Value> = HashMap::from([
+        ("Title", zvariant::Value::from(image.title.as_str())),
+        ("Copyright", zvariant::Value::from(copyright.as_str())),
+        ("Url", zvariant::Value::from(image.url.as_str())),
+    ]);
+    connection.emit_signal(
+        None::<&str>,
+        OBJECT_PATH,
+        "org.freedesktop.DBus.Properties",
+        "PropertiesChanged",
+        &(BUS_NAME, changed, Vec::<&str>::new()),
+    )
+}
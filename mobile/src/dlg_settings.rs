@@ -1,5 +1,7 @@
 use crate::Settings;
 use crate::LogLevel;
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+use crate::viewmodel::scheduler::{RotationPool, RotationSchedule};
 pub use crate::dlg_settings_stt::*;
 use eframe::egui;
 use egui_i18n::tr;
@@ -121,6 +123,83 @@ impl DlgSettings {
         }
     }
 
+    /// Interval, times of day, login and pool of automatic rotation
+    #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+    fn show_rotation_schedule(&mut self, ui: &mut egui::Ui, schedule: &mut RotationSchedule) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label(tr!("schedule"));
+            ui.weak(tr!("schedule-hint"));
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            let mut every = schedule.interval_minutes > 0;
+            ui.checkbox(&mut every, tr!("schedule-every"));
+            let factor = if self.schedule_in_hours { 60 } else { 1 };
+            let mut count = (schedule.interval_minutes / factor).max(1);
+            ui.add_enabled(every, egui::DragValue::new(&mut count).range(1..=999));
+            egui::ComboBox::from_id_salt("schedule_unit")
+                .selected_text(if self.schedule_in_hours { tr!("schedule-hours") } else { tr!("schedule-minutes") })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.schedule_in_hours, false, tr!("schedule-minutes"));
+                    ui.selectable_value(&mut self.schedule_in_hours, true, tr!("schedule-hours"));
+                });
+            let factor = if self.schedule_in_hours { 60 } else { 1 };
+            schedule.interval_minutes = if every { count * factor } else { 0 };
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label(tr!("schedule-at"));
+            let mut removed = None;
+            for (i, time) in schedule.times.iter().enumerate() {
+                if ui.button(format!("{} ✕", time.format("%H:%M")))
+                    .on_hover_text(tr!("schedule-remove-time"))
+                    .clicked()
+                {
+                    removed = Some(i);
+                }
+            }
+            if let Some(i) = removed {
+                schedule.times.remove(i);
+            }
+
+            ui.add(egui::DragValue::new(&mut self.schedule_hour).range(0..=23).custom_formatter(|n, _| format!("{:02}", n)));
+            ui.label(":");
+            ui.add(egui::DragValue::new(&mut self.schedule_minute).range(0..=59).custom_formatter(|n, _| format!("{:02}", n)));
+            if ui.button(tr!("schedule-add-time")).clicked() {
+                if let Some(time) = chrono::NaiveTime::from_hms_opt(self.schedule_hour, self.schedule_minute, 0) {
+                    schedule.add_time(time);
+                }
+            }
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            ui.checkbox(&mut schedule.on_login, tr!("schedule-on-login"));
+            ui.add_space(8.0);
+            ui.label(tr!("schedule-pool"));
+            let pool_label = |pool: RotationPool| match pool {
+                RotationPool::NewImages => tr!("schedule-pool-new"),
+                RotationPool::Favorites => tr!("schedule-pool-favorites"),
+                RotationPool::Collection(id) => self
+                    .collections
+                    .iter()
+                    .find(|(collection_id, _)| *collection_id == id)
+                    .map(|(_, name)| name.clone())
+                    // Deleted collections rotate favorites
+                    .unwrap_or_else(|| tr!("schedule-pool-favorites")),
+            };
+            egui::ComboBox::from_id_salt("schedule_pool")
+                .selected_text(pool_label(schedule.pool))
+                .show_ui(ui, |ui| {
+                    let pools = [RotationPool::NewImages, RotationPool::Favorites]
+                        .into_iter()
+                        .chain(self.collections.iter().map(|(id, _)| RotationPool::Collection(*id)));
+                    for pool in pools {
+                        ui.selectable_value(&mut schedule.pool, pool, pool_label(pool));
+                    }
+                });
+        });
+    }
+
     pub fn show(
         &mut self,
         ctx: &egui::Context,
@@ -276,6 +355,40 @@ impl DlgSettings {
                         });
                         ui.add_space(8.0);
 
+                        // Wallpaper Resolution
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label(tr!("wallpaper-resolution"));
+                            let resolutions = [
+                                ("auto", tr!("wallpaper-resolution-auto")),
+                                ("UHD", tr!("wallpaper-resolution-uhd")),
+                                ("1920x1080", tr!("wallpaper-resolution-1080p")),
+                                ("1080x1920", tr!("wallpaper-resolution-portrait")),
+                            ];
+                            let selected_label = resolutions
+                                .iter()
+                                .find(|(value, _)| *value == settings.image_resolution)
+                                .map(|(_, label)| label.clone())
+                                .unwrap_or_else(|| settings.image_resolution.clone());
+                            egui::ComboBox::from_id_salt("wallpaper_resolution")
+                                .selected_text(selected_label)
+                                .show_ui(ui, |ui| {
+                                    for (value, label) in &resolutions {
+                                        ui.selectable_value(
+                                            &mut settings.image_resolution,
+                                            value.to_string(),
+                                            label,
+                                        );
+                                    }
+                                });
+                        });
+                        ui.add_space(8.0);
+
+                        #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+                        {
+                            self.show_rotation_schedule(ui, &mut settings.rotation_schedule);
+                            ui.add_space(8.0);
+                        }
+
                         // Display Size + Color Mode + Theme
                         ui.horizontal_wrapped(|ui| {
                             ui.label(tr!("display-size"));
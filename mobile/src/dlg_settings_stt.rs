@@ -21,6 +21,12 @@ pub struct DlgSettings {
     pub selected_font_display: String,
     pub system_fonts: Vec<(String, String)>,
     pub system_fonts_loaded: bool,
+    // Rotation schedule state
+    pub schedule_in_hours: bool,
+    pub schedule_hour: u32,
+    pub schedule_minute: u32,
+    /// Collections offered as rotation pools (id, name)
+    pub collections: Vec<(i32, String)>,
     // Action results
     pub save_clicked: bool,
     pub theme_to_apply: Option<String>,
@@ -49,6 +55,10 @@ impl Default for DlgSettings {
             selected_font_display: "Default (NotoSansKr)".to_string(),
             system_fonts: Vec::new(),
             system_fonts_loaded: false,
+            schedule_in_hours: true,
+            schedule_hour: 8,
+            schedule_minute: 0,
+            collections: Vec::new(),
             save_clicked: false,
             theme_to_apply: None,
         }
@@ -6,7 +6,7 @@
 //! - Checking for updates
 //! - Downloading and applying updates
 
-use crate::install_stt::{GitHubRelease, InstallPaths, InstallResult, InstallStatus, UpdateInfo};
+use crate::install_stt::{GitHubRelease, InstallOptions, InstallPaths, InstallResult, InstallStatus, UpdateInfo};
 use std::env;
 use std::fs;
 use std::io;
@@ -259,6 +259,13 @@ pub fn get_install_paths() -> InstallPaths {
                     .join(format!("{}.desktop", APP_NAME)),
             ),
             uninstall_key: None,
+            systemd_unit: Some(
+                dirs::config_dir()
+                    .unwrap_or_else(|| home.join(".config"))
+                    .join("systemd")
+                    .join("user")
+                    .join(format!("{}.service", APP_NAME)),
+            ),
         }
     }
 
@@ -270,6 +277,7 @@ pub fn get_install_paths() -> InstallPaths {
             desktop_shortcut: None, // macOS doesn't use desktop shortcuts
             start_menu_entry: None, // App bundle is self-contained
             uninstall_key: None,
+            systemd_unit: None,
         }
     }
 
@@ -294,6 +302,7 @@ pub fn get_install_paths() -> InstallPaths {
                 "HKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\{}",
                 APP_NAME
             )),
+            systemd_unit: None,
         }
     }
 
@@ -304,6 +313,7 @@ pub fn get_install_paths() -> InstallPaths {
             desktop_shortcut: None,
             start_menu_entry: None,
             uninstall_key: None,
+            systemd_unit: None,
         }
     }
 }
@@ -478,6 +488,12 @@ fn check_windows_registry(_paths: &InstallPaths) -> bool {
 
 /// Install the application
 pub fn do_install() -> InstallResult {
+    do_install_with(InstallOptions::default())
+}
+
+/// Install the application with optional parts
+#[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+pub fn do_install_with(options: InstallOptions) -> InstallResult {
     let paths = get_install_paths();
     #[cfg_attr(any(target_os = "android", target_arch = "wasm32"), allow(unused_variables))]
     let current_exe = match env::current_exe() {
@@ -495,7 +511,7 @@ pub fn do_install() -> InstallResult {
 
     #[cfg(target_os = "linux")]
     {
-        match install_linux(&paths, &current_exe) {
+        match install_linux(&paths, &current_exe, options) {
             Ok(msg) => InstallResult::Success(msg),
             Err(e) => InstallResult::Error(e),
         }
@@ -524,7 +540,7 @@ pub fn do_install() -> InstallResult {
 }
 
 #[cfg(target_os = "linux")]
-fn install_linux(paths: &InstallPaths, current_exe: &PathBuf) -> Result<String, String> {
+fn install_linux(paths: &InstallPaths, current_exe: &PathBuf, options: InstallOptions) -> Result<String, String> {
     log::info!("Starting Linux installation...");
     log::info!("Current exe: {}", current_exe.display());
     log::info!("Target directory: {}", paths.bin_dir.display());
@@ -601,8 +617,76 @@ Keywords=bing;wallpaper;bingtray;
         }
     }
 
+    let mut message = format!("Successfully installed to {}", binary_dest.display());
+    if options.systemd_service {
+        if let Some(ref unit) = paths.systemd_unit {
+            write_systemd_unit(unit, &binary_dest)?;
+            match systemctl_user(&["enable", "--now", &format!("{}.service", APP_NAME)]) {
+                Ok(()) => message.push_str(&format!("\nEnabled the {} user service", APP_NAME)),
+                Err(e) => {
+                    log::warn!("Failed to enable the user service: {}", e);
+                    message.push_str(&format!(
+                        "\nWrote {}, enable it with: systemctl --user enable --now {}.service",
+                        unit.display(),
+                        APP_NAME
+                    ));
+                }
+            }
+        }
+    }
+
     log::info!("Installation completed successfully");
-    Ok(format!("Successfully installed to {}", binary_dest.display()))
+    Ok(message)
+}
+
+/// Write the systemd user unit running `binary --daemon`
+#[cfg(target_os = "linux")]
+fn write_systemd_unit(unit: &Path, binary: &Path) -> Result<(), String> {
+    let content = format!(
+        r#"[Unit]
+Description=Bingtray wallpaper rotation
+PartOf=graphical-session.target
+After=graphical-session.target
+
+[Service]
+Type=simple
+ExecStart="{}" --daemon
+Restart=on-failure
+RestartSec=30
+
+[Install]
+WantedBy=graphical-session.target
+"#,
+        binary.display()
+    );
+
+    log::info!("Writing systemd user unit: {}", unit.display());
+    if let Some(parent) = unit.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create systemd user directory: {}", e))?;
+    }
+    fs::write(unit, content).map_err(|e| format!("Failed to write systemd user unit: {}", e))?;
+
+    // Make the user manager see the new or changed unit
+    if let Err(e) = systemctl_user(&["daemon-reload"]) {
+        log::warn!("Failed to reload the systemd user manager: {}", e);
+    }
+    Ok(())
+}
+
+/// Run `systemctl --user ARGS`
+#[cfg(target_os = "linux")]
+fn systemctl_user(args: &[&str]) -> Result<(), String> {
+    let output = std::process::Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run systemctl: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
 }
 
 #[cfg(target_os = "macos")]
@@ -943,6 +1027,17 @@ fn uninstall_linux(paths: &InstallPaths) -> Result<String, String> {
         let _ = fs::remove_file(desktop);
     }
 
+    // Stop and remove the user service
+    if let Some(ref unit) = paths.systemd_unit {
+        if unit.exists() {
+            if let Err(e) = systemctl_user(&["disable", "--now", &format!("{}.service", APP_NAME)]) {
+                log::warn!("Failed to stop the user service: {}", e);
+            }
+            fs::remove_file(unit).map_err(|e| format!("Failed to remove systemd user unit: {}", e))?;
+            let _ = systemctl_user(&["daemon-reload"]);
+        }
+    }
+
     Ok("Successfully uninstalled Bingtray".to_string())
 }
 
@@ -1501,6 +1596,15 @@ Keywords=android;debloat;shizuku;adb;
             }
 
             log::info!(".desktop files updated successfully");
+
+            // Point an installed user service at the new binary
+            if let Some(ref unit) = paths.systemd_unit {
+                if unit.exists() {
+                    if let Err(e) = write_systemd_unit(unit, &dest) {
+                        log::warn!("Failed to update systemd user unit: {}", e);
+                    }
+                }
+            }
         }
     }
 
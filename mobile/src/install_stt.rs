@@ -56,4 +56,13 @@ pub struct InstallPaths {
     pub start_menu_entry: Option<std::path::PathBuf>,
     /// Uninstall registry key (Windows only)
     pub uninstall_key: Option<String>,
+    /// systemd user unit running `--daemon` (Linux only)
+    pub systemd_unit: Option<std::path::PathBuf>,
+}
+
+/// Optional parts of an installation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InstallOptions {
+    /// Write and enable a systemd user service running `--daemon` (Linux only)
+    pub systemd_service: bool,
 }
@@ -2,20 +2,17 @@
 #![allow(clippy::manual_range_contains)]
 
 use anyhow::Result;
-#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
-use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
-#[cfg(not(target_os = "android"))]
-use directories::ProjectDirs;
-
 // Database
 #[cfg(not(target_arch = "wasm32"))]
 pub mod schema;
 
 pub mod db;
+pub mod location;
+pub mod paths;
 pub mod viewmodel;
 
 // Core modules
@@ -40,6 +37,14 @@ pub mod api_setwallpaper;
 #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
 pub mod cli;
 #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub mod cli_commands;
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub mod control;
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub mod daemon;
+#[cfg(target_os = "linux")]
+pub mod dbus;
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
 pub mod tray;
 
 // Android-only modules
@@ -73,8 +78,9 @@ impl Config {
         #[cfg(target_os = "android")]
         {
             // Android-specific paths
-            let config_dir = PathBuf::from("/data/data/pe.nikescar.bingtray/files");
-            let cache_dir = PathBuf::from("/data/data/pe.nikescar.bingtray/cache");
+            let paths = paths::data_paths()?;
+            let config_dir = paths.config_dir;
+            let cache_dir = paths.cache_dir;
 
             log::info!(
                 "Android config paths - config_dir: {:?}, cache_dir: {:?}",
@@ -122,12 +128,10 @@ impl Config {
 
         #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
         {
-            // Desktop platforms (Linux, Windows, macOS)
-            let proj_dirs = ProjectDirs::from("pe", "nikescar", "bingtray")
-                .context("Failed to get project directories")?;
-
-            let config_dir = proj_dirs.config_dir().to_path_buf();
-            let cache_dir = proj_dirs.cache_dir().to_path_buf();
+            // Desktop platforms (Linux, Windows, macOS), same locations as the database
+            let paths = paths::data_paths()?;
+            let config_dir = paths.config_dir;
+            let cache_dir = paths.cache_dir;
 
             let unprocessed_dir = cache_dir.join("unprocessed");
             let keepfavorite_dir = cache_dir.join("keepfavorite");
@@ -154,12 +158,79 @@ impl Config {
 }
 
 /// Bing image record from API
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BingImage {
     pub url: String,
     pub title: String,
     pub copyright: Option<String>,
+    #[serde(alias = "copyrightlink")]
     pub copyright_link: Option<String>,
+    /// Market the image was fetched from (e.g. "en-US"), if known
+    #[serde(default)]
+    pub market_code: Option<String>,
+    /// First day the image was shown (YYYYMMDD)
+    #[serde(default)]
+    pub startdate: Option<String>,
+    /// Publication time (YYYYMMDDHHMM)
+    #[serde(default)]
+    pub fullstartdate: Option<String>,
+    /// Day the image was replaced (YYYYMMDD)
+    #[serde(default)]
+    pub enddate: Option<String>,
+    /// URL without resolution suffix, e.g. "/th?id=OHR.Name_EN-US123"
+    #[serde(default)]
+    pub urlbase: Option<String>,
+    #[serde(default)]
+    pub hsh: Option<String>,
+    /// Bing homepage quiz link
+    #[serde(default)]
+    pub quiz: Option<String>,
+}
+
+impl BingImage {
+    /// Fill in the title from the copyright text when Bing sends none
+    ///
+    /// Some markets leave `title` empty (or "Info") and put the caption in
+    /// `copyright` as "Caption (© Credit)".
+    pub fn normalize_title(mut self) -> Self {
+        let placeholder = self.title.trim().is_empty() || self.title.trim() == "Info";
+        if placeholder {
+            if let Some(copyright) = &self.copyright {
+                let caption = copyright.split("(©").next().unwrap_or(copyright).trim();
+                if !caption.is_empty() {
+                    self.title = caption.to_string();
+                }
+            }
+        }
+        self
+    }
+
+    /// Fields that go into `bing_images` as a new row
+    pub fn to_new_record<'a>(
+        &'a self,
+        market_code: &'a str,
+        status: &'a str,
+        fetched_at: i32,
+        now: i32,
+    ) -> crate::db::models::NewBingImage<'a> {
+        crate::db::models::NewBingImage {
+            url: &self.url,
+            title: &self.title,
+            copyright: self.copyright.as_deref(),
+            copyright_link: self.copyright_link.as_deref(),
+            market_code,
+            fetched_at,
+            status,
+            created_at: now,
+            updated_at: now,
+            startdate: self.startdate.as_deref(),
+            fullstartdate: self.fullstartdate.as_deref(),
+            enddate: self.enddate.as_deref(),
+            urlbase: self.urlbase.as_deref(),
+            hsh: self.hsh.as_deref(),
+            quiz: self.quiz.as_deref(),
+        }
+    }
 }
 
 /// Bing API response
@@ -235,6 +306,12 @@ pub struct Settings {
     pub unsafe_app_remove: bool,
     #[serde(default)]
     pub autoupdate: bool,
+    /// Wallpaper download size: "auto", "UHD", "1920x1080" or "1080x1920"
+    #[serde(default = "default_image_resolution")]
+    pub image_resolution: String,
+    /// Automatic rotation schedule (lives in the database, shared with the tray)
+    #[serde(skip)]
+    pub rotation_schedule: crate::viewmodel::scheduler::RotationSchedule,
 }
 
 impl Default for Settings {
@@ -259,6 +336,8 @@ impl Default for Settings {
             theme_name: default_theme_name(),
             unsafe_app_remove: false,
             autoupdate: false,
+            image_resolution: default_image_resolution(),
+            rotation_schedule: Default::default(),
         }
     }
 }
@@ -275,6 +354,10 @@ fn default_theme_mode() -> String {
     "Auto".to_string()
 }
 
+fn default_image_resolution() -> String {
+    "auto".to_string()
+}
+
 fn default_display_size() -> String {
     "Desktop (1024x768)".to_string()
 }
@@ -0,0 +1,140 @@
+//! Place and country extraction from image copyrights
+//!
+//! Bing copyrights start with the place: "Hnausapollur, Iceland (© ...)". The
+//! text before the photographer credit is split on commas and its parts are
+//! matched against the bundled country table (`resources/countries.tsv`), which
+//! lists the spellings of every Bing market and regions such as US states.
+//! Everything runs offline.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const COUNTRY_TABLE: &str = include_str!("../resources/countries.tsv");
+
+/// A country of the bundled table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Country {
+    /// ISO 3166-1 alpha-2 code
+    pub code: &'static str,
+    /// English name
+    pub name: &'static str,
+}
+
+/// Place and country of an image
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    /// Place text without the country ("" when the copyright names no place)
+    pub place: String,
+    /// Country code (None when no part names a known country or region)
+    pub country: Option<&'static str>,
+}
+
+struct CountryTable {
+    countries: Vec<Country>,
+    /// Lowercase name -> (country code, whether the name is a region of it)
+    names: HashMap<String, (&'static str, bool)>,
+}
+
+fn table() -> &'static CountryTable {
+    static TABLE: OnceLock<CountryTable> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut countries = Vec::new();
+        let mut names = HashMap::new();
+
+        for line in COUNTRY_TABLE.lines() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((code, spellings)) = line.split_once('\t') else {
+                continue;
+            };
+            let (code, region) = match code.strip_prefix('>') {
+                Some(code) => (code, true),
+                None => (code, false),
+            };
+
+            for (i, name) in spellings.split('|').enumerate() {
+                if i == 0 && !region {
+                    countries.push(Country { code, name });
+                }
+                names.entry(normalize(name)).or_insert((code, region));
+            }
+        }
+
+        countries.sort_by_key(|country| country.name);
+        CountryTable { countries, names }
+    })
+}
+
+fn normalize(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// All countries of the bundled table, by English name
+pub fn countries() -> &'static [Country] {
+    &table().countries
+}
+
+/// Country with an ISO code
+pub fn country(code: &str) -> Option<&'static Country> {
+    countries().iter().find(|country| country.code.eq_ignore_ascii_case(code))
+}
+
+/// Code of the country a name (any spelling) or region name belongs to
+/// The flag is true for regions, e.g. "Utah" -> ("US", true).
+pub fn lookup_country(name: &str) -> Option<(&'static str, bool)> {
+    table().names.get(&normalize(name)).copied()
+}
+
+/// Text naming the place: the copyright before the credit, or the title when
+/// the copyright is only a credit (GitHub archive rows split them that way)
+pub fn location_text<'a>(title: &'a str, copyright: Option<&'a str>) -> Option<&'a str> {
+    let copyright = copyright?.trim();
+    let text = if copyright.starts_with('©') {
+        title.trim()
+    } else {
+        let end = copyright
+            .find("(©")
+            .or_else(|| copyright.find("（©"))
+            .or_else(|| copyright.rfind(" ("))
+            .unwrap_or(copyright.len());
+        copyright[..end].trim()
+    };
+    Some(text).filter(|text| !text.is_empty())
+}
+
+/// Extract the place and country of an image
+pub fn parse_location(title: &str, copyright: Option<&str>) -> Location {
+    let Some(text) = location_text(title, copyright) else {
+        return Location::default();
+    };
+    let parts: Vec<&str> = text
+        .split([',', '，', '、'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    // The country usually comes last ("Place, Country"), first in Chinese ("国家，地点")
+    let last = parts.len().saturating_sub(1);
+    let order = std::iter::once(last).chain(std::iter::once(0)).chain((1..last).rev());
+    let found = order
+        .filter(|&i| i < parts.len())
+        .find_map(|i| lookup_country(parts[i]).map(|(code, region)| (i, code, region)));
+
+    let place = match found {
+        // Regions stay part of the place ("Arches National Park, Utah")
+        Some((i, _, false)) if parts.len() > 1 => parts
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .map(|(_, part)| *part)
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => parts.join(", "),
+    };
+
+    Location {
+        place,
+        country: found.map(|(_, code, _)| code),
+    }
+}
@@ -3,13 +3,21 @@
 //! Mode selection logic:
 //! - `--gui` flag: Runs GUI mode
 //! - `--tray` flag: Runs tray mode
-//! - `--silent` flag: Performs silent installation and exits (desktop only)
+//! - `--daemon` flag: Runs rotation and cache refills headless, logging to a file
+//! - `--silent` flag: Performs silent installation and exits (desktop only);
+//!   with `--service`, also installs a systemd user service running `--daemon` (Linux)
 //! - `--uninstall` flag: Performs uninstallation and exits (desktop only)
+//! - A command (`next`, `keep`, `list`, `help`, ...): Runs it and exits, see `bingtray::cli_commands`
 //! - Terminal detected: Runs CLI mode
-//! - Default (double-click): Runs tray mode
+//! - Default (double-click): Runs tray mode, or opens the app of the tray already running
 //!
 //! Logging options:
-//! - `--log [FILE]`: Enable file logging (defaults to bingtray.log if FILE not specified)
+//! - `--log [FILE]`: Enable file logging (defaults to bingtray.log if FILE not specified;
+//!   `--daemon` always logs to a file, `bingtray-daemon.log` in the data directory)
+//!
+//! Data location options (see `bingtray::paths`):
+//! - `--data-dir PATH`: Keep the database and caches in PATH (overrides `BINGTRAY_HOME`)
+//! - `--portable`: Keep everything in `bingtray-data/` next to the executable
 
 #![cfg_attr(target_os = "windows", windows_subsystem = "windows")]
 #![cfg(not(target_os = "android"))]
@@ -64,6 +72,24 @@ fn main() -> Result<()> {
         log_file_opt
     };
 
+    // Data location overrides must be set before anything opens the database (or the daemon log)
+    if let Some(data_dir) = parse_data_dir(&args) {
+        bingtray::paths::set_data_dir(data_dir.into());
+    }
+    if args.iter().any(|arg| arg == "--portable") {
+        bingtray::paths::set_portable(true);
+    }
+
+    // Subcommands run once and exit, see bingtray::cli_commands
+    let invocation = bingtray::cli_commands::parse_args(&args).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, bingtray::cli_commands::USAGE);
+        std::process::exit(bingtray::cli_commands::EXIT_USAGE);
+    });
+
+    // The daemon logs to its data directory unless --log names a file
+    let daemon = args.iter().any(|arg| arg == "--daemon");
+    let log_file = log_file.or_else(|| daemon.then(daemon_log_path).flatten());
+
     // Initialize logger
     if let Some(log_path) = &log_file {
         // File-based logging
@@ -88,8 +114,9 @@ fn main() -> Result<()> {
             .target(env_logger::Target::Pipe(target))
             .init();
     } else {
-        // Console logging (default)
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+        // Console logging (default), only warnings next to the output of subcommands
+        let default_filter = if invocation.is_some() { "warn" } else { "info" };
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter)).init();
     }
 
     log::info!("Bingtray v{} starting...", env!("CARGO_PKG_VERSION"));
@@ -99,6 +126,11 @@ fn main() -> Result<()> {
         log::error!("Failed to initialize i18n: {}", e);
     }
 
+    if let Some(invocation) = invocation {
+        log::info!("Running subcommand {:?}", invocation.command);
+        std::process::exit(bingtray::cli_commands::run(&invocation));
+    }
+
     // Initialize global tray event handlers (one-time setup, Linux only for GTK backend)
     #[cfg(target_os = "linux")]
     bingtray::tray::init_tray_event_handlers();
@@ -136,7 +168,10 @@ fn main() -> Result<()> {
 
         use bingtray::install_stt::InstallResult;
 
-        match bingtray::install::do_install() {
+        let options = bingtray::install_stt::InstallOptions {
+            systemd_service: args.iter().any(|arg| arg == "--service"),
+        };
+        match bingtray::install::do_install_with(options) {
             InstallResult::Success(msg) => {
                 log::info!("Silent installation succeeded: {}", msg);
                 println!("{}", msg);
@@ -150,7 +185,16 @@ fn main() -> Result<()> {
         }
     }
 
-    if force_gui {
+    if daemon {
+        // Daemon mode (explicitly requested via --daemon flag)
+        log::info!("Running in daemon mode (--daemon flag)");
+
+        // Hide console window on Windows
+        #[cfg(target_os = "windows")]
+        hide_console();
+
+        bingtray::daemon::run_daemon_mode()?;
+    } else if force_gui {
         // GUI mode (explicitly requested via --gui flag)
         log::info!("Running in GUI mode (--gui flag)");
 
@@ -167,21 +211,7 @@ fn main() -> Result<()> {
         #[cfg(target_os = "windows")]
         hide_console();
 
-        // Run tray mode
-        loop {
-            log::info!("*** Calling run_tray_mode() ***");
-            match bingtray::tray::run_tray_mode()? {
-                bingtray::tray::TrayExitAction::Quit => {
-                    log::info!("*** Received Quit action, exiting application ***");
-                    break;
-                }
-                bingtray::tray::TrayExitAction::OpenGui => {
-                    log::info!("*** Received OpenGui action, opening GUI window on main thread ***");
-                    run_gui_mode()?;
-                    log::info!("*** GUI closed, will return to tray mode ***");
-                }
-            }
-        }
+        run_tray_loop(false)?;
     } else if std::io::stdout().is_terminal() {
         // Terminal mode - run CLI interface
         log::info!("Running in CLI mode (terminal detected)");
@@ -194,24 +224,82 @@ fn main() -> Result<()> {
         #[cfg(target_os = "windows")]
         hide_console();
 
-        // Run tray mode
-        loop {
-            log::info!("*** Calling run_tray_mode() ***");
-            match bingtray::tray::run_tray_mode()? {
-                bingtray::tray::TrayExitAction::Quit => {
-                    log::info!("*** Received Quit action, exiting application ***");
-                    break;
-                }
-                bingtray::tray::TrayExitAction::OpenGui => {
-                    log::info!("*** Received OpenGui action, opening GUI window on main thread ***");
-                    run_gui_mode()?;
-                    log::info!("*** GUI closed, will return to tray mode ***");
-                }
+        // Launching again shows the app of the running instance
+        run_tray_loop(true)?;
+    }
+
+    Ok(())
+}
+
+/// Default log file of the daemon (None when the data directory is unavailable)
+fn daemon_log_path() -> Option<String> {
+    let paths = bingtray::paths::data_paths().ok()?;
+    Some(paths.config_dir.join(bingtray::daemon::DAEMON_LOG_FILE).to_string_lossy().into_owned())
+}
+
+/// Value of `--data-dir PATH` or `--data-dir=PATH`
+fn parse_data_dir(args: &[String]) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--data-dir" {
+            return iter.next().filter(|value| !value.starts_with("--")).cloned();
+        }
+        if let Some(value) = arg.strip_prefix("--data-dir=") {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Run tray mode, opening the GUI from it, until Quit
+///
+/// Only one tray (or daemon) runs per database: when one is already running,
+/// this returns at once, asking it to open its GUI with `open_gui_if_running`.
+/// The rotation scheduler, the control server and the D-Bus service run for
+/// the whole session, also while the GUI is open.
+fn run_tray_loop(open_gui_if_running: bool) -> Result<()> {
+    let pool = bingtray::db::DbPool::shared(&bingtray::db::get_database_path()?)?;
+    let Some(_control) = bingtray::control::ControlServer::start(pool.clone(), true)? else {
+        log::info!("Bingtray is already running");
+        if !(open_gui_if_running && forward_open_gui()) {
+            println!("Bingtray is already running");
+        }
+        return Ok(());
+    };
+    #[cfg(target_os = "linux")]
+    let _dbus = bingtray::dbus::DbusService::start(pool.clone());
+    let _scheduler = bingtray::viewmodel::scheduler::Scheduler::start(pool);
+
+    loop {
+        log::info!("*** Calling run_tray_mode() ***");
+        match bingtray::tray::run_tray_mode()? {
+            bingtray::tray::TrayExitAction::Quit => {
+                log::info!("*** Received Quit action, exiting application ***");
+                return Ok(());
+            }
+            bingtray::tray::TrayExitAction::OpenGui => {
+                log::info!("*** Received OpenGui action, opening GUI window on main thread ***");
+                run_gui_mode()?;
+                log::info!("*** GUI closed, will return to tray mode ***");
             }
         }
     }
+}
 
-    Ok(())
+/// Ask a running tray to open its GUI
+/// Returns false when no tray is running (or only the daemon, which has no GUI).
+fn forward_open_gui() -> bool {
+    match bingtray::control::send(bingtray::control::ControlCommand::OpenGui) {
+        Some(Ok(_)) => {
+            log::info!("Opened the GUI of the running instance");
+            true
+        }
+        Some(Err(e)) => {
+            log::info!("Running instance cannot open the GUI: {}", e);
+            false
+        }
+        None => false,
+    }
 }
 
 fn run_gui_mode() -> Result<()> {
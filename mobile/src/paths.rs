@@ -0,0 +1,285 @@
+//! Data locations shared by every mode (GUI, tray, CLI)
+//!
+//! The data root is picked, in order, from:
+//! 1. `--data-dir PATH`
+//! 2. the `BINGTRAY_HOME` environment variable
+//! 3. portable mode (`--portable`, or a `portable` file next to the executable),
+//!    which keeps everything in `bingtray-data/` next to the executable
+//! 4. the platform default (`ProjectDirs` config and cache directories)
+//!
+//! With a data root, the database lives in the root and caches in `root/cache`.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Environment variable overriding the data root
+pub const DATA_DIR_ENV: &str = "BINGTRAY_HOME";
+
+/// File next to the executable that turns on portable mode
+pub const PORTABLE_MARKER: &str = "portable";
+
+/// Directory next to the executable used in portable mode
+pub const PORTABLE_DIR: &str = "bingtray-data";
+
+/// Database file name inside the config directory
+pub const DATABASE_FILE: &str = "bingtray.db";
+
+/// Resolved data locations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataPaths {
+    /// Database and settings
+    pub config_dir: PathBuf,
+    /// Downloaded images and HTTP cache
+    pub cache_dir: PathBuf,
+}
+
+impl DataPaths {
+    /// Layout inside an explicit data root
+    pub fn in_root(root: &Path) -> Self {
+        Self {
+            config_dir: root.to_path_buf(),
+            cache_dir: root.join("cache"),
+        }
+    }
+
+    pub fn db_path(&self) -> PathBuf {
+        self.config_dir.join(DATABASE_FILE)
+    }
+
+    /// Wallpaper image cache
+    pub fn image_cache_dir(&self) -> PathBuf {
+        self.cache_dir.join("images")
+    }
+
+    fn create_dirs(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.config_dir)?;
+        std::fs::create_dir_all(self.image_cache_dir())?;
+        Ok(())
+    }
+}
+
+struct Overrides {
+    data_dir: Option<PathBuf>,
+    portable: bool,
+    resolved: Option<DataPaths>,
+}
+
+static OVERRIDES: Mutex<Overrides> = Mutex::new(Overrides {
+    data_dir: None,
+    portable: false,
+    resolved: None,
+});
+
+/// Use this directory as data root (`--data-dir`); call before the first `data_paths()`
+pub fn set_data_dir(dir: PathBuf) {
+    let mut overrides = OVERRIDES.lock().unwrap();
+    overrides.data_dir = Some(dir);
+    overrides.resolved = None;
+}
+
+/// Keep all data next to the executable (`--portable`); call before the first `data_paths()`
+pub fn set_portable(portable: bool) {
+    let mut overrides = OVERRIDES.lock().unwrap();
+    overrides.portable = portable;
+    overrides.resolved = None;
+}
+
+/// Pick the data root from the flag, environment and portable mode (None = platform default)
+pub fn resolve_data_root(
+    data_dir: Option<&Path>,
+    home_env: Option<&Path>,
+    portable: bool,
+    exe_dir: Option<&Path>,
+) -> Option<PathBuf> {
+    if let Some(dir) = data_dir {
+        return Some(dir.to_path_buf());
+    }
+    if let Some(home) = home_env.filter(|home| !home.as_os_str().is_empty()) {
+        return Some(home.to_path_buf());
+    }
+
+    let exe_dir = exe_dir?;
+    if portable || exe_dir.join(PORTABLE_MARKER).exists() {
+        return Some(exe_dir.join(PORTABLE_DIR));
+    }
+    None
+}
+
+/// Data locations for this process (resolved, created and migrated on first use)
+pub fn data_paths() -> Result<DataPaths> {
+    let mut overrides = OVERRIDES.lock().unwrap();
+    if let Some(paths) = &overrides.resolved {
+        return Ok(paths.clone());
+    }
+
+    let paths = resolve(&overrides)?;
+    log::info!("Data directory: {:?}, cache directory: {:?}", paths.config_dir, paths.cache_dir);
+    overrides.resolved = Some(paths.clone());
+    Ok(paths)
+}
+
+#[cfg(target_os = "android")]
+fn resolve(_overrides: &Overrides) -> Result<DataPaths> {
+    let paths = DataPaths {
+        config_dir: PathBuf::from("/data/data/pe.nikescar.bingtray/files"),
+        cache_dir: PathBuf::from("/data/data/pe.nikescar.bingtray/cache"),
+    };
+    paths.create_dirs()?;
+    Ok(paths)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn resolve(_overrides: &Overrides) -> Result<DataPaths> {
+    // WASM: No filesystem
+    Ok(DataPaths {
+        config_dir: PathBuf::new(),
+        cache_dir: PathBuf::new(),
+    })
+}
+
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+fn resolve(overrides: &Overrides) -> Result<DataPaths> {
+    let home_env = std::env::var_os(DATA_DIR_ENV).map(PathBuf::from);
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+
+    let root = resolve_data_root(
+        overrides.data_dir.as_deref(),
+        home_env.as_deref(),
+        overrides.portable,
+        exe_dir.as_deref(),
+    );
+
+    let migrate = root.is_none();
+    let paths = match root {
+        Some(root) => DataPaths::in_root(&root),
+        None => {
+            let proj_dirs = directories::ProjectDirs::from("pe", "nikescar", "bingtray")
+                .ok_or_else(|| anyhow::anyhow!("Failed to get project directories"))?;
+            DataPaths {
+                config_dir: proj_dirs.config_dir().to_path_buf(),
+                cache_dir: proj_dirs.cache_dir().to_path_buf(),
+            }
+        }
+    };
+    paths.create_dirs()?;
+
+    // Explicit data roots start fresh; the default location picks up older databases
+    if migrate {
+        if let Err(e) = migrate_legacy_database(&paths.db_path(), &legacy_database_paths()) {
+            log::warn!("Failed to migrate database from an old location: {}", e);
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Databases written by older versions (tray, CLI) outside the default location
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+fn legacy_database_paths() -> Vec<PathBuf> {
+    legacy_config_dirs()
+        .into_iter()
+        .map(|dir| dir.join(DATABASE_FILE))
+        .collect()
+}
+
+/// Config directories used by older versions (tray, CLI)
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+fn legacy_config_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(proj_dirs) = directories::ProjectDirs::from("com", "nikescar", "bingtray") {
+        dirs.push(proj_dirs.config_dir().to_path_buf());
+    }
+    if let Some(config_dir) = dirs::config_dir() {
+        dirs.push(config_dir.join("bingtray"));
+    }
+    dirs
+}
+
+#[cfg(any(target_os = "android", target_arch = "wasm32"))]
+fn legacy_config_dirs() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+/// Copy the most recently used old database to `target` if there is none yet
+///
+/// The old files are left in place. Returns the database that was copied.
+pub fn migrate_legacy_database(target: &Path, candidates: &[PathBuf]) -> Result<Option<PathBuf>> {
+    if target.exists() {
+        return Ok(None);
+    }
+
+    let newest = candidates
+        .iter()
+        .filter(|candidate| candidate.as_path() != target && candidate.is_file())
+        .filter_map(|candidate| {
+            let modified = std::fs::metadata(candidate).and_then(|m| m.modified()).ok()?;
+            Some((modified, candidate))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, candidate)| candidate.clone());
+
+    let source = match newest {
+        Some(source) => source,
+        None => return Ok(None),
+    };
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // Uncheckpointed WAL content lives next to the database
+    for suffix in ["-wal", "-shm"] {
+        let sidecar = PathBuf::from(format!("{}{}", source.display(), suffix));
+        if sidecar.exists() {
+            std::fs::copy(&sidecar, format!("{}{}", target.display(), suffix))?;
+        }
+    }
+    std::fs::copy(&source, target)?;
+
+    log::info!("Migrated database from {:?} to {:?}", source, target);
+    Ok(Some(source))
+}
+
+/// Image cache belonging to a database
+/// The app database uses the resolved cache directory; any other database
+/// (tests, explicit paths) keeps its images in `<db dir>/cache/images`.
+pub fn image_cache_dir_for(db_path: &Path) -> Result<PathBuf> {
+    // Only compare with already resolved paths, never resolve (and migrate) from here
+    let resolved = OVERRIDES.lock().unwrap().resolved.clone();
+    if let Some(paths) = resolved.filter(|paths| paths.db_path() == db_path) {
+        return Ok(paths.image_cache_dir());
+    }
+
+    Ok(db_path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Invalid db_path"))?
+        .join("cache")
+        .join("images"))
+}
+
+/// Directories that may hold the file-based layout of older versions
+/// (`unprocessed/`, `keepfavorite/`, `blacklist.conf`, `marketcodes.conf`)
+///
+/// Like `image_cache_dir_for`, only the app database looks at the data and old
+/// config directories; any other database uses its own directory.
+pub fn legacy_layout_roots_for(db_path: &Path) -> Result<Vec<PathBuf>> {
+    let resolved = OVERRIDES.lock().unwrap().resolved.clone();
+    let mut roots = match resolved.filter(|paths| paths.db_path() == db_path) {
+        Some(paths) => {
+            let mut roots = vec![paths.config_dir, paths.cache_dir];
+            roots.extend(legacy_config_dirs());
+            roots
+        }
+        None => vec![db_path
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Invalid db_path"))?
+            .to_path_buf()],
+    };
+
+    let mut seen = std::collections::HashSet::new();
+    roots.retain(|root| seen.insert(root.clone()));
+    Ok(roots)
+}
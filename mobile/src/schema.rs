@@ -14,6 +14,34 @@ diesel::table! {
         updated_at -> Integer,
         cached_at -> Nullable<Integer>,
         crop_coords -> Nullable<Text>,
+        startdate -> Nullable<Text>,
+        fullstartdate -> Nullable<Text>,
+        enddate -> Nullable<Text>,
+        urlbase -> Nullable<Text>,
+        hsh -> Nullable<Text>,
+        quiz -> Nullable<Text>,
+        phash -> Nullable<Text>,
+        canonical_id -> Nullable<Integer>,
+        rating -> Integer,
+        place -> Nullable<Text>,
+        country -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    collection_images (collection_id, image_id) {
+        collection_id -> Integer,
+        image_id -> Integer,
+        added_at -> Integer,
+    }
+}
+
+diesel::table! {
+    collections (id) {
+        id -> Integer,
+        name -> Text,
+        created_at -> Integer,
+        updated_at -> Integer,
     }
 }
 
@@ -27,18 +55,71 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    image_tags (image_id, tag_id) {
+        image_id -> Integer,
+        tag_id -> Integer,
+        created_at -> Integer,
+    }
+}
+
 diesel::table! {
     market_codes (id) {
         id -> Integer,
         code -> Text,
         last_used_at -> Integer,
         created_at -> Integer,
-        updated_at -> Integer
+        updated_at -> Integer,
+        page_offset -> Integer,
+    }
+}
+
+diesel::table! {
+    status_log (id) {
+        id -> Integer,
+        url -> Text,
+        old_status -> Text,
+        new_status -> Text,
+        changed_at -> Integer,
+        action -> Text,
+        source -> Nullable<Text>,
+        reverts_id -> Nullable<Integer>,
+        replaced_wallpaper_url -> Nullable<Text>,
     }
 }
 
+diesel::table! {
+    tags (id) {
+        id -> Integer,
+        name -> Text,
+        created_at -> Integer,
+    }
+}
+
+diesel::table! {
+    wallpaper_history (id) {
+        id -> Integer,
+        url -> Text,
+        set_at -> Integer,
+        source -> Text,
+        monitor -> Nullable<Text>,
+        crop_coords -> Nullable<Text>,
+    }
+}
+
+diesel::joinable!(collection_images -> bing_images (image_id));
+diesel::joinable!(collection_images -> collections (collection_id));
+diesel::joinable!(image_tags -> bing_images (image_id));
+diesel::joinable!(image_tags -> tags (tag_id));
+
 diesel::allow_tables_to_appear_in_same_query!(
     bing_images,
+    collection_images,
+    collections,
     config_kv,
+    image_tags,
     market_codes,
+    status_log,
+    tags,
+    wallpaper_history,
 );
@@ -9,7 +9,7 @@ use tao::{
     platform::run_return::EventLoopExtRunReturn,
 };
 use tray_icon::{
-    menu::{Menu, MenuEvent, MenuItem, MenuId},
+    menu::{Menu, MenuEvent, MenuItem, MenuId, Submenu},
     Icon, TrayIconBuilder, TrayIcon, TrayIconEvent,
 };
 
@@ -91,6 +91,24 @@ impl TrayBackend for GtkTrayBackend {
                                 update_tray_menu(icon, &mut self.logic, &mut menu_items.as_mut().unwrap());
                             }
                         }
+                    } else if let Some(rating) = items.rate.iter().position(|id| menu_event.id == *id) {
+                        if self.logic.rate_current_image(rating as i32).is_ok() {
+                            if let Some(ref icon) = tray_icon {
+                                update_tray_menu(icon, &mut self.logic, &mut menu_items.as_mut().unwrap());
+                            }
+                        }
+                    } else if menu_event.id == items.undo {
+                        if self.logic.undo_last_action().is_ok() {
+                            if let Some(ref icon) = tray_icon {
+                                update_tray_menu(icon, &mut self.logic, &mut menu_items.as_mut().unwrap());
+                            }
+                        }
+                    } else if menu_event.id == items.redo {
+                        if self.logic.redo().is_ok() {
+                            if let Some(ref icon) = tray_icon {
+                                update_tray_menu(icon, &mut self.logic, &mut menu_items.as_mut().unwrap());
+                            }
+                        }
                     } else if menu_event.id == items.quit {
                         *control_flow = ControlFlow::Exit;
                     }
@@ -112,6 +130,15 @@ impl TrayBackend for GtkTrayBackend {
 
                         tray_icon = Some(new_tray_icon);
                         menu_items = Some(items);
+                    } else if crate::control::take_open_gui_request() {
+                        // Another invocation asked for the app
+                        *exit_action_for_return.lock().unwrap() = TrayExitAction::OpenGui;
+                        *control_flow = ControlFlow::Exit;
+                    } else if crate::viewmodel::scheduler::take_rotated() | crate::control::take_changed() {
+                        // The scheduler or a control command changed the wallpaper shown in the menu
+                        if let Some(ref icon) = tray_icon {
+                            update_tray_menu(icon, &mut self.logic, &mut menu_items.as_mut().unwrap());
+                        }
                     }
 
                     std::thread::sleep(std::time::Duration::from_millis(50));
@@ -146,6 +173,10 @@ struct MenuItems {
     keep_current: MenuId,
     blacklist_current: MenuId,
     random_favorite: MenuId,
+    /// Rating items, indexed by star count (0 clears)
+    rate: Vec<MenuId>,
+    undo: MenuId,
+    redo: MenuId,
     quit: MenuId,
 }
 
@@ -202,6 +233,27 @@ fn create_tray_menu(logic: &mut TrayLogic) -> (Menu, MenuItems) {
         None,
     );
 
+    // Rating submenu, the current rating is marked
+    let current_rating = logic.current_rating();
+    let rate = Submenu::new(format!("{}", tr!("tray-rate")), current_rating.is_some());
+    let rate_items: Vec<MenuItem> = (0..=crate::db::models::MAX_RATING)
+        .map(|rating| {
+            let mark = if current_rating == Some(rating) { "✓ " } else { "" };
+            let label = if rating == 0 {
+                format!("{}{}", mark, tr!("tray-rate-clear"))
+            } else {
+                format!("{}{}", mark, crate::db::models::rating_stars(rating))
+            };
+            MenuItem::new(label, true, None)
+        })
+        .collect();
+    for item in &rate_items {
+        rate.append(item).ok();
+    }
+
+    let undo = MenuItem::new(format!("{}", tr!("tray-undo")), logic.can_undo(), None);
+    let redo = MenuItem::new(format!("{}", tr!("tray-redo")), logic.can_redo(), None);
+
     let quit = MenuItem::new(format!("{}", tr!("tray-quit")), true, None);
 
     let menu_items = MenuItems {
@@ -212,6 +264,9 @@ fn create_tray_menu(logic: &mut TrayLogic) -> (Menu, MenuItems) {
         keep_current: keep_current.id().clone(),
         blacklist_current: blacklist_current.id().clone(),
         random_favorite: random_favorite.id().clone(),
+        rate: rate_items.iter().map(|item| item.id().clone()).collect(),
+        undo: undo.id().clone(),
+        redo: redo.id().clone(),
         quit: quit.id().clone(),
     };
 
@@ -223,6 +278,9 @@ fn create_tray_menu(logic: &mut TrayLogic) -> (Menu, MenuItems) {
     menu.append(&keep_current).ok();
     menu.append(&blacklist_current).ok();
     menu.append(&random_favorite).ok();
+    menu.append(&rate).ok();
+    menu.append(&undo).ok();
+    menu.append(&redo).ok();
     menu.append(&MenuItem::new("", false, None)).ok();
     menu.append(&quit).ok();
 
@@ -142,6 +142,11 @@ impl XEmbedTrayBackend {
                 return Ok(TrayExitAction::Quit);
             }
 
+            // Another invocation asked for the app
+            if crate::control::take_open_gui_request() {
+                open_gui(&gui_process)?;
+            }
+
             // Poll for events (non-blocking)
             let event = match conn.poll_for_event()? {
                 Some(event) => event,
@@ -161,52 +166,7 @@ impl XEmbedTrayBackend {
                         1 | 3 => {
                             // Left or right click - open/focus GUI
                             log::info!("Click on tray icon");
-
-                            let mut process_guard = gui_process.lock().unwrap();
-
-                            // Check if GUI is already running
-                            let is_running = if let Some(ref mut child) = *process_guard {
-                                match child.try_wait() {
-                                    Ok(Some(_)) => {
-                                        // Process exited
-                                        log::info!("GUI process exited");
-                                        false
-                                    }
-                                    Ok(None) => {
-                                        // Process still running
-                                        log::info!("GUI already running, attempting to focus");
-                                        true
-                                    }
-                                    Err(e) => {
-                                        log::error!("Error checking GUI process: {}", e);
-                                        false
-                                    }
-                                }
-                            } else {
-                                false
-                            };
-
-                            if is_running {
-                                // Try to focus the existing window using wmctrl or xdotool
-                                // For now, just log - window should auto-focus when activated
-                                let _ = std::process::Command::new("wmctrl")
-                                    .args(&["-a", "BingTray"])
-                                    .spawn();
-                            } else {
-                                // Spawn new GUI process
-                                match std::process::Command::new(std::env::current_exe()?)
-                                    .arg("--gui")
-                                    .spawn()
-                                {
-                                    Ok(child) => {
-                                        log::info!("Spawned GUI process");
-                                        *process_guard = Some(child);
-                                    }
-                                    Err(e) => {
-                                        log::error!("Failed to spawn GUI: {}", e);
-                                    }
-                                }
-                            }
+                            open_gui(&gui_process)?;
                         }
                         _ => {}
                     }
@@ -328,3 +288,53 @@ pub fn rgba_to_x11_format(rgba: &RgbaImage) -> Vec<u8> {
         .flat_map(|p| [p[2], p[1], p[0]]) // RGBA -> BGR (drop alpha)
         .collect()
 }
+
+/// Focus the GUI process, or spawn it when it is not running
+fn open_gui(gui_process: &Mutex<Option<Child>>) -> Result<()> {
+    let mut process_guard = gui_process.lock().unwrap();
+
+    // Check if GUI is already running
+    let is_running = if let Some(ref mut child) = *process_guard {
+        match child.try_wait() {
+            Ok(Some(_)) => {
+                // Process exited
+                log::info!("GUI process exited");
+                false
+            }
+            Ok(None) => {
+                // Process still running
+                log::info!("GUI already running, attempting to focus");
+                true
+            }
+            Err(e) => {
+                log::error!("Error checking GUI process: {}", e);
+                false
+            }
+        }
+    } else {
+        false
+    };
+
+    if is_running {
+        // Try to focus the existing window using wmctrl or xdotool
+        // For now, just log - window should auto-focus when activated
+        let _ = std::process::Command::new("wmctrl")
+            .args(&["-a", "BingTray"])
+            .spawn();
+    } else {
+        // Spawn new GUI process
+        match std::process::Command::new(std::env::current_exe()?)
+            .arg("--gui")
+            .spawn()
+        {
+            Ok(child) => {
+                log::info!("Spawned GUI process");
+                *process_guard = Some(child);
+            }
+            Err(e) => {
+                log::error!("Failed to spawn GUI: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
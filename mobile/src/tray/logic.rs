@@ -2,26 +2,45 @@
 
 use anyhow::Result;
 
+/// Cloning shares the connection pool, it never reopens the database
+#[derive(Clone)]
 pub struct TrayLogic {
-    conn: diesel::SqliteConnection,
+    pool: crate::db::DbPool,
 }
 
 impl TrayLogic {
     pub fn new() -> Result<Self> {
-        use diesel::Connection;
         let db_path = crate::db::get_database_path()?;
-        let mut conn = diesel::SqliteConnection::establish(&db_path.to_string_lossy())?;
+        let pool = crate::db::DbPool::shared(&db_path)?;
 
-        // Run migrations
-        use diesel_migrations::MigrationHarness;
-        conn.run_pending_migrations(crate::db::MIGRATIONS)
-            .map_err(|e| anyhow::anyhow!("Migration failed: {}", e))?;
+        let logic = Self { pool };
+        logic.import_legacy_layout();
+        Ok(logic)
+    }
+
+    /// Import favorites, blacklist and markets of older releases (once, logged)
+    fn import_legacy_layout(&self) {
+        use crate::viewmodel::legacy_import::import_legacy_layout_once_sync;
+
+        let result = crate::paths::legacy_layout_roots_for(self.pool.db_path())
+            .and_then(|roots| import_legacy_layout_once_sync(&mut *self.conn()?, &roots));
+        if let Err(e) = result {
+            log::error!("Failed to import files of an older version: {}", e);
+        }
+    }
 
-        Ok(Self { conn })
+    fn conn(&self) -> Result<crate::db::PooledConnection> {
+        self.pool.get().map_err(|e| {
+            log::error!("Tray has no database connection: {}", e);
+            e
+        })
     }
 
     pub fn get_wallpaper_page_status(&mut self) -> String {
-        match crate::db::operations::count_by_status(&mut self.conn, crate::db::ImageStatus::Unprocessed) {
+        let Ok(mut conn) = self.conn() else {
+            return String::new();
+        };
+        match crate::db::operations::count_by_status(&mut conn, crate::db::ImageStatus::Unprocessed) {
             Ok(count) => format!("({} available)", count),
             Err(_) => String::new(),
         }
@@ -34,8 +53,11 @@ impl TrayLogic {
     pub fn get_current_image_title(&mut self) -> String {
         use crate::viewmodel::commands::get_current_desktop_wallpaper_url_sync;
 
-        if let Ok(Some(url)) = get_current_desktop_wallpaper_url_sync(&mut self.conn) {
-            if let Ok(Some(image)) = crate::db::operations::get_image(&mut self.conn, &url) {
+        let Ok(mut conn) = self.conn() else {
+            return String::new();
+        };
+        if let Ok(Some(url)) = get_current_desktop_wallpaper_url_sync(&mut conn) {
+            if let Ok(Some(image)) = crate::db::operations::get_image(&mut conn, &url) {
                 let title = &image.title;
                 if title.len() > 40 {
                     format!("{}...", &title[..40])
@@ -53,8 +75,11 @@ impl TrayLogic {
     pub fn can_keep(&mut self) -> bool {
         use crate::viewmodel::commands::get_current_desktop_wallpaper_url_sync;
 
-        if let Ok(Some(url)) = get_current_desktop_wallpaper_url_sync(&mut self.conn) {
-            if let Ok(Some(image)) = crate::db::operations::get_image(&mut self.conn, &url) {
+        let Ok(mut conn) = self.conn() else {
+            return false;
+        };
+        if let Ok(Some(url)) = get_current_desktop_wallpaper_url_sync(&mut conn) {
+            if let Ok(Some(image)) = crate::db::operations::get_image(&mut conn, &url) {
                 image.status != crate::db::ImageStatus::KeepFavorite.as_str()
             } else {
                 false
@@ -66,11 +91,17 @@ impl TrayLogic {
 
     pub fn can_blacklist(&mut self) -> bool {
         use crate::viewmodel::commands::get_current_desktop_wallpaper_url_sync;
-        get_current_desktop_wallpaper_url_sync(&mut self.conn).ok().flatten().is_some()
+        let Ok(mut conn) = self.conn() else {
+            return false;
+        };
+        get_current_desktop_wallpaper_url_sync(&mut conn).ok().flatten().is_some()
     }
 
     pub fn has_kept_wallpapers(&mut self) -> bool {
-        crate::db::operations::count_by_status(&mut self.conn, crate::db::ImageStatus::KeepFavorite)
+        let Ok(mut conn) = self.conn() else {
+            return false;
+        };
+        crate::db::operations::count_by_status(&mut conn, crate::db::ImageStatus::KeepFavorite)
             .map(|count| count > 0)
             .unwrap_or(false)
     }
@@ -107,8 +138,12 @@ impl TrayLogic {
     pub fn set_next_market_wallpaper(&mut self) -> Result<bool> {
         use crate::viewmodel::commands::download_and_set_next_wallpaper_sync;
 
-        match download_and_set_next_wallpaper_sync(&mut self.conn) {
-            Ok(_result) => Ok(true),
+        match download_and_set_next_wallpaper_sync(&mut *self.conn()?, crate::db::WallpaperSource::Tray) {
+            Ok(_result) => {
+                // The first download fills the database older files are matched against
+                self.import_legacy_layout();
+                Ok(true)
+            }
             Err(e) => {
                 log::error!("Failed to set next wallpaper: {}", e);
                 Err(e)
@@ -119,7 +154,7 @@ impl TrayLogic {
     pub fn keep_current_image(&mut self) -> Result<()> {
         use crate::viewmodel::commands::keep_current_wallpaper_sync;
 
-        if let Some(_title) = keep_current_wallpaper_sync(&mut self.conn)? {
+        if let Some(_title) = keep_current_wallpaper_sync(&mut *self.conn()?, crate::db::WallpaperSource::Tray)? {
             log::info!("Kept current image");
             Ok(())
         } else {
@@ -130,7 +165,7 @@ impl TrayLogic {
     pub fn blacklist_current_image(&mut self) -> Result<()> {
         use crate::viewmodel::commands::blacklist_current_wallpaper_sync;
 
-        if let Some(_title) = blacklist_current_wallpaper_sync(&mut self.conn)? {
+        if let Some(_title) = blacklist_current_wallpaper_sync(&mut *self.conn()?, crate::db::WallpaperSource::Tray)? {
             log::info!("Blacklisted current image");
             Ok(())
         } else {
@@ -138,10 +173,69 @@ impl TrayLogic {
         }
     }
 
+    /// Star rating of the current wallpaper (None if it is not from BingTray)
+    pub fn current_rating(&mut self) -> Option<i32> {
+        use crate::viewmodel::commands::get_current_wallpaper_rating_sync;
+
+        let mut conn = self.conn().ok()?;
+        get_current_wallpaper_rating_sync(&mut conn).ok().flatten()
+    }
+
+    /// Set the star rating of the current wallpaper (0 clears it)
+    pub fn rate_current_image(&mut self, rating: i32) -> Result<()> {
+        use crate::viewmodel::commands::rate_current_wallpaper_sync;
+
+        if let Some(title) = rate_current_wallpaper_sync(&mut *self.conn()?, rating)? {
+            log::info!("Rated {}: {} stars", title, rating);
+            Ok(())
+        } else {
+            anyhow::bail!("No current wallpaper to rate")
+        }
+    }
+
+    pub fn can_undo(&mut self) -> bool {
+        let Ok(mut conn) = self.conn() else {
+            return false;
+        };
+        crate::viewmodel::undo::can_undo_sync(&mut conn).unwrap_or(false)
+    }
+
+    pub fn can_redo(&mut self) -> bool {
+        let Ok(mut conn) = self.conn() else {
+            return false;
+        };
+        crate::viewmodel::undo::can_redo_sync(&mut conn).unwrap_or(false)
+    }
+
+    /// Undo the latest keep/blacklist, putting back the wallpaper it replaced
+    pub fn undo_last_action(&mut self) -> Result<()> {
+        use crate::viewmodel::undo::undo_last_action_sync;
+
+        match undo_last_action_sync(&mut *self.conn()?, crate::db::WallpaperSource::Tray)? {
+            Some(outcome) => {
+                log::info!("Undid status change of {}", outcome.title);
+                Ok(())
+            }
+            None => anyhow::bail!("Nothing to undo"),
+        }
+    }
+
+    pub fn redo(&mut self) -> Result<()> {
+        use crate::viewmodel::undo::redo_sync;
+
+        match redo_sync(&mut *self.conn()?, crate::db::WallpaperSource::Tray)? {
+            Some(outcome) => {
+                log::info!("Redid status change of {}", outcome.title);
+                Ok(())
+            }
+            None => anyhow::bail!("Nothing to redo"),
+        }
+    }
+
     pub fn set_kept_wallpaper(&mut self) -> Result<bool> {
         use crate::viewmodel::commands::set_random_favorite_wallpaper_sync;
 
-        match set_random_favorite_wallpaper_sync(&mut self.conn) {
+        match set_random_favorite_wallpaper_sync(&mut *self.conn()?, crate::db::WallpaperSource::Tray) {
             Ok(Some(_title)) => Ok(true),
             Ok(None) => {
                 log::warn!("No favorite wallpapers available");
@@ -155,8 +249,3 @@ impl TrayLogic {
     }
 }
 
-impl Clone for TrayLogic {
-    fn clone(&self) -> Self {
-        Self::new().expect("Failed to clone TrayLogic")
-    }
-}
@@ -33,6 +33,9 @@ pub enum MenuAction {
     KeepCurrent,
     BlacklistCurrent,
     RandomFavorite,
+    RateCurrent,
+    Undo,
+    Redo,
     Quit,
     Separator,
 }
@@ -243,6 +246,23 @@ impl MenuPopup {
                 logic.set_kept_wallpaper()?;
                 Ok(None)
             }
+            MenuAction::RateCurrent => {
+                // A popup has no submenus: each click adds a star, five stars wrap to unrated
+                log::info!("Rate current clicked");
+                let rating = logic.current_rating().unwrap_or(0);
+                logic.rate_current_image((rating + 1) % (crate::db::models::MAX_RATING + 1))?;
+                Ok(None)
+            }
+            MenuAction::Undo => {
+                log::info!("Undo clicked");
+                logic.undo_last_action()?;
+                Ok(None)
+            }
+            MenuAction::Redo => {
+                log::info!("Redo clicked");
+                logic.redo()?;
+                Ok(None)
+            }
             MenuAction::Separator => Ok(None),
         }
     }
@@ -281,6 +301,7 @@ pub fn calculate_menu_size(items: &[MenuItem]) -> (u16, u16) {
 
 fn build_menu_items(logic: &mut TrayLogic) -> Vec<MenuItem> {
     let current_title = logic.get_current_image_title();
+    let current_rating = logic.current_rating();
 
     vec![
         MenuItem::new(MenuAction::ShowApp, tr!("tray-show-app").to_string(), true),
@@ -314,6 +335,13 @@ fn build_menu_items(logic: &mut TrayLogic) -> Vec<MenuItem> {
             tr!("tray-random-favorite").to_string(),
             logic.has_kept_wallpapers(),
         ),
+        MenuItem::new(
+            MenuAction::RateCurrent,
+            format!("{} {}", tr!("tray-rate"), crate::db::models::rating_stars(current_rating.unwrap_or(0))),
+            current_rating.is_some(),
+        ),
+        MenuItem::new(MenuAction::Undo, tr!("tray-undo").to_string(), logic.can_undo()),
+        MenuItem::new(MenuAction::Redo, tr!("tray-redo").to_string(), logic.can_redo()),
         MenuItem::separator(),
         MenuItem::new(MenuAction::Quit, tr!("tray-quit").to_string(), true),
     ]
@@ -1,10 +1,10 @@
 use std::sync::mpsc::{Receiver, Sender};
-use std::path::PathBuf;
 use super::{ViewModelCommand, ViewModelEvent};
+use crate::db::WallpaperSource;
 
 /// Background thread message loop (GUI/Android only)
 pub fn run_background_loop(
-    db_path: PathBuf,
+    pool: crate::db::DbPool,
     cmd_rx: Receiver<ViewModelCommand>,
     evt_tx: Sender<ViewModelEvent>,
 ) {
@@ -13,7 +13,16 @@ pub fn run_background_loop(
     // Create executor for async operations (using smol)
     let ex = smol::Executor::new();
 
-    let mut conn = crate::db::establish_connection(&db_path);
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            log::error!("ViewModel background thread has no database: {}", e);
+            evt_tx.send(ViewModelEvent::Error {
+                message: format!("Database unavailable: {}", e)
+            }).ok();
+            return;
+        }
+    };
 
     // Message loop
     for cmd in cmd_rx {
@@ -62,7 +71,7 @@ fn handle_command(
         }
 
         ToggleFavorite { url } => {
-            match super::commands::toggle_favorite_sync(conn, &url) {
+            match super::commands::toggle_favorite_sync(conn, &url, WallpaperSource::Gui) {
                 Ok(_) => {
                     evt_tx.send(ViewModelEvent::StatusUpdated {
                         url,
@@ -78,7 +87,7 @@ fn handle_command(
         }
 
         BlacklistImage { url } => {
-            match super::commands::blacklist_image_sync(conn, &url) {
+            match super::commands::blacklist_image_sync(conn, &url, WallpaperSource::Gui) {
                 Ok(_) => {
                     evt_tx.send(ViewModelEvent::StatusUpdated {
                         url,
@@ -94,7 +103,7 @@ fn handle_command(
         }
 
         UnmarkImage { url } => {
-            match super::commands::unmark_image_sync(conn, &url) {
+            match super::commands::unmark_image_sync(conn, &url, WallpaperSource::Gui) {
                 Ok(_) => {
                     evt_tx.send(ViewModelEvent::StatusUpdated {
                         url,
@@ -110,8 +119,10 @@ fn handle_command(
         }
 
         DownloadImages { market_code } => {
-            // Placeholder: will implement async download with Asupersync later
-            match super::commands::download_images_sync(conn, &market_code) {
+            let result = super::commands::download_images_with_progress_sync(conn, &market_code, |current, total| {
+                evt_tx.send(ViewModelEvent::DownloadProgress { current, total }).ok();
+            });
+            match result {
                 Ok(count) => {
                     evt_tx.send(ViewModelEvent::DownloadComplete { count }).ok();
                 }
@@ -124,7 +135,7 @@ fn handle_command(
         }
 
         SetWallpaper { url } => {
-            match super::commands::set_wallpaper_sync(conn, &url) {
+            match super::commands::set_wallpaper_sync(conn, &url, WallpaperSource::Gui) {
                 Ok(success) => {
                     evt_tx.send(ViewModelEvent::WallpaperSet { success }).ok();
                 }
@@ -136,9 +147,227 @@ fn handle_command(
             }
         }
 
+        SetPreviousWallpaper => {
+            match super::commands::set_previous_wallpaper_sync(conn, WallpaperSource::Gui) {
+                Ok(result) => {
+                    evt_tx.send(ViewModelEvent::WallpaperSet { success: result.is_some() }).ok();
+                }
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to set previous wallpaper: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        LoadHistoryPage { page } => {
+            match super::history::get_history_page_sync(conn, page) {
+                Ok(history) => {
+                    evt_tx.send(ViewModelEvent::HistoryPageLoaded { history }).ok();
+                }
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to load history: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        UndoLastAction => {
+            match super::undo::undo_last_action_sync(conn, WallpaperSource::Gui) {
+                Ok(outcome) => {
+                    evt_tx.send(ViewModelEvent::UndoApplied { outcome }).ok();
+                }
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to undo: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        Redo => {
+            match super::undo::redo_sync(conn, WallpaperSource::Gui) {
+                Ok(outcome) => {
+                    evt_tx.send(ViewModelEvent::UndoApplied { outcome }).ok();
+                }
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to redo: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        Search { query, filter, page } => {
+            match super::search::search_page_sync(conn, &query, filter, page) {
+                Ok(results) => {
+                    evt_tx.send(ViewModelEvent::SearchResultsLoaded {
+                        query: results.query,
+                        page: results.page,
+                        images: results.images,
+                        total_count: results.total_count,
+                    }).ok();
+                }
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Search failed: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        TagImage { url, tag } => {
+            match operations::tag_image(conn, &url, &tag) {
+                Ok(_) => send_image_groups(conn, evt_tx, url),
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to tag image: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        UntagImage { url, tag_id } => {
+            match operations::untag_image(conn, &url, tag_id) {
+                Ok(()) => send_image_groups(conn, evt_tx, url),
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to untag image: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        LoadImageGroups { url } => send_image_groups(conn, evt_tx, url),
+
+        LoadCollections => send_collections(conn, evt_tx),
+
+        CreateCollection { name } => {
+            match operations::create_collection(conn, &name) {
+                Ok(_) => send_collections(conn, evt_tx),
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to create collection: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        AddToCollection { url, collection_id } => {
+            match operations::add_image_to_collection(conn, collection_id, &url) {
+                Ok(_) => {
+                    send_image_groups(conn, evt_tx, url);
+                    send_collections(conn, evt_tx);
+                }
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to add image to collection: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        RemoveFromCollection { url, collection_id } => {
+            match operations::remove_image_from_collection(conn, collection_id, &url) {
+                Ok(()) => {
+                    send_image_groups(conn, evt_tx, url);
+                    send_collections(conn, evt_tx);
+                }
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to remove image from collection: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        SetRotationCollection { collection_id } => {
+            match super::collections::set_rotation_collection_sync(conn, collection_id) {
+                Ok(()) => send_collections(conn, evt_tx),
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to set rotation collection: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        LoadCollectionPage { collection_id, page } => {
+            match super::collections::get_collection_page_sync(conn, collection_id, page) {
+                Ok((images, total_count)) => {
+                    evt_tx.send(ViewModelEvent::CarouselPageLoaded { page, images, total_count }).ok();
+                }
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to load collection page: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        LoadCountries => send_countries(conn, evt_tx),
+
+        SetRotationCountries { countries } => {
+            match super::countries::set_rotation_countries_sync(conn, &countries) {
+                Ok(()) => send_countries(conn, evt_tx),
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to set rotation countries: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        LoadCountryPage { country, page } => {
+            match super::countries::get_country_page_sync(conn, &country, page) {
+                Ok((images, total_count)) => {
+                    evt_tx.send(ViewModelEvent::CarouselPageLoaded { page, images, total_count }).ok();
+                }
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to load country page: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        LoadRating { url } => {
+            match operations::get_image(conn, &url) {
+                Ok(Some(image)) => {
+                    evt_tx.send(ViewModelEvent::RatingLoaded { url, rating: image.rating }).ok();
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to load rating: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
+        SetRating { url, rating } => {
+            match operations::set_image_rating(conn, &url, rating) {
+                Ok(_) => {
+                    evt_tx.send(ViewModelEvent::RatingLoaded { url, rating }).ok();
+                }
+                Err(e) => {
+                    evt_tx.send(ViewModelEvent::Error {
+                        message: format!("Failed to set rating: {}", e)
+                    }).ok();
+                }
+            }
+        }
+
         RefreshDatabase => {
-            // No-op for now
             log::info!("RefreshDatabase command received");
+
+            // Pick up new or removed files in local image directories
+            let result = super::commands::get_source_settings_sync(conn)
+                .and_then(|settings| super::local_source::sync_local_images_sync(conn, &settings));
+            if let Err(e) = result {
+                log::warn!("Failed to rescan local directories: {}", e);
+            }
         }
 
         Shutdown => {
@@ -161,14 +390,14 @@ fn handle_command(
                 let status_str = status.as_str();
                 bing_images::table
                     .filter(bing_images::status.eq(status_str))
-                    .order(bing_images::fetched_at.desc())
+                    .order(operations::by_publication_date())
                     .limit(20)
                     .offset(offset)
                     .load::<crate::db::BingImage>(conn)
             } else {
                 // No filter = All images
                 bing_images::table
-                    .order(bing_images::fetched_at.desc())
+                    .order(operations::by_publication_date())
                     .limit(20)
                     .offset(offset)
                     .load::<crate::db::BingImage>(conn)
@@ -203,7 +432,7 @@ fn handle_command(
             use crate::viewmodel::commands;
 
             // Try cache first
-            match commands::load_cached_image(&url) {
+            match commands::load_cached_image(conn, &url) {
                 Ok(Some(bytes)) => {
                     log::info!("Loaded from cache: {} ({} bytes)", url, bytes.len());
                     evt_tx.send(ViewModelEvent::MainImageLoaded {
@@ -234,7 +463,7 @@ fn handle_command(
                     match commands::download_image(&url) {
                         Ok(bytes) => {
                             // Save to cache
-                            commands::save_to_cache(&url, &bytes).ok();
+                            commands::save_to_cache(conn, &url, &bytes).ok();
 
                             evt_tx.send(ViewModelEvent::MainImageLoaded {
                                 url,
@@ -291,3 +520,51 @@ fn handle_command(
         }
     }
 }
+
+fn send_image_groups(conn: &mut diesel::SqliteConnection, evt_tx: &Sender<ViewModelEvent>, url: String) {
+    match super::collections::get_image_groups_sync(conn, &url) {
+        Ok(groups) => {
+            evt_tx.send(ViewModelEvent::ImageGroupsLoaded { url, groups }).ok();
+        }
+        Err(e) => {
+            evt_tx.send(ViewModelEvent::Error {
+                message: format!("Failed to load tags: {}", e)
+            }).ok();
+        }
+    }
+}
+
+fn send_collections(conn: &mut diesel::SqliteConnection, evt_tx: &Sender<ViewModelEvent>) {
+    let result = super::collections::get_collection_summaries_sync(conn).and_then(|collections| {
+        let rotation = super::collections::get_rotation_collection_sync(conn)?;
+        Ok((collections, rotation.map(|collection| collection.id)))
+    });
+
+    match result {
+        Ok((collections, rotation_collection_id)) => {
+            evt_tx.send(ViewModelEvent::CollectionsLoaded { collections, rotation_collection_id }).ok();
+        }
+        Err(e) => {
+            evt_tx.send(ViewModelEvent::Error {
+                message: format!("Failed to load collections: {}", e)
+            }).ok();
+        }
+    }
+}
+
+fn send_countries(conn: &mut diesel::SqliteConnection, evt_tx: &Sender<ViewModelEvent>) {
+    let result = super::countries::get_country_summaries_sync(conn).and_then(|countries| {
+        Ok((countries, super::countries::get_rotation_countries_sync(conn)?))
+    });
+
+    match result {
+        Ok((countries, rotation_countries)) => {
+            evt_tx.send(ViewModelEvent::CountriesLoaded { countries, rotation_countries }).ok();
+        }
+        Err(e) => {
+            evt_tx.send(ViewModelEvent::Error {
+                message: format!("Failed to load countries: {}", e)
+            }).ok();
+        }
+    }
+}
@@ -1,27 +1,30 @@
 use anyhow::{Context, Result};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, RwLock};
 
-/// Get cache filename from URL
+use super::markets::DEFAULT_MARKET_CODE;
+
+/// Cache filename of an image URL, shared by every cache reader and writer
+///
+/// Bing images are named by OHR identifier and resolution (OHR_Name_UHD.jpg),
+/// so all markets share one file per size; other URLs by a hash of the whole URL.
 pub fn get_cache_filename(url: &str) -> String {
-    // Extract identifier from Bing URL
-    let identifier = url
-        .split("th?id=")
-        .nth(1)
-        .and_then(|s| s.split('_').next())
-        .map(|s| s.replace('.', "_"))
-        .unwrap_or_else(|| {
-            // Fallback: use last part of path
-            url.split('/')
-                .last()
-                .unwrap_or("unknown")
-                .split('?')
-                .next()
-                .unwrap_or("unknown")
-                .to_string()
-        });
-
-    format!("{}.jpg", identifier)
+    let identifier = super::sources::extract_identifier(url)
+        .map(|id| {
+            let id = id.split('&').next().unwrap_or_default();
+            id.chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+                .collect::<String>()
+        })
+        .filter(|id| !id.is_empty());
+
+    // Keep size variants of the same image apart (OHR_Name_UHD.jpg, OHR_Name_1920x1080.jpg)
+    match (identifier, super::resolution::resolution_suffix(url)) {
+        (Some(identifier), Some(resolution)) => format!("{}_{}.jpg", identifier, resolution),
+        (Some(identifier), None) => format!("{}.jpg", identifier),
+        (None, _) => format!("url_{:x}.jpg", md5::compute(url)),
+    }
 }
 
 /// Cached image metadata
@@ -34,15 +37,15 @@ pub struct CachedImage {
 /// Smart pre-download cache manager
 pub struct CacheManager {
     cache_dir: PathBuf,
-    db_path: PathBuf,
-    sources: Option<Arc<super::sources::ImageSource>>,
+    pool: crate::db::DbPool,
+    sources: RwLock<Option<Arc<super::sources::ImageSource>>>,
 }
 
 impl CacheManager {
     /// Create new cache manager and ensure cache directory exists
     pub fn new(
         cache_dir: PathBuf,
-        db_path: PathBuf,
+        pool: crate::db::DbPool,
         sources: Option<Arc<super::sources::ImageSource>>,
     ) -> Self {
         // Create cache directory if it doesn't exist
@@ -52,14 +55,19 @@ impl CacheManager {
 
         Self {
             cache_dir,
-            db_path,
-            sources,
+            pool,
+            sources: RwLock::new(sources),
         }
     }
 
-    /// Populate database with all historical images from GitHub archive (public/sync)
-    pub fn populate_historical_images_sync(&self) -> Result<()> {
-        self.populate_historical_images()
+    /// Current image source registry
+    pub fn sources(&self) -> Option<Arc<super::sources::ImageSource>> {
+        self.sources.read().unwrap().clone()
+    }
+
+    /// Replace the image source registry (e.g. after source settings changed)
+    pub fn set_sources(&self, sources: Arc<super::sources::ImageSource>) {
+        *self.sources.write().unwrap() = Some(sources);
     }
 
     /// Cache initial images without downloading historical data (for background use)
@@ -78,78 +86,58 @@ impl CacheManager {
         self.download_and_cache(needed)
     }
 
-    /// Populate database with all historical images from GitHub archive
-    fn populate_historical_images(&self) -> Result<()> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
-
-        // Check if we already have historical images (>30 means we've done this before)
-        use diesel::prelude::*;
-        use crate::schema::bing_images;
-        let existing_count: i64 = bing_images::table.count().get_result(&mut conn)?;
-
-        if existing_count > 30 {
-            log::info!("Database already has {} images, skipping historical download", existing_count);
-            return Ok(());
-        }
-
-        log::info!("Downloading all historical images from sources...");
-
-        let sources = self.sources.as_ref()
+    /// Import all historical images from the GitHub archive (resumable, see `import_github_archive_sync`)
+    pub fn import_historical_images(
+        &self,
+        cancel: &AtomicBool,
+        on_progress: impl FnMut(usize, usize),
+    ) -> Result<super::commands::ArchiveImport> {
+        let sources = self.sources()
             .context("No image sources available")?;
 
-        // Get existing URLs to skip
-        let existing_urls: Vec<String> = bing_images::table
-            .select(bing_images::url)
-            .load(&mut conn)?;
-
-        // Fetch ALL available images (GitHub archive has ~1800 images)
-        // Request a large count to get everything
-        let images = sources.fetch_images(10000, &existing_urls)?;
-
-        log::info!("Downloaded {} new images, inserting into database...", images.len());
-
-        // Insert all images into database (without caching the files yet)
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i32;
+        let mut conn = self.pool.get()?;
 
-        for image in &images {
-            let new_image = crate::db::models::NewBingImage {
-                url: &image.url,
-                title: &image.title,
-                copyright: image.copyright.as_deref(),
-                copyright_link: image.copyright_link.as_deref(),
-                market_code: "en-US",
-                fetched_at: now,
-                status: "unprocessed",
-                created_at: now,
-                updated_at: now,
-            };
-            crate::db::operations::upsert_image(&mut conn, &new_image)?;
-        }
+        // The GitHub archive has ~1800 images; all rows are stored, markets are left to the regular rotation
+        log::info!("Importing historical images from the GitHub archive...");
+        let import = super::commands::import_github_archive_sync(&mut conn, &sources, cancel, on_progress)?;
 
-        log::info!("Successfully inserted {} historical images into database", images.len());
-        Ok(())
+        log::info!("Imported {} historical images ({} new)", import.stored, import.added);
+        Ok(import)
     }
 
     /// Download and cache N images
     fn download_and_cache(&self, count: usize) -> Result<usize> {
-        let sources = self.sources.as_ref()
+        let sources = self.sources()
             .context("No image sources available")?;
 
-        let mut conn = crate::db::establish_connection(&self.db_path);
+        let mut conn = self.pool.get()?;
+
+        // Local directory images are read in place, so they only need to be synced
+        if let Err(e) = super::local_source::sync_local_images_sync(&mut conn, sources.settings()) {
+            log::warn!("Failed to rescan local directories: {}", e);
+        }
+
+        // Pick up rows appended to the GitHub archive (conditional, at most weekly)
+        if let Err(e) = super::commands::sync_github_archive_sync(&mut conn, &sources) {
+            log::warn!("GitHub archive sync failed: {}", e);
+        }
 
         // Get existing URLs to skip
         use diesel::prelude::*;
         use crate::schema::bing_images;
         let existing_urls: Vec<String> = bing_images::table
             .select(bing_images::url)
-            .load(&mut conn)?;
+            .load(&mut *conn)?;
 
         // Fetch images from sources (fetch extra in case some fail)
-        let images = sources.fetch_images(count * 2, &existing_urls)?;
+        let market_page = super::markets::next_market_page_sync(&mut conn)?;
+        let fetched = sources.fetch_images(market_page.as_ref(), count * 2, &existing_urls)?;
+        if let Some(page) = market_page.as_ref().filter(|_| fetched.answered(super::sources::BING_API_PROVIDER)) {
+            super::markets::advance_market_page_sync(&mut conn, page)?;
+        }
+        let images = fetched.images;
 
+        let resolution = super::resolution::preferred_resolution_sync(&mut conn)?;
         let mut downloaded = 0;
 
         for image in images.iter().take(count) {
@@ -162,7 +150,7 @@ impl CacheManager {
                 let cached_at: Option<i32> = bing_images::table
                     .filter(bing_images::url.eq(&image.url))
                     .select(bing_images::cached_at)
-                    .first(&mut conn)
+                    .first(&mut *conn)
                     .optional()?
                     .flatten();
 
@@ -173,40 +161,48 @@ impl CacheManager {
                 }
             }
 
-            // Download image bytes with retry
-            match self.download_with_retry(&image.url, 3) {
-                Ok(bytes) => {
-                    // Save to cache directory
-                    let filename = get_cache_filename(&image.url);
-                    let cache_path = self.cache_dir.join(&filename);
-
-                    std::fs::write(&cache_path, &bytes)
-                        .with_context(|| format!("Failed to write {}", filename))?;
+            // Download image bytes with retry, in the preferred resolution when Bing has it
+            let variant_url = super::resolution::variant_url(&image.url, image.urlbase.as_deref(), resolution);
+            let result = self.download_with_retry(&variant_url, 3)
+                .map(|bytes| (bytes, variant_url.as_str()))
+                .or_else(|e| {
+                    if variant_url == image.url {
+                        return Err(e);
+                    }
+                    log::warn!("{} variant unavailable ({}), using {}", resolution.suffix(), e, image.url);
+                    self.download_with_retry(&image.url, 3).map(|bytes| (bytes, image.url.as_str()))
+                });
+
+            match result {
+                Ok((bytes, downloaded_url)) => {
+                    // Save to cache directory under the URL the bytes came from (local files are used in place)
+                    if !super::local_source::is_local_url(&image.url) {
+                        let filename = get_cache_filename(downloaded_url);
+                        let cache_path = self.cache_dir.join(&filename);
+
+                        std::fs::write(&cache_path, &bytes)
+                            .with_context(|| format!("Failed to write {}", filename))?;
+                    }
 
                     log::info!("Cached image: {} ({} bytes)", image.title, bytes.len());
 
                     // Insert/update image in database first
-                    use crate::db::models::NewBingImage;
                     let now = std::time::SystemTime::now()
                         .duration_since(std::time::UNIX_EPOCH)
                         .unwrap()
                         .as_secs() as i32;
 
-                    let new_image = NewBingImage {
-                        url: &image.url,
-                        title: &image.title,
-                        copyright: image.copyright.as_deref(),
-                        copyright_link: image.copyright_link.as_deref(),
-                        market_code: "en-US",  // Default market code
-                        fetched_at: now,
-                        status: "unprocessed",  // Default status
-                        created_at: now,
-                        updated_at: now,
-                    };
+                    let new_image = image.to_new_record(
+                        image.market_code.as_deref().unwrap_or(DEFAULT_MARKET_CODE),
+                        "unprocessed",  // Default status
+                        now,
+                        now,
+                    );
                     crate::db::operations::upsert_image(&mut conn, &new_image)?;
 
-                    // Then mark as cached
+                    // Then mark as cached and link it if it's a near-duplicate
                     self.mark_as_cached(&mut conn, &image.url)?;
+                    super::dedup::try_record_image_hash_sync(&mut conn, &image.url, &bytes);
 
                     downloaded += 1;
                 }
@@ -225,6 +221,11 @@ impl CacheManager {
 
     /// Download image bytes with retry (exponential backoff)
     fn download_with_retry(&self, url: &str, max_retries: usize) -> Result<Vec<u8>> {
+        if let Some(path) = super::local_source::local_path_from_url(url) {
+            return std::fs::read(&path)
+                .with_context(|| format!("Failed to read local image: {:?}", path));
+        }
+
         let mut retry_delay = std::time::Duration::from_secs(1);
 
         for attempt in 0..max_retries {
@@ -283,13 +284,14 @@ impl CacheManager {
         use diesel::prelude::*;
         use crate::schema::bing_images;
 
-        let mut conn = crate::db::establish_connection(&self.db_path);
+        let mut conn = self.pool.get()?;
 
         let count: i64 = bing_images::table
             .filter(bing_images::status.eq("unprocessed"))
             .filter(bing_images::cached_at.is_not_null())
+            .filter(bing_images::canonical_id.is_null())
             .count()
-            .get_result(&mut conn)?;
+            .get_result(&mut *conn)?;
 
         Ok(count as usize)
     }
@@ -310,40 +312,86 @@ impl CacheManager {
         use diesel::prelude::*;
         use crate::schema::bing_images;
 
-        let mut conn = crate::db::establish_connection(&self.db_path);
+        let mut conn = self.pool.get()?;
+        let resolution = super::resolution::preferred_resolution_sync(&mut conn)?;
 
-        // Query for next unprocessed image with cached_at set
-        let result: Option<crate::db::BingImage> = bing_images::table
-            .filter(bing_images::status.eq("unprocessed"))
-            .filter(bing_images::cached_at.is_not_null())
-            .order(bing_images::fetched_at.desc())
-            .first(&mut conn)
-            .optional()?;
+        loop {
+            // Query for next unprocessed image with cached_at set (near-duplicates skipped)
+            let result: Option<crate::db::BingImage> = bing_images::table
+                .filter(bing_images::status.eq("unprocessed"))
+                .filter(bing_images::cached_at.is_not_null())
+                .filter(bing_images::canonical_id.is_null())
+                .order(crate::db::operations::by_publication_date())
+                .first(&mut *conn)
+                .optional()?;
 
-        if let Some(img) = result {
-            let filename = get_cache_filename(&img.url);
-            let cached_path = self.cache_dir.join(&filename);
+            let Some(img) = result else {
+                return Ok(None);
+            };
+            let cached_path = self.cached_path(&img.url, img.urlbase.as_deref(), resolution);
 
-            // Verify file exists
+            // Deleted, or cached in another resolution: not cached any more, try the next one
             if !cached_path.exists() {
                 log::warn!("Cache file missing: {:?}", cached_path);
-                return Ok(None);
+                crate::db::operations::clear_image_cached(&mut conn, &img.url)?;
+                continue;
             }
 
-            Ok(Some(CachedImage {
+            return Ok(Some(CachedImage {
                 url: img.url,
                 title: img.title,
                 cached_path,
-            }))
-        } else {
-            Ok(None)
+            }));
+        }
+    }
+
+    /// Hash cached images that have no perceptual hash yet
+    /// Returns the number of images hashed
+    pub fn hash_cached_images(&self) -> Result<usize> {
+        let mut conn = self.pool.get()?;
+        let resolution = super::resolution::preferred_resolution_sync(&mut conn)?;
+        let mut hashed = 0;
+
+        for image in crate::db::operations::get_unhashed_cached_images(&mut conn)? {
+            let path = self.cached_path(&image.url, image.urlbase.as_deref(), resolution);
+            let bytes = match std::fs::read(&path) {
+                Ok(bytes) => bytes,
+                Err(_) => continue, // Cached in another resolution
+            };
+
+            super::dedup::try_record_image_hash_sync(&mut conn, &image.url, &bytes);
+            hashed += 1;
         }
+
+        log::info!("Hashed {} cached images", hashed);
+        Ok(hashed)
+    }
+
+    /// Path of an image's bytes on disk (local directory images are not copied)
+    /// Images without the preferred variant are cached under their stored URL.
+    fn cached_path(
+        &self,
+        url: &str,
+        urlbase: Option<&str>,
+        resolution: super::resolution::ImageResolution,
+    ) -> PathBuf {
+        super::local_source::local_path_from_url(url).unwrap_or_else(|| {
+            let variant_url = super::resolution::variant_url(url, urlbase, resolution);
+            let variant_path = self.cache_dir.join(get_cache_filename(&variant_url));
+            let fallback_path = self.cache_dir.join(get_cache_filename(url));
+            if !variant_path.exists() && fallback_path.exists() {
+                fallback_path
+            } else {
+                variant_path
+            }
+        })
     }
 
     /// Load image bytes from cache (instant, no network)
     pub fn load_cached_bytes(&self, url: &str) -> Result<Vec<u8>> {
-        let filename = get_cache_filename(url);
-        let cache_path = self.cache_dir.join(&filename);
+        let mut conn = self.pool.get()?;
+        let resolution = super::resolution::preferred_resolution_sync(&mut conn)?;
+        let cache_path = self.cached_path(url, None, resolution);
 
         std::fs::read(&cache_path)
             .with_context(|| format!("Failed to read cached file: {:?}", cache_path))
@@ -0,0 +1,98 @@
+//! Tags and collections that group images besides their status
+//!
+//! Random favorite rotation can be restricted to one collection: it then picks
+//! from the collection's images that are not blacklisted instead of all favorites.
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+
+use crate::db::{operations, BingImage, Collection, ImageStatus, Tag};
+
+/// Collection images per carousel page
+pub const COLLECTION_PAGE_SIZE: usize = 20;
+
+/// Config key holding the id of the collection rotation is restricted to
+const ROTATION_COLLECTION_KEY: &str = "rotation_collection_id";
+
+/// A collection with its image count
+#[derive(Debug, Clone)]
+pub struct CollectionSummary {
+    pub collection: Collection,
+    pub image_count: usize,
+}
+
+/// Tags and collections of one image
+#[derive(Debug, Clone, Default)]
+pub struct ImageGroups {
+    pub tags: Vec<Tag>,
+    pub collections: Vec<Collection>,
+}
+
+/// All collections by name, with their image counts
+pub fn get_collection_summaries_sync(conn: &mut SqliteConnection) -> Result<Vec<CollectionSummary>> {
+    operations::get_collections(conn)?
+        .into_iter()
+        .map(|collection| {
+            let image_count = operations::count_collection_images(conn, collection.id)? as usize;
+            Ok(CollectionSummary { collection, image_count })
+        })
+        .collect()
+}
+
+/// Tags and collections of an image
+pub fn get_image_groups_sync(conn: &mut SqliteConnection, url: &str) -> Result<ImageGroups> {
+    Ok(ImageGroups {
+        tags: operations::get_image_tags(conn, url)?,
+        collections: operations::get_image_collections(conn, url)?,
+    })
+}
+
+/// Load a page of a collection's images, newest publication first
+/// Returns the images and the collection's total image count.
+pub fn get_collection_page_sync(
+    conn: &mut SqliteConnection,
+    collection_id: i32,
+    page: usize,
+) -> Result<(Vec<BingImage>, usize)> {
+    let offset = (page * COLLECTION_PAGE_SIZE) as i64;
+    let images = operations::get_collection_images(conn, collection_id, COLLECTION_PAGE_SIZE as i64, offset)?;
+    let total_count = operations::count_collection_images(conn, collection_id)? as usize;
+    Ok((images, total_count))
+}
+
+/// Collection rotation is restricted to (None = all favorites)
+///
+/// A deleted collection is forgotten so rotation falls back to favorites.
+pub fn get_rotation_collection_sync(conn: &mut SqliteConnection) -> Result<Option<Collection>> {
+    let Some(id) = operations::get_config(conn, ROTATION_COLLECTION_KEY)?
+        .and_then(|value| value.parse::<i32>().ok())
+    else {
+        return Ok(None);
+    };
+
+    let collection = operations::get_collection(conn, id)?;
+    if collection.is_none() {
+        operations::set_config(conn, ROTATION_COLLECTION_KEY, "")?;
+    }
+    Ok(collection)
+}
+
+/// Restrict rotation to a collection (None = all favorites)
+pub fn set_rotation_collection_sync(conn: &mut SqliteConnection, collection_id: Option<i32>) -> Result<()> {
+    let value = collection_id.map(|id| id.to_string()).unwrap_or_default();
+    operations::set_config(conn, ROTATION_COLLECTION_KEY, &value)
+}
+
+/// Images random favorite rotation picks from
+pub fn get_rotation_candidates_sync(conn: &mut SqliteConnection) -> Result<Vec<BingImage>> {
+    match get_rotation_collection_sync(conn)? {
+        Some(collection) => {
+            let images = operations::get_collection_rotation_images(conn, collection.id)?;
+            if images.is_empty() {
+                log::info!("Rotation collection '{}' has no images to show", collection.name);
+            }
+            Ok(images)
+        }
+        None => operations::get_images_by_status(conn, ImageStatus::KeepFavorite),
+    }
+}
@@ -1,27 +1,186 @@
 use diesel::prelude::*;
 use anyhow::{Result, Context};
-use crate::db::ImageStatus;
-#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
-use crate::db::models::NewBingImage;
+use crate::db::{ImageStatus, WallpaperSource};
 use std::path::PathBuf;
 
-/// Download images for a market code (stub for now)
-pub fn download_images_sync(_conn: &mut SqliteConnection, _market_code: &str) -> Result<usize> {
-    // TODO: Implement actual download logic using api_bingimage.rs
-    // For now, return 0 to make compilation work
-    log::info!("download_images_sync called (stub)");
-    Ok(0)
+/// Download a page of new images for a market code
+/// Returns the number of images downloaded into the cache
+pub fn download_images_sync(conn: &mut SqliteConnection, market_code: &str) -> Result<usize> {
+    download_images_with_progress_sync(conn, market_code, |_, _| {})
+}
+
+/// Download a page of new images for a market code, reporting `(current, total)` after each image
+///
+/// New images are fetched from every enabled source, stored as unprocessed and
+/// cached in the preferred resolution. An image that fails to download stays in
+/// the database and is fetched again when it is first shown.
+pub fn download_images_with_progress_sync(
+    conn: &mut SqliteConnection,
+    market_code: &str,
+    mut on_progress: impl FnMut(usize, usize),
+) -> Result<usize> {
+    use crate::db::operations;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let sources = image_source_from_settings_sync(conn)?;
+    let page = super::markets::market_page_for_sync(conn, market_code)?;
+
+    let existing_urls: Vec<String> = {
+        use crate::schema::bing_images::dsl::*;
+        bing_images.select(url).load(conn)?
+    };
+
+    let fetched = sources
+        .fetch_images(Some(&page), super::markets::MARKET_PAGE_SIZE as usize, &existing_urls)
+        .context("Failed to fetch images from sources")?;
+    let images = &fetched.images;
+
+    let total = images.len();
+    log::info!("Downloading {} new images for market {}", total, page.market_code);
+
+    let mut downloaded = 0;
+    for (index, img) in images.iter().enumerate() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i32;
+
+        let record = img.to_new_record(
+            img.market_code.as_deref().unwrap_or(&page.market_code),
+            "unprocessed",
+            now,
+            now,
+        );
+        let image = operations::upsert_image(conn, &record)?;
+
+        match load_wallpaper_bytes_sync(conn, &image) {
+            Ok(_) => {
+                operations::mark_image_cached(conn, &image.url, now)?;
+                downloaded += 1;
+            }
+            Err(e) => log::warn!("Failed to download {}: {}", image.url, e),
+        }
+
+        on_progress(index + 1, total);
+    }
+
+    // The page is done once its rows are stored; an interrupted run fetches it again
+    if fetched.answered(super::sources::BING_API_PROVIDER) {
+        super::markets::advance_market_page_sync(conn, &page)?;
+    }
+
+    log::info!("Downloaded {}/{} images", downloaded, total);
+    Ok(downloaded)
+}
+
+/// Set an image as wallpaper with the platform setter
+///
+/// Loads the image in the preferred resolution (cache first), and records it in
+/// the wallpaper history on success. URLs that are not in the database are
+/// downloaded as-is.
+pub fn set_wallpaper_sync(conn: &mut SqliteConnection, url: &str, source: WallpaperSource) -> Result<bool> {
+    set_wallpaper_with_crop_sync(conn, url, source, None)
+}
+
+/// Set an image as wallpaper, cropped to `crop` (normalized coordinates)
+pub fn set_wallpaper_with_crop_sync(
+    conn: &mut SqliteConnection,
+    url: &str,
+    source: WallpaperSource,
+    crop: Option<&super::CropCoords>,
+) -> Result<bool> {
+    let success = apply_wallpaper_sync(conn, url, crop)?;
+
+    if success {
+        super::history::record_wallpaper_set_sync(conn, url, source, None, crop)?;
+    }
+
+    Ok(success)
+}
+
+/// Go back to the wallpaper shown before the current one
+/// Returns None at the start of the history.
+pub fn set_previous_wallpaper_sync(
+    conn: &mut SqliteConnection,
+    source: WallpaperSource,
+) -> Result<Option<crate::viewmodel::WallpaperSetResult>> {
+    use crate::db::operations;
+
+    let Some(entry) = super::history::previous_entry_sync(conn)? else {
+        return Ok(None);
+    };
+
+    let crop = entry.crop_coords.as_deref().and_then(|json| super::CropCoords::from_json(json).ok());
+    if !apply_wallpaper_sync(conn, &entry.url, crop.as_ref())? {
+        anyhow::bail!("Failed to set previous wallpaper");
+    }
+    super::history::record_previous_set_sync(conn, &entry, source)?;
+
+    let title = operations::get_image(conn, &entry.url)?
+        .map(|image| image.title)
+        .unwrap_or_default();
+    Ok(Some(crate::viewmodel::WallpaperSetResult { title, url: entry.url }))
 }
 
-/// Set wallpaper from URL (stub for now)
-pub fn set_wallpaper_sync(_conn: &mut SqliteConnection, url: &str) -> Result<bool> {
-    // TODO: Implement actual wallpaper setting using api_setwallpaper.rs
-    log::info!("set_wallpaper_sync called for: {}", url);
+/// Load an image (cache first), crop it and hand it to the platform setter
+fn apply_wallpaper_sync(conn: &mut SqliteConnection, url: &str, crop: Option<&super::CropCoords>) -> Result<bool> {
+    use crate::db::operations;
+
+    let bytes = match operations::get_image(conn, url)? {
+        Some(image) => load_wallpaper_bytes_sync(conn, &image)?,
+        None => match load_image_from_cache(conn, url)? {
+            Some(bytes) => bytes,
+            None => {
+                let bytes = download_image(url)?;
+                save_image_to_cache(conn, url, &bytes)?;
+                bytes
+            }
+        },
+    };
+
+    let bytes = match crop {
+        Some(crop) => crop_image_bytes(&bytes, crop)?,
+        None => bytes,
+    };
+
+    log::info!("Setting wallpaper: {}", url);
+    set_platform_wallpaper(&bytes)
+}
+
+/// Crop encoded image bytes to normalized coordinates (re-encoded as JPEG)
+pub fn crop_image_bytes(bytes: &[u8], crop: &super::CropCoords) -> Result<Vec<u8>> {
+    let image = image::load_from_memory(bytes).context("Failed to decode image")?;
+    let crop = crop.clamp();
+    let (width, height) = (image.width() as f32, image.height() as f32);
+
+    let left = ((crop.x * width) as u32).min(image.width().saturating_sub(1));
+    let top = ((crop.y * height) as u32).min(image.height().saturating_sub(1));
+    let crop_width = ((crop.width * width) as u32).clamp(1, image.width() - left);
+    let crop_height = ((crop.height * height) as u32).clamp(1, image.height() - top);
+
+    let mut output = Vec::new();
+    image
+        .crop_imm(left, top, crop_width, crop_height)
+        .to_rgb8()
+        .write_to(&mut std::io::Cursor::new(&mut output), image::ImageFormat::Jpeg)?;
+    Ok(output)
+}
+
+/// Set wallpaper bytes with the desktop setter
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+fn set_platform_wallpaper(bytes: &[u8]) -> Result<bool> {
+    crate::api_setwallpaper::set_wallpaper_from_bytes(bytes)?;
     Ok(true)
 }
 
+/// Set wallpaper bytes with the Android live wallpaper service
+#[cfg(target_os = "android")]
+fn set_platform_wallpaper(bytes: &[u8]) -> Result<bool> {
+    Ok(crate::android_wallpaper::set_wallpaper_from_bytes(bytes)?)
+}
+
 /// Toggle favorite status for an image
-pub fn toggle_favorite_sync(conn: &mut SqliteConnection, url: &str) -> Result<()> {
+pub fn toggle_favorite_sync(conn: &mut SqliteConnection, url: &str, source: WallpaperSource) -> Result<()> {
     use crate::db::operations;
 
     // Get current image
@@ -36,24 +195,20 @@ pub fn toggle_favorite_sync(conn: &mut SqliteConnection, url: &str) -> Result<()
             _ => ImageStatus::KeepFavorite,
         };
 
-        operations::update_image_status(conn, url, new_status)?;
+        super::undo::record_status_change_sync(conn, url, new_status, source, None)?;
     }
 
     Ok(())
 }
 
 /// Blacklist an image
-pub fn blacklist_image_sync(conn: &mut SqliteConnection, url: &str) -> Result<()> {
-    use crate::db::operations;
-    operations::update_image_status(conn, url, ImageStatus::Blacklisted)?;
-    Ok(())
+pub fn blacklist_image_sync(conn: &mut SqliteConnection, url: &str, source: WallpaperSource) -> Result<()> {
+    super::undo::record_status_change_sync(conn, url, ImageStatus::Blacklisted, source, None)
 }
 
 /// Unmark an image (set back to Unprocessed)
-pub fn unmark_image_sync(conn: &mut SqliteConnection, url: &str) -> Result<()> {
-    use crate::db::operations;
-    operations::update_image_status(conn, url, ImageStatus::Unprocessed)?;
-    Ok(())
+pub fn unmark_image_sync(conn: &mut SqliteConnection, url: &str, source: WallpaperSource) -> Result<()> {
+    super::undo::record_status_change_sync(conn, url, ImageStatus::Unprocessed, source, None)
 }
 
 // ============================================================================
@@ -61,12 +216,11 @@ pub fn unmark_image_sync(conn: &mut SqliteConnection, url: &str) -> Result<()> {
 // ============================================================================
 
 /// Get the cache directory path for storing downloaded images
-#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
-fn get_cache_dir() -> Result<PathBuf> {
-    let cache_dir = dirs::cache_dir()
-        .ok_or_else(|| anyhow::anyhow!("Could not determine cache directory"))?
-        .join("bingtray")
-        .join("images");
+/// Each database has its own cache, recorded when its pool was opened.
+fn get_cache_dir(conn: &mut SqliteConnection) -> Result<PathBuf> {
+    let cache_dir = crate::db::operations::get_config(conn, crate::db::IMAGE_CACHE_DIR_KEY)?
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow::anyhow!("No image cache recorded for this database (not opened through DbPool)"))?;
 
     // Create cache directory if it doesn't exist
     std::fs::create_dir_all(&cache_dir)?;
@@ -74,61 +228,15 @@ fn get_cache_dir() -> Result<PathBuf> {
     Ok(cache_dir)
 }
 
-/// Get the cache directory path for storing downloaded images (Android)
-#[cfg(target_os = "android")]
-fn get_cache_dir() -> Result<PathBuf> {
-    // On Android, use the app's cache directory
-    let cache_dir = PathBuf::from("/data/data/pe.nikescar.bingtray/cache/images");
-
-    // Create cache directory if it doesn't exist
-    std::fs::create_dir_all(&cache_dir)?;
-
-    Ok(cache_dir)
-}
-
-/// Generate a cache filename from a URL
-fn get_cache_filename(url: &str) -> String {
-    // Extract filename from URL (e.g., OHR.SomeImage_EN-US123_UHD.jpg)
-    // URLs look like: https://www.bing.com/th?id=OHR.Hnausapollur_EN-US2080493040_1920x1080.jpg&rf=...
-    // We want to extract: OHR_Hnausapollur (the first two parts before market code)
-
-    let filename = url
-        .split("th?id=")
-        .nth(1)
-        .and_then(|s| {
-            // Split on & to remove query params
-            let clean = s.split('&').next().unwrap_or(s);
-            // Split on _ to get parts: [OHR.Name, EN-US..., 1920x1080.jpg]
-            let parts: Vec<&str> = clean.split('_').collect();
-            if parts.len() >= 2 {
-                // Take first part (OHR.Name) and replace . with _
-                Some(parts[0].replace('.', "_"))
-            } else {
-                Some(parts.first().unwrap_or(&"unknown").to_string())
-            }
-        })
-        .unwrap_or_else(|| "unknown".to_string());
-
-    // Sanitize filename
-    let sanitized = filename
-        .chars()
-        .map(|c| {
-            if c.is_alphanumeric() || c == '-' || c == '_' {
-                c
-            } else {
-                '_'
-            }
-        })
-        .collect::<String>();
-
-    format!("{}.jpg", sanitized)
-}
-
 /// Load image bytes from cache if available
 /// Returns Some(bytes) if cached, None if not found
-fn load_image_from_cache(url: &str) -> Result<Option<Vec<u8>>> {
-    let cache_dir = get_cache_dir()?;
-    let filename = get_cache_filename(url);
+fn load_image_from_cache(conn: &mut SqliteConnection, url: &str) -> Result<Option<Vec<u8>>> {
+    if let Some(path) = super::local_source::local_path_from_url(url) {
+        return Ok(Some(std::fs::read(&path)?));
+    }
+
+    let cache_dir = get_cache_dir(conn)?;
+    let filename = super::cache_manager::get_cache_filename(url);
     let cache_path = cache_dir.join(&filename);
 
     if cache_path.exists() {
@@ -142,9 +250,13 @@ fn load_image_from_cache(url: &str) -> Result<Option<Vec<u8>>> {
 }
 
 /// Save image bytes to cache
-fn save_image_to_cache(url: &str, bytes: &[u8]) -> Result<()> {
-    let cache_dir = get_cache_dir()?;
-    let filename = get_cache_filename(url);
+fn save_image_to_cache(conn: &mut SqliteConnection, url: &str, bytes: &[u8]) -> Result<()> {
+    if super::local_source::is_local_url(url) {
+        return Ok(());
+    }
+
+    let cache_dir = get_cache_dir(conn)?;
+    let filename = super::cache_manager::get_cache_filename(url);
     let cache_path = cache_dir.join(&filename);
 
     std::fs::write(&cache_path, bytes)?;
@@ -153,6 +265,42 @@ fn save_image_to_cache(url: &str, bytes: &[u8]) -> Result<()> {
     Ok(())
 }
 
+/// Load wallpaper bytes in the preferred resolution (cache first, then network)
+/// Falls back to the stored URL when Bing has no such variant for the image.
+fn load_wallpaper_bytes_sync(conn: &mut SqliteConnection, image: &crate::db::BingImage) -> Result<Vec<u8>> {
+    let resolution = super::resolution::preferred_resolution_sync(conn)?;
+    let url = super::resolution::variant_url(&image.url, image.urlbase.as_deref(), resolution);
+
+    // A variant Bing did not have was cached under the stored URL instead
+    let cached = match load_image_from_cache(conn, &url)? {
+        None if url != image.url => load_image_from_cache(conn, &image.url)?,
+        cached => cached,
+    };
+    if let Some(cached_bytes) = cached {
+        log::info!("Using cached image: {}", image.title);
+        if image.phash.is_none() {
+            super::dedup::try_record_image_hash_sync(conn, &image.url, &cached_bytes);
+        }
+        return Ok(cached_bytes);
+    }
+
+    log::info!("Downloading {} image bytes: {}", resolution.suffix(), url);
+    let (bytes, downloaded_url) = match download_image(&url) {
+        Ok(bytes) => (bytes, url.as_str()),
+        Err(e) if url != image.url => {
+            log::warn!("{} variant unavailable ({}), using {}", resolution.suffix(), e, image.url);
+            (download_image(&image.url)?, image.url.as_str())
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Save to cache for future use, under the URL the bytes came from
+    save_image_to_cache(conn, downloaded_url, &bytes)?;
+    super::dedup::try_record_image_hash_sync(conn, &image.url, &bytes);
+
+    Ok(bytes)
+}
+
 // ============================================================================
 // Market State Helpers
 // ============================================================================
@@ -190,6 +338,211 @@ pub fn increment_market_offset_sync(conn: &mut SqliteConnection) -> Result<()> {
     Ok(())
 }
 
+// ============================================================================
+// Image Source Settings
+// ============================================================================
+
+/// Get image source settings (enabled providers and order) from config
+pub fn get_source_settings_sync(conn: &mut SqliteConnection) -> Result<super::sources::SourceSettings> {
+    use crate::db::operations;
+
+    match operations::get_config(conn, "image_sources")? {
+        Some(json) => serde_json::from_str(&json).context("Invalid image_sources setting"),
+        None => Ok(super::sources::SourceSettings::default()),
+    }
+}
+
+/// Save image source settings to config
+pub fn save_source_settings_sync(
+    conn: &mut SqliteConnection,
+    settings: &super::sources::SourceSettings,
+) -> Result<()> {
+    use crate::db::operations;
+
+    let json = serde_json::to_string(settings)?;
+    operations::set_config(conn, "image_sources", &json)
+}
+
+/// Build the image source registry configured in the database
+pub fn image_source_from_settings_sync(conn: &mut SqliteConnection) -> Result<super::sources::ImageSource> {
+    let settings = get_source_settings_sync(conn).unwrap_or_else(|e| {
+        log::warn!("{}, using default source order", e);
+        Default::default()
+    });
+    let source = super::sources::ImageSource::with_settings(None, settings);
+
+    if let Some(archive) = source.archive() {
+        archive.set_state(get_archive_state_sync(conn)?);
+    }
+
+    Ok(source)
+}
+
+/// Manifest type of the GitHub archive in `last_download_*` config keys
+pub const GITHUB_ARCHIVE_MANIFEST: &str = "github_archive";
+
+/// Get the GitHub archive sync state (ETag, Last-Modified, watermark) from config
+/// `due` is set when the last check is older than the manifest refresh interval.
+pub fn get_archive_state_sync(conn: &mut SqliteConnection) -> Result<super::sources::ArchiveSyncState> {
+    use crate::db::operations;
+
+    let mut state: super::sources::ArchiveSyncState = match operations::get_config(conn, "github_archive_state")? {
+        Some(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            log::warn!("Invalid github_archive_state ({}), resyncing archive", e);
+            Default::default()
+        }),
+        None => Default::default(),
+    };
+    state.due = operations::should_download_manifest(conn, GITHUB_ARCHIVE_MANIFEST);
+
+    Ok(state)
+}
+
+/// Ingest new GitHub archive rows if a check is due
+///
+/// Every row past the watermark is stored (not just a page of them), so the
+/// watermark can advance safely. Returns the number of rows added.
+pub fn sync_github_archive_sync(conn: &mut SqliteConnection, source: &super::sources::ImageSource) -> Result<usize> {
+    let cancel = std::sync::atomic::AtomicBool::new(false);
+    Ok(import_github_archive_sync(conn, source, &cancel, |_, _| {})?.added)
+}
+
+/// Rows stored per transaction by the historical import
+pub const ARCHIVE_IMPORT_CHUNK: usize = 200;
+
+/// Outcome of a GitHub archive import
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArchiveImport {
+    /// Rows that were not in the database before
+    pub added: usize,
+    /// Rows stored (new or updated)
+    pub stored: usize,
+    /// Rows the archive had past the watermark
+    pub total: usize,
+    /// Stopped early through the cancel flag
+    pub cancelled: bool,
+}
+
+/// Ingest new GitHub archive rows in chunks, oldest first, if a check is due
+///
+/// Each chunk is one transaction that also moves the stored watermark past its
+/// rows, so an import interrupted by a crash or exit resumes where it stopped.
+/// `cancel` is checked between chunks; `on_progress` gets `(stored, total)`.
+pub fn import_github_archive_sync(
+    conn: &mut SqliteConnection,
+    source: &super::sources::ImageSource,
+    cancel: &std::sync::atomic::AtomicBool,
+    mut on_progress: impl FnMut(usize, usize),
+) -> Result<ArchiveImport> {
+    use crate::db::operations;
+    use super::sources::{archive_row_date, ArchiveFetch, ArchiveSyncState};
+    use std::sync::atomic::Ordering;
+
+    let archive = match source.archive() {
+        Some(archive) if source.settings().is_enabled(super::sources::GITHUB_ARCHIVE_PROVIDER) => archive,
+        _ => return Ok(ArchiveImport::default()),
+    };
+
+    let state = archive.state();
+    if !state.due {
+        log::info!("GitHub archive checked recently, skipping");
+        return Ok(ArchiveImport::default());
+    }
+
+    let (mut images, final_state) = match archive.fetch_since(&state)? {
+        ArchiveFetch::NotModified => {
+            archive.set_state(ArchiveSyncState { due: false, ..state });
+            archive.mark_checked();
+            save_archive_state_sync(conn, source)?;
+            return Ok(ArchiveImport::default());
+        }
+        ArchiveFetch::Modified { images, state } => (images, state),
+    };
+
+    // Oldest first, so everything up to a checkpoint's watermark is stored
+    images.sort_by(|a, b| a.startdate.cmp(&b.startdate));
+
+    let mut import = ArchiveImport { total: images.len(), ..Default::default() };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i32;
+
+    for chunk in images.chunks(ARCHIVE_IMPORT_CHUNK) {
+        if cancel.load(Ordering::SeqCst) {
+            log::info!("GitHub archive import cancelled after {}/{} rows", import.stored, import.total);
+            // Leave the archive alone for this session; the next launch resumes from the checkpoint
+            archive.set_state(ArchiveSyncState { due: false, ..archive.state() });
+            import.cancelled = true;
+            return Ok(import);
+        }
+
+        // Only checkpoint on a date boundary, a date may not be split across chunks
+        let next_date = images.get(import.stored + chunk.len()).and_then(archive_row_date);
+        let checkpoint = chunk
+            .last()
+            .and_then(archive_row_date)
+            .filter(|date| next_date.as_ref() != Some(date))
+            .map(|date| ArchiveSyncState { watermark: Some(date), due: true, ..Default::default() });
+
+        let records: Vec<_> = chunk
+            .iter()
+            .map(|image| image.to_new_record(
+                image.market_code.as_deref().unwrap_or(super::markets::DEFAULT_MARKET_CODE),
+                "unprocessed",
+                now,
+                now,
+            ))
+            .collect();
+        let urls: Vec<&str> = records.iter().map(|record| record.url).collect();
+
+        let added = conn.transaction::<_, anyhow::Error, _>(|conn| {
+            let existing = operations::get_existing_urls(conn, &urls)?.len();
+            operations::upsert_images_batch(conn, &records)?;
+            if let Some(checkpoint) = &checkpoint {
+                operations::set_config(conn, "github_archive_state", &serde_json::to_string(checkpoint)?)?;
+            }
+            Ok(records.len() - existing)
+        })?;
+
+        if let Some(checkpoint) = checkpoint {
+            archive.set_state(checkpoint);
+        }
+
+        import.added += added;
+        import.stored += chunk.len();
+        on_progress(import.stored, import.total);
+    }
+
+    archive.set_state(final_state);
+    archive.mark_checked();
+    save_archive_state_sync(conn, source)?;
+
+    if import.total > 0 {
+        log::info!("GitHub archive sync: {} rows, {} new", import.total, import.added);
+    }
+    Ok(import)
+}
+
+/// Persist the GitHub archive sync state if the archive was checked since the last save
+pub fn save_archive_state_sync(conn: &mut SqliteConnection, source: &super::sources::ImageSource) -> Result<()> {
+    use crate::db::operations;
+
+    let archive = match source.archive() {
+        Some(archive) if archive.take_checked() => archive,
+        _ => return Ok(()),
+    };
+
+    let json = serde_json::to_string(&archive.state())?;
+    operations::set_config(conn, "github_archive_state", &json)?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    operations::set_last_download_timestamp(conn, GITHUB_ARCHIVE_MANIFEST, now)
+}
+
 // ============================================================================
 // CLI-Specific: Desktop Wallpaper Matching
 // ============================================================================
@@ -267,7 +620,7 @@ pub fn get_current_desktop_wallpaper_url_sync(conn: &mut SqliteConnection) -> Re
 /// Mark current desktop wallpaper as favorite
 /// Returns image title if successful, None if no match found
 #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
-pub fn keep_current_wallpaper_sync(conn: &mut SqliteConnection) -> Result<Option<String>> {
+pub fn keep_current_wallpaper_sync(conn: &mut SqliteConnection, source: WallpaperSource) -> Result<Option<String>> {
     use crate::db::operations;
 
     // Get current wallpaper URL
@@ -282,12 +635,12 @@ pub fn keep_current_wallpaper_sync(conn: &mut SqliteConnection) -> Result<Option
 
     let title = image.title.clone();
 
-    // Update status to keepfavorite
-    operations::update_image_status(conn, &url, ImageStatus::KeepFavorite)?;
+    // Update status to keepfavorite (undo puts this wallpaper back)
+    super::undo::record_status_change_sync(conn, &url, ImageStatus::KeepFavorite, source, Some(&url))?;
 
     // Auto-advance: set next wallpaper (with rotation and auto-download)
     log::info!("Auto-advancing to next wallpaper after keep");
-    if let Err(e) = download_and_set_next_wallpaper_sync(conn) {
+    if let Err(e) = download_and_set_next_wallpaper_sync(conn, source) {
         log::warn!("Failed to auto-advance to next wallpaper: {}", e);
     }
 
@@ -297,7 +650,7 @@ pub fn keep_current_wallpaper_sync(conn: &mut SqliteConnection) -> Result<Option
 /// Mark current desktop wallpaper as blacklisted
 /// Returns image title if successful, None if no match found
 #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
-pub fn blacklist_current_wallpaper_sync(conn: &mut SqliteConnection) -> Result<Option<String>> {
+pub fn blacklist_current_wallpaper_sync(conn: &mut SqliteConnection, source: WallpaperSource) -> Result<Option<String>> {
     use crate::db::operations;
 
     // Get current wallpaper URL
@@ -312,68 +665,84 @@ pub fn blacklist_current_wallpaper_sync(conn: &mut SqliteConnection) -> Result<O
 
     let title = image.title.clone();
 
-    // Update status to blacklisted
-    operations::update_image_status(conn, &url, ImageStatus::Blacklisted)?;
+    // Update status to blacklisted (undo puts this wallpaper back)
+    super::undo::record_status_change_sync(conn, &url, ImageStatus::Blacklisted, source, Some(&url))?;
 
     // Auto-advance: set next wallpaper (with rotation and auto-download)
     log::info!("Auto-advancing to next wallpaper after blacklist");
-    if let Err(e) = download_and_set_next_wallpaper_sync(conn) {
+    if let Err(e) = download_and_set_next_wallpaper_sync(conn, source) {
         log::warn!("Failed to auto-advance to next wallpaper: {}", e);
     }
 
     Ok(Some(title))
 }
 
-/// Set a random favorite as desktop wallpaper
-/// Returns image title if successful, None if no favorites available
+/// Set the star rating of the current desktop wallpaper (0 clears it)
+/// Returns image title if successful, None if no match found
 #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
-pub fn set_random_favorite_wallpaper_sync(conn: &mut SqliteConnection) -> Result<Option<String>> {
+pub fn rate_current_wallpaper_sync(conn: &mut SqliteConnection, rating: i32) -> Result<Option<String>> {
     use crate::db::operations;
-    use rand::seq::SliceRandom;
 
-    // Query all favorites
-    let favorites = operations::get_images_by_status(conn, ImageStatus::KeepFavorite)?;
-
-    if favorites.is_empty() {
+    let Some(url) = get_current_desktop_wallpaper_url_sync(conn)? else {
         return Ok(None);
-    }
-
-    // Pick one randomly
-    let mut rng = rand::thread_rng();
-    let image = favorites.choose(&mut rng)
-        .ok_or_else(|| anyhow::anyhow!("Failed to pick random favorite"))?;
+    };
+    let image = operations::get_image(conn, &url)?
+        .ok_or_else(|| anyhow::anyhow!("Image not found in database"))?;
 
-    // Try to load from cache first
-    let bytes = if let Some(cached_bytes) = load_image_from_cache(&image.url)? {
-        log::info!("Using cached image for: {}", image.title);
-        cached_bytes
-    } else {
-        log::info!("Cache miss, downloading image for: {}", image.title);
-        // Download image bytes on-demand via ehttp (synchronous)
-        let (tx, rx) = std::sync::mpsc::channel();
-        ehttp::fetch(ehttp::Request::get(&image.url), move |response| {
-            let _ = tx.send(response);
-        });
+    operations::set_image_rating(conn, &url, rating)?;
+    log::info!("Rated {}: {} stars", url, rating);
+    Ok(Some(image.title))
+}
 
-        let response = rx.recv_timeout(std::time::Duration::from_secs(30))
-            .map_err(|_| anyhow::anyhow!("Image download timeout"))?;
+/// Star rating of the current desktop wallpaper (None if no match found)
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub fn get_current_wallpaper_rating_sync(conn: &mut SqliteConnection) -> Result<Option<i32>> {
+    let Some(url) = get_current_desktop_wallpaper_url_sync(conn)? else {
+        return Ok(None);
+    };
+    Ok(crate::db::operations::get_image(conn, &url)?.map(|image| image.rating))
+}
 
-        let downloaded_bytes = response
-            .map_err(|e| anyhow::anyhow!("Image download failed: {}", e))?
-            .bytes;
+/// Set a random favorite (or image of the rotation collection) as desktop wallpaper
+/// Higher rated images that were not shown recently are picked more often.
+/// Returns image title if successful, None if no favorites available
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub fn set_random_favorite_wallpaper_sync(conn: &mut SqliteConnection, source: WallpaperSource) -> Result<Option<String>> {
+    // Favorites, or the rotation collection when one is chosen
+    let image = super::rotation::pick_rotation_image_sync(conn)?;
+    set_picked_wallpaper_sync(conn, image, source)
+}
 
-        // Save to cache for future use
-        save_image_to_cache(&image.url, &downloaded_bytes)?;
+/// Set a random image of a collection as desktop wallpaper, weighted like favorites
+/// Returns image title if successful, None if the collection has no images to show
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub fn set_random_collection_wallpaper_sync(
+    conn: &mut SqliteConnection,
+    collection_id: i32,
+    source: WallpaperSource,
+) -> Result<Option<String>> {
+    let image = super::rotation::pick_collection_image_sync(conn, collection_id)?;
+    set_picked_wallpaper_sync(conn, image, source)
+}
 
-        downloaded_bytes
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+fn set_picked_wallpaper_sync(
+    conn: &mut SqliteConnection,
+    image: Option<crate::db::BingImage>,
+    source: WallpaperSource,
+) -> Result<Option<String>> {
+    let Some(image) = image else {
+        return Ok(None);
     };
 
+    // Try to load from cache first, then download in the preferred resolution
+    let bytes = load_wallpaper_bytes_sync(conn, &image)?;
+
     // Set wallpaper
     crate::api_setwallpaper::set_wallpaper_from_bytes(&bytes)?;
 
-    // Track current wallpaper URL in config for detection
-    operations::set_config(conn, "current_wallpaper_url", &image.url)?;
-    log::debug!("Tracked current wallpaper URL: {}", image.url);
+    // Record in history and track as current wallpaper for detection
+    super::history::record_wallpaper_set_sync(conn, &image.url, source, None, None)?;
 
     Ok(Some(image.title.clone()))
 }
@@ -385,10 +754,34 @@ pub fn set_random_favorite_wallpaper_sync(conn: &mut SqliteConnection) -> Result
 /// Download next wallpaper if needed, then set it as desktop wallpaper
 /// Returns WallpaperSetResult with title and URL
 #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
-pub fn download_and_set_next_wallpaper_sync(conn: &mut SqliteConnection) -> Result<crate::viewmodel::WallpaperSetResult> {
+pub fn download_and_set_next_wallpaper_sync(
+    conn: &mut SqliteConnection,
+    source: WallpaperSource,
+) -> Result<crate::viewmodel::WallpaperSetResult> {
+    let sources = image_source_from_settings_sync(conn)?;
+    download_and_set_next_wallpaper_with_sources_sync(conn, &sources, source)
+}
+
+/// Download next wallpaper from the given source registry if needed, then set it as desktop wallpaper
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub fn download_and_set_next_wallpaper_with_sources_sync(
+    conn: &mut SqliteConnection,
+    sources: &super::sources::ImageSource,
+    source: WallpaperSource,
+) -> Result<crate::viewmodel::WallpaperSetResult> {
     use crate::db::operations;
     use crate::schema::bing_images;
 
+    // Pick up new or removed files in local image directories
+    if let Err(e) = super::local_source::sync_local_images_sync(conn, sources.settings()) {
+        log::warn!("Failed to rescan local directories: {}", e);
+    }
+
+    // Pick up rows appended to the GitHub archive (conditional, at most weekly)
+    if let Err(e) = sync_github_archive_sync(conn, sources) {
+        log::warn!("GitHub archive sync failed: {}", e);
+    }
+
     // Step 1: Check count of unprocessed images
     let unprocessed_count = operations::count_by_status(conn, crate::db::ImageStatus::Unprocessed)?;
     log::info!("Unprocessed images count: {}", unprocessed_count);
@@ -397,7 +790,8 @@ pub fn download_and_set_next_wallpaper_sync(conn: &mut SqliteConnection) -> Resu
     if unprocessed_count < 7 {
         log::info!("Unprocessed count ({}) < 7, downloading new page", unprocessed_count);
 
-        let (market_code_str, _offset) = get_market_state_sync(conn)?;
+        // Pick the next market page in rotation (None = all markets drained this week)
+        let market_page = super::markets::next_market_page_sync(conn)?;
 
         // Get all existing URLs to avoid re-downloading
         let existing_urls: Vec<String> = {
@@ -408,18 +802,23 @@ pub fn download_and_set_next_wallpaper_sync(conn: &mut SqliteConnection) -> Resu
         };
         log::info!("Found {} existing URLs in database", existing_urls.len());
 
-        // Use ImageSource to fetch from both Bing API and GitHub archive
+        // Fetch from every enabled source in the registry
         // Pass existing URLs so it can skip them and return next batch
-        let sources = crate::viewmodel::sources::ImageSource::new(None);
-        let images = sources.fetch_images(20, &existing_urls)
+        let fetched = sources.fetch_images(market_page.as_ref(), 20, &existing_urls)
             .context("Failed to fetch images from sources")?;
 
+        // Only a page Bing actually served moves the market rotation on
+        if let Some(page) = market_page.as_ref().filter(|_| fetched.answered(super::sources::BING_API_PROVIDER)) {
+            super::markets::advance_market_page_sync(conn, page)?;
+        }
+        let images = fetched.images;
+
         if images.is_empty() {
             anyhow::bail!("No images returned from sources");
         }
 
         let fetched_count = images.len();
-        log::info!("Fetched {} images from dual sources (Bing + GitHub)", fetched_count);
+        log::info!("Fetched {} images from image sources", fetched_count);
 
         // Filter out already-downloaded images
         let new_images: Vec<_> = images.into_iter()
@@ -439,21 +838,16 @@ pub fn download_and_set_next_wallpaper_sync(conn: &mut SqliteConnection) -> Resu
                 .unwrap()
                 .as_secs() as i32;
 
-            for img in &new_images {
-                let new_img = NewBingImage {
-                    url: &img.url,
-                    title: &img.title,
-                    copyright: img.copyright.as_deref(),
-                    copyright_link: img.copyright_link.as_deref(),
-                    market_code: &market_code_str,
-                    status: "unprocessed",
-                    fetched_at: current_timestamp,
-                    created_at: current_timestamp,
-                    updated_at: current_timestamp,
-                };
-
-                operations::upsert_image(conn, &new_img)?;
-            }
+            let records: Vec<_> = new_images
+                .iter()
+                .map(|img| img.to_new_record(
+                    img.market_code.as_deref().unwrap_or(super::markets::DEFAULT_MARKET_CODE),
+                    "unprocessed",
+                    current_timestamp,
+                    current_timestamp,
+                ))
+                .collect();
+            operations::upsert_images_batch(conn, &records)?;
 
             log::info!("Inserted {} new images into database", new_images.len());
         } else {
@@ -464,7 +858,8 @@ pub fn download_and_set_next_wallpaper_sync(conn: &mut SqliteConnection) -> Resu
     // Step 3: Get next unprocessed image (with rotation if at end)
     let unprocessed_list = bing_images::table
         .filter(bing_images::status.eq("unprocessed"))
-        .order(bing_images::fetched_at.desc())
+        .filter(bing_images::canonical_id.is_null()) // never show the same scene twice
+        .order(operations::by_publication_date())
         .load::<crate::db::BingImage>(conn)?;
 
     if unprocessed_list.is_empty() {
@@ -496,37 +891,15 @@ pub fn download_and_set_next_wallpaper_sync(conn: &mut SqliteConnection) -> Resu
         unprocessed_list[0].clone()
     };
     
-    // Step 3: Download image bytes on-demand (with caching)
-    let bytes = if let Some(cached_bytes) = load_image_from_cache(&image.url)? {
-        log::info!("Using cached image: {}", image.title);
-        cached_bytes
-    } else {
-        log::info!("Downloading image bytes: {}", image.url);
-        let (tx, rx) = std::sync::mpsc::channel();
-        ehttp::fetch(ehttp::Request::get(&image.url), move |response| {
-            let _ = tx.send(response);
-        });
-
-        let response = rx.recv_timeout(std::time::Duration::from_secs(30))
-            .map_err(|_| anyhow::anyhow!("Image download timeout"))?;
-
-        let downloaded_bytes = response
-            .map_err(|e| anyhow::anyhow!("Image download failed: {}", e))?
-            .bytes;
-
-        // Save to cache for future use
-        save_image_to_cache(&image.url, &downloaded_bytes)?;
-
-        downloaded_bytes
-    };
+    // Step 3: Download image bytes on-demand in the preferred resolution (with caching)
+    let bytes = load_wallpaper_bytes_sync(conn, &image)?;
     
     // Step 4: Set wallpaper
     log::info!("Setting wallpaper: {}", image.title);
     crate::api_setwallpaper::set_wallpaper_from_bytes(&bytes)?;
     
-    // Step 5: Track current wallpaper URL in config for detection
-    operations::set_config(conn, "current_wallpaper_url", &image.url)?;
-    log::debug!("Tracked current wallpaper URL: {}", image.url);
+    // Step 5: Record in history and track as current wallpaper for detection
+    super::history::record_wallpaper_set_sync(conn, &image.url, source, None, None)?;
     
     // Step 6: Return result
     Ok(crate::viewmodel::WallpaperSetResult {
@@ -549,17 +922,16 @@ use std::sync::Arc;
 pub fn keep_current_wallpaper_instant_sync(
     conn: &mut SqliteConnection,
     cache_mgr: &Arc<CacheManager>,
+    source: WallpaperSource,
 ) -> Result<String> {
-    use crate::db::operations;
-
     // 1. Get current wallpaper URL
     let url = get_current_desktop_wallpaper_url_sync(conn)?
         .ok_or_else(|| anyhow::anyhow!("No current wallpaper"))?;
 
     log::info!("Keeping current wallpaper: {}", url);
 
-    // 2. Mark as favorite (instant database update)
-    operations::update_image_status(conn, &url, ImageStatus::KeepFavorite)?;
+    // 2. Mark as favorite (instant database update, undo puts this wallpaper back)
+    super::undo::record_status_change_sync(conn, &url, ImageStatus::KeepFavorite, source, Some(&url))?;
 
     // 3. Get next cached image (pre-downloaded)
     let next_image = cache_mgr.get_next_cached_image()?
@@ -573,8 +945,8 @@ pub fn keep_current_wallpaper_instant_sync(
     // 5. Set wallpaper
     crate::api_setwallpaper::set_wallpaper_from_bytes(&bytes)?;
 
-    // 6. Update current wallpaper tracking
-    operations::set_config(conn, "current_wallpaper_url", &next_image.url)?;
+    // 6. Record in history and update current wallpaper tracking
+    super::history::record_wallpaper_set_sync(conn, &next_image.url, source, None, None)?;
 
     // 7. Trigger background cache refill if count < 3
     if cache_mgr.needs_refill()? {
@@ -594,17 +966,16 @@ pub fn keep_current_wallpaper_instant_sync(
 pub fn blacklist_current_wallpaper_instant_sync(
     conn: &mut SqliteConnection,
     cache_mgr: &Arc<CacheManager>,
+    source: WallpaperSource,
 ) -> Result<String> {
-    use crate::db::operations;
-
     // 1. Get current wallpaper URL
     let url = get_current_desktop_wallpaper_url_sync(conn)?
         .ok_or_else(|| anyhow::anyhow!("No current wallpaper"))?;
 
     log::info!("Blacklisting current wallpaper: {}", url);
 
-    // 2. Mark as blacklisted (instant database update)
-    operations::update_image_status(conn, &url, ImageStatus::Blacklisted)?;
+    // 2. Mark as blacklisted (instant database update, undo puts this wallpaper back)
+    super::undo::record_status_change_sync(conn, &url, ImageStatus::Blacklisted, source, Some(&url))?;
 
     // 3. Get next cached image (pre-downloaded)
     let next_image = cache_mgr.get_next_cached_image()?
@@ -618,8 +989,8 @@ pub fn blacklist_current_wallpaper_instant_sync(
     // 5. Set wallpaper
     crate::api_setwallpaper::set_wallpaper_from_bytes(&bytes)?;
 
-    // 6. Update current wallpaper tracking
-    operations::set_config(conn, "current_wallpaper_url", &next_image.url)?;
+    // 6. Record in history and update current wallpaper tracking
+    super::history::record_wallpaper_set_sync(conn, &next_image.url, source, None, None)?;
 
     // 7. Trigger background cache refill if count < 3
     if cache_mgr.needs_refill()? {
@@ -639,9 +1010,13 @@ pub fn blacklist_current_wallpaper_instant_sync(
 // ============================================================================
 
 /// Load image bytes from cache if available
-pub fn load_cached_image(url: &str) -> Result<Option<Vec<u8>>> {
-    let cache_dir = get_cache_dir()?;
-    let filename = get_cache_filename(url);
+pub fn load_cached_image(conn: &mut SqliteConnection, url: &str) -> Result<Option<Vec<u8>>> {
+    if let Some(path) = super::local_source::local_path_from_url(url) {
+        return Ok(Some(std::fs::read(&path)?));
+    }
+
+    let cache_dir = get_cache_dir(conn)?;
+    let filename = super::cache_manager::get_cache_filename(url);
     let cache_path = cache_dir.join(&filename);
 
     if cache_path.exists() {
@@ -656,6 +1031,11 @@ pub fn load_cached_image(url: &str) -> Result<Option<Vec<u8>>> {
 
 /// Download image from network (blocking)
 pub fn download_image(url: &str) -> Result<Vec<u8>> {
+    if let Some(path) = super::local_source::local_path_from_url(url) {
+        return std::fs::read(&path)
+            .with_context(|| format!("Failed to read local image: {:?}", path));
+    }
+
     log::info!("Downloading image: {}", url);
 
     let (tx, rx) = std::sync::mpsc::channel();
@@ -677,9 +1057,13 @@ pub fn download_image(url: &str) -> Result<Vec<u8>> {
 }
 
 /// Save image bytes to cache
-pub fn save_to_cache(url: &str, bytes: &[u8]) -> Result<()> {
-    let cache_dir = get_cache_dir()?;
-    let filename = get_cache_filename(url);
+pub fn save_to_cache(conn: &mut SqliteConnection, url: &str, bytes: &[u8]) -> Result<()> {
+    if super::local_source::is_local_url(url) {
+        return Ok(());
+    }
+
+    let cache_dir = get_cache_dir(conn)?;
+    let filename = super::cache_manager::get_cache_filename(url);
     let cache_path = cache_dir.join(&filename);
 
     std::fs::write(&cache_path, bytes)?;
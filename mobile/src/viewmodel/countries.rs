@@ -0,0 +1,83 @@
+//! Countries parsed from image copyrights (see `crate::location`)
+//!
+//! Random favorite rotation can be limited to a set of countries: images of
+//! other or unknown countries are then left out.
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+
+use crate::db::{operations, BingImage};
+
+/// Country images per carousel page
+pub const COUNTRY_PAGE_SIZE: usize = 20;
+
+/// Config key holding the comma-separated codes rotation is limited to
+const ROTATION_COUNTRIES_KEY: &str = "rotation_countries";
+
+/// A country with its image count
+#[derive(Debug, Clone)]
+pub struct CountrySummary {
+    /// ISO 3166-1 code
+    pub code: String,
+    /// English name (the code for countries missing from the table)
+    pub name: String,
+    pub image_count: usize,
+}
+
+/// Countries that have images, most images first
+pub fn get_country_summaries_sync(conn: &mut SqliteConnection) -> Result<Vec<CountrySummary>> {
+    Ok(operations::count_images_by_country(conn)?
+        .into_iter()
+        .map(|(code, count)| CountrySummary {
+            name: crate::location::country(&code).map_or_else(|| code.clone(), |country| country.name.to_string()),
+            code,
+            image_count: count as usize,
+        })
+        .collect())
+}
+
+/// Load a page of a country's images, newest publication first
+/// Returns the images and the country's total image count.
+pub fn get_country_page_sync(
+    conn: &mut SqliteConnection,
+    country: &str,
+    page: usize,
+) -> Result<(Vec<BingImage>, usize)> {
+    let offset = (page * COUNTRY_PAGE_SIZE) as i64;
+    let images = operations::get_images_by_country(conn, country, COUNTRY_PAGE_SIZE as i64, offset)?;
+    let total_count = operations::count_images_in_country(conn, country)? as usize;
+    Ok((images, total_count))
+}
+
+/// Country codes rotation is limited to (empty = all countries)
+pub fn get_rotation_countries_sync(conn: &mut SqliteConnection) -> Result<Vec<String>> {
+    Ok(operations::get_config(conn, ROTATION_COUNTRIES_KEY)?
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|code| !code.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Limit rotation to countries (empty = all countries)
+pub fn set_rotation_countries_sync(conn: &mut SqliteConnection, countries: &[String]) -> Result<()> {
+    operations::set_config(conn, ROTATION_COUNTRIES_KEY, &countries.join(","))
+}
+
+/// Keep the rotation candidates of the chosen countries (all when none are chosen)
+pub fn filter_rotation_countries_sync(conn: &mut SqliteConnection, images: Vec<BingImage>) -> Result<Vec<BingImage>> {
+    let countries = get_rotation_countries_sync(conn)?;
+    if countries.is_empty() {
+        return Ok(images);
+    }
+
+    let images: Vec<BingImage> = images
+        .into_iter()
+        .filter(|image| image.country.as_ref().is_some_and(|country| countries.contains(country)))
+        .collect();
+    if images.is_empty() {
+        log::info!("No rotation images from {}", countries.join(", "));
+    }
+    Ok(images)
+}
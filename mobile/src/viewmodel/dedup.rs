@@ -0,0 +1,105 @@
+//! Perceptual-hash deduplication
+//!
+//! Bing republishes the same photo under different OHR ids across markets and
+//! years, and other sources mirror it under unrelated URLs. Once an image's
+//! bytes are cached we compute a dHash; rows within `DUPLICATE_DISTANCE` bits
+//! of an existing canonical row are linked to it via `canonical_id` and left
+//! out of the rotation.
+
+use anyhow::{Context, Result};
+use diesel::SqliteConnection;
+
+use crate::db::operations;
+
+/// Maximum Hamming distance (out of 64 bits) between hashes of the same scene
+pub const DUPLICATE_DISTANCE: u32 = 6;
+
+/// Compute the 64-bit difference hash (dHash) of encoded image bytes
+///
+/// The image is reduced to 9x8 grayscale and each bit records whether a
+/// pixel is brighter than its right neighbour, so re-encodes, crops of a few
+/// pixels and resolution variants hash (nearly) the same.
+pub fn dhash(bytes: &[u8]) -> Result<u64> {
+    let image = image::load_from_memory(bytes).context("Failed to decode image")?;
+    let small = image::imageops::resize(
+        &image.to_luma8(),
+        9,
+        8,
+        image::imageops::FilterType::Triangle,
+    );
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+
+    Ok(hash)
+}
+
+/// Number of differing bits between two hashes
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Hex form stored in `bing_images.phash`
+pub fn hash_to_hex(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+pub fn hash_from_hex(hex: &str) -> Option<u64> {
+    u64::from_str_radix(hex, 16).ok()
+}
+
+/// Pick the canonical row a hash duplicates: the closest within `DUPLICATE_DISTANCE`,
+/// the oldest row on ties
+pub fn find_canonical(hash: u64, candidates: &[(i32, u64)]) -> Option<i32> {
+    candidates
+        .iter()
+        .map(|&(id, other)| (hamming_distance(hash, other), id))
+        .filter(|&(distance, _)| distance <= DUPLICATE_DISTANCE)
+        .min()
+        .map(|(_, id)| id)
+}
+
+/// Hash an image's cached bytes and link it to its canonical row
+///
+/// Returns the canonical id if the image is a near-duplicate. Images that
+/// already have a hash are left as they are.
+pub fn record_image_hash_sync(conn: &mut SqliteConnection, url: &str, bytes: &[u8]) -> Result<Option<i32>> {
+    let image = match operations::get_image(conn, url)? {
+        Some(image) => image,
+        None => return Ok(None),
+    };
+
+    if image.phash.is_some() {
+        return Ok(image.canonical_id);
+    }
+
+    let hash = dhash(bytes)?;
+    operations::set_image_phash(conn, url, &hash_to_hex(hash))?;
+
+    let candidates: Vec<(i32, u64)> = operations::get_canonical_hashes(conn)?
+        .into_iter()
+        .filter(|(id, _)| *id != image.id)
+        .filter_map(|(id, hex)| hash_from_hex(&hex).map(|hash| (id, hash)))
+        .collect();
+
+    let canonical = find_canonical(hash, &candidates);
+    if let Some(canonical_id) = canonical {
+        log::info!("{} is a near-duplicate of image #{}", image.title, canonical_id);
+        operations::set_canonical_id(conn, image.id, Some(canonical_id))?;
+    }
+
+    Ok(canonical)
+}
+
+/// Hash an image, logging instead of failing (hashing must never block a download)
+pub fn try_record_image_hash_sync(conn: &mut SqliteConnection, url: &str, bytes: &[u8]) {
+    if let Err(e) = record_image_hash_sync(conn, url, bytes) {
+        log::warn!("Failed to hash {}: {}", url, e);
+    }
+}
@@ -0,0 +1,175 @@
+//! Wallpaper history backed by the `wallpaper_history` table
+//!
+//! Every setter records the wallpaper here (and as `current_wallpaper_url`).
+//! "Previous" walks back through the history: the entry it went back to is
+//! remembered, so going back again continues from there until another setter
+//! records a new wallpaper.
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::CropCoords;
+use crate::db::models::NewWallpaperHistoryEntry;
+use crate::db::{operations, WallpaperHistoryEntry, WallpaperSource};
+
+/// History entries per page
+pub const HISTORY_PAGE_SIZE: usize = 20;
+
+/// Config key holding the id of the entry "previous" last went back to
+const HISTORY_CURSOR_KEY: &str = "history_cursor";
+
+/// A history entry with its image title and how long it was shown
+#[derive(Debug, Clone)]
+pub struct HistoryItem {
+    pub entry: WallpaperHistoryEntry,
+    /// Title of the image (None if it is no longer in the database)
+    pub title: Option<String>,
+    /// Seconds until the next wallpaper was set (until now for the current one)
+    pub shown_secs: i64,
+}
+
+/// A page of history, newest first
+#[derive(Debug, Clone, Default)]
+pub struct HistoryPage {
+    pub page: usize,
+    pub items: Vec<HistoryItem>,
+    pub total: usize,
+}
+
+fn current_timestamp() -> i32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i32
+}
+
+fn insert_entry(
+    conn: &mut SqliteConnection,
+    url: &str,
+    source: WallpaperSource,
+    monitor: Option<&str>,
+    crop: Option<&CropCoords>,
+) -> Result<WallpaperHistoryEntry> {
+    let crop_json = crop.map(CropCoords::to_json).transpose()?;
+    let entry = operations::insert_history_entry(conn, &NewWallpaperHistoryEntry {
+        url,
+        set_at: current_timestamp(),
+        source: source.as_str(),
+        monitor,
+        crop_coords: crop_json.as_deref(),
+    })?;
+
+    operations::set_config(conn, "current_wallpaper_url", url)?;
+    log::debug!("Recorded wallpaper {} (set by {})", url, source.as_str());
+    Ok(entry)
+}
+
+/// Record a wallpaper that was just set and track it as the current wallpaper
+///
+/// `monitor` is None when the wallpaper was set on all monitors, `crop` is the
+/// crop applied to the image (None for the full image).
+pub fn record_wallpaper_set_sync(
+    conn: &mut SqliteConnection,
+    url: &str,
+    source: WallpaperSource,
+    monitor: Option<&str>,
+    crop: Option<&CropCoords>,
+) -> Result<WallpaperHistoryEntry> {
+    let entry = insert_entry(conn, url, source, monitor, crop)?;
+    operations::set_config(conn, HISTORY_CURSOR_KEY, "")?;
+    Ok(entry)
+}
+
+/// Entry "previous" goes back to (None at the start of the history)
+pub fn previous_entry_sync(conn: &mut SqliteConnection) -> Result<Option<WallpaperHistoryEntry>> {
+    let current_url = operations::get_config(conn, "current_wallpaper_url")?.unwrap_or_default();
+    let cursor = operations::get_config(conn, HISTORY_CURSOR_KEY)?
+        .and_then(|value| value.parse::<i32>().ok());
+
+    let before_id = match cursor {
+        Some(id) => id,
+        None => match operations::get_history_entries(conn, 0, 1)?.first() {
+            Some(latest) => latest.id,
+            None => return Ok(None),
+        },
+    };
+
+    operations::get_history_entry_before(conn, before_id, &current_url)
+}
+
+/// Record that "previous" went back to `entry`, so the next step continues before it
+pub fn record_previous_set_sync(
+    conn: &mut SqliteConnection,
+    entry: &WallpaperHistoryEntry,
+    source: WallpaperSource,
+) -> Result<()> {
+    let crop = entry.crop_coords.as_deref().and_then(|json| CropCoords::from_json(json).ok());
+    insert_entry(conn, &entry.url, source, entry.monitor.as_deref(), crop.as_ref())?;
+    operations::set_config(conn, HISTORY_CURSOR_KEY, &entry.id.to_string())?;
+    Ok(())
+}
+
+/// Load a page of history (newest first) with titles and display durations
+pub fn get_history_page_sync(conn: &mut SqliteConnection, page: usize) -> Result<HistoryPage> {
+    let total = operations::count_history_entries(conn)? as usize;
+    let offset = page * HISTORY_PAGE_SIZE;
+
+    // The entry after the page (newer) ends the display of the page's first entry
+    let (query_offset, newer_included) = if offset > 0 { (offset - 1, true) } else { (0, false) };
+    let limit = HISTORY_PAGE_SIZE + usize::from(newer_included);
+    let mut entries = operations::get_history_entries(conn, query_offset as i64, limit as i64)?;
+
+    let mut next_set_at = if newer_included && !entries.is_empty() {
+        entries.remove(0).set_at
+    } else {
+        current_timestamp()
+    };
+
+    let mut items = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let title = operations::get_image(conn, &entry.url)?.map(|image| image.title);
+        let shown_secs = i64::from(next_set_at - entry.set_at).max(0);
+        next_set_at = entry.set_at;
+        items.push(HistoryItem { entry, title, shown_secs });
+    }
+
+    Ok(HistoryPage { page, items, total })
+}
+
+/// Total seconds each image was shown as wallpaper, longest first
+pub fn time_shown_per_image_sync(conn: &mut SqliteConnection) -> Result<Vec<(String, i64)>> {
+    let timeline = operations::get_history_timeline(conn)?;
+    let now = current_timestamp();
+
+    let mut totals: HashMap<String, i64> = HashMap::new();
+    for (i, (_, url, set_at)) in timeline.iter().enumerate() {
+        let until = timeline.get(i + 1).map_or(now, |(_, _, next)| *next);
+        *totals.entry(url.clone()).or_default() += i64::from(until - set_at).max(0);
+    }
+
+    let mut totals: Vec<(String, i64)> = totals.into_iter().collect();
+    totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(totals)
+}
+
+/// Total seconds an image was shown as wallpaper
+pub fn time_shown_sync(conn: &mut SqliteConnection, url: &str) -> Result<i64> {
+    Ok(time_shown_per_image_sync(conn)?
+        .into_iter()
+        .find(|(shown_url, _)| shown_url == url)
+        .map_or(0, |(_, secs)| secs))
+}
+
+/// Short duration for history listings ("3d 4h", "2h 5m", "12m")
+pub fn format_duration(secs: i64) -> String {
+    let (days, hours, minutes) = (secs / 86_400, secs % 86_400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
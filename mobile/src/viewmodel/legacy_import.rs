@@ -0,0 +1,342 @@
+//! One-shot import of the file-based layout used by older releases
+//!
+//! Older versions kept wallpapers as files in `unprocessed/` and `keepfavorite/`,
+//! blacklisted images in `blacklist.conf` and the market rotation in
+//! `marketcodes.conf`. Files and lines are matched to `bing_images` rows by OHR
+//! identifier, so every market's copy of a photo is matched; blacklist lines that
+//! are Bing `hsh` values match by hash. Only unprocessed rows change status, so
+//! choices already made in this version win. The old files are left in place.
+
+use anyhow::Result;
+use diesel::{Connection, SqliteConnection};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::db::{operations, ImageStatus};
+
+/// Config key set once the legacy layout was imported (or found absent)
+pub const LEGACY_IMPORT_DONE_KEY: &str = "legacy_import_done";
+
+/// Config key holding the files and blacklist lines still to match (JSON list)
+pub const LEGACY_IMPORT_PENDING_KEY: &str = "legacy_import_pending";
+
+pub const UNPROCESSED_DIR: &str = "unprocessed";
+pub const KEEPFAVORITE_DIR: &str = "keepfavorite";
+pub const BLACKLIST_FILE: &str = "blacklist.conf";
+pub const MARKETCODES_FILE: &str = "marketcodes.conf";
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "bmp"];
+
+/// What a legacy import matched, and what it could not
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LegacyImportReport {
+    /// `keepfavorite/` files matched to images
+    pub favorites: usize,
+    /// `blacklist.conf` lines matched to images
+    pub blacklisted: usize,
+    /// `unprocessed/` files found in the database
+    pub unprocessed: usize,
+    /// `marketcodes.conf` markets imported
+    pub markets: usize,
+    /// Files and lines that matched no image or market
+    pub unmatched: Vec<String>,
+    /// The part of `unmatched` that may match images imported later
+    pub retryable: Vec<String>,
+}
+
+impl LegacyImportReport {
+    /// One-line summary for logs and the CLI
+    pub fn summary(&self) -> String {
+        format!(
+            "{} favorites, {} blacklisted, {} unprocessed, {} markets imported; {} unmatched",
+            self.favorites,
+            self.blacklisted,
+            self.unprocessed,
+            self.markets,
+            self.unmatched.len()
+        )
+    }
+}
+
+/// OHR identifier of a legacy file name, blacklist entry or URL
+///
+/// "OHR.Hnausapollur_EN-US2080493040_1920x1080.jpg", "th?id=OHR.Hnausapollur_UHD.jpg"
+/// and "Hnausapollur.jpg" all give "OHR.Hnausapollur", the form `extract_identifier`
+/// returns for Bing URLs.
+pub fn legacy_identifier(name: &str) -> Option<String> {
+    let name = name.trim().rsplit(['/', '\\']).next()?;
+    let rest = match name.find("OHR.") {
+        Some(start) => &name[start + "OHR.".len()..],
+        None => name,
+    };
+
+    let stem = rest.split(['_', '.', '&']).next()?;
+    if stem.is_empty() || !stem.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+
+    Some(format!("OHR.{}", stem))
+}
+
+/// Parse a `marketcodes.conf` line: "en-US|1700000000" (or "=", ",", whitespace; timestamp optional)
+pub fn parse_market_line(line: &str) -> Option<(String, Option<i64>)> {
+    let mut fields = line
+        .split(|c: char| matches!(c, '|' | '=' | ',' | ';') || c.is_whitespace())
+        .filter(|field| !field.is_empty());
+
+    let code = normalize_market_code(fields.next()?)?;
+    let timestamp = fields.next().and_then(|field| field.parse().ok());
+    Some((code, timestamp))
+}
+
+/// "en-us" / "EN_US" -> "en-US"
+fn normalize_market_code(code: &str) -> Option<String> {
+    let (lang, region) = code.split_once(['-', '_'])?;
+    if lang.len() != 2 || region.len() != 2 || !lang.chars().chain(region.chars()).all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some(format!("{}-{}", lang.to_lowercase(), region.to_uppercase()))
+}
+
+fn is_bing_hash(entry: &str) -> bool {
+    entry.len() >= 16 && entry.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Whether a directory holds any part of the legacy layout
+pub fn has_legacy_files(root: &Path) -> bool {
+    root.join(BLACKLIST_FILE).is_file()
+        || root.join(MARKETCODES_FILE).is_file()
+        || !list_image_files(&root.join(KEEPFAVORITE_DIR)).is_empty()
+        || !list_image_files(&root.join(UNPROCESSED_DIR)).is_empty()
+}
+
+/// Image files directly in `dir`, sorted by name (missing directory = none)
+fn list_image_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Non-empty, non-comment lines of a config file (missing file = none)
+fn read_entries(path: &Path) -> Result<Vec<String>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Image URLs by OHR identifier and by Bing hash
+struct ImageIndex {
+    by_identifier: HashMap<String, Vec<String>>,
+    by_hash: HashMap<String, Vec<String>>,
+}
+
+impl ImageIndex {
+    fn load(conn: &mut SqliteConnection) -> Result<Self> {
+        let mut index = Self {
+            by_identifier: HashMap::new(),
+            by_hash: HashMap::new(),
+        };
+
+        for (url, hsh) in operations::get_urls_and_hashes(conn)? {
+            if let Some(identifier) = super::sources::extract_identifier(&url) {
+                index.by_identifier.entry(identifier).or_default().push(url.clone());
+            }
+            if let Some(hsh) = hsh.filter(|hsh| !hsh.is_empty()) {
+                index.by_hash.entry(hsh.to_lowercase()).or_default().push(url);
+            }
+        }
+
+        Ok(index)
+    }
+
+    fn by_name(&self, name: &str) -> &[String] {
+        legacy_identifier(name)
+            .and_then(|identifier| self.by_identifier.get(&identifier))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    fn by_entry(&self, entry: &str) -> &[String] {
+        if is_bing_hash(entry) {
+            if let Some(urls) = self.by_hash.get(&entry.to_lowercase()) {
+                return urls;
+            }
+        }
+        self.by_name(entry)
+    }
+}
+
+/// Set the status of images that are still unprocessed
+fn mark_unprocessed(conn: &mut SqliteConnection, urls: &[String], status: ImageStatus) -> Result<()> {
+    for url in urls {
+        let unprocessed = operations::get_image(conn, url)?
+            .is_some_and(|image| ImageStatus::from_str(&image.status) == Some(ImageStatus::Unprocessed));
+        if unprocessed {
+            operations::update_image_status(conn, url, status)?;
+        }
+    }
+    Ok(())
+}
+
+/// Import the legacy layout found in `roots` (runs every time; see `import_legacy_layout_once_sync`)
+pub fn import_legacy_layout_sync(conn: &mut SqliteConnection, roots: &[PathBuf]) -> Result<LegacyImportReport> {
+    import_entries_sync(conn, roots, None)
+}
+
+/// Import the legacy layout, limited to the image entries in `only` when given
+/// (markets are then skipped, they were imported by the first run)
+fn import_entries_sync(
+    conn: &mut SqliteConnection,
+    roots: &[PathBuf],
+    only: Option<&[String]>,
+) -> Result<LegacyImportReport> {
+    let index = ImageIndex::load(conn)?;
+    let mut report = LegacyImportReport::default();
+    let wanted = |key: &str| only.map_or(true, |only| only.iter().any(|entry| entry == key));
+
+    conn.transaction(|conn| {
+        // Blacklist first: a photo both kept and blacklisted stays blacklisted
+        for root in roots {
+            let path = root.join(BLACKLIST_FILE);
+            for entry in read_entries(&path)? {
+                let key = format!("{}: {}", path.display(), entry);
+                if !wanted(&key) {
+                    continue;
+                }
+                let urls = index.by_entry(&entry);
+                if urls.is_empty() {
+                    report.unmatched.push(key.clone());
+                    report.retryable.push(key);
+                } else {
+                    mark_unprocessed(conn, urls, ImageStatus::Blacklisted)?;
+                    report.blacklisted += 1;
+                }
+            }
+        }
+
+        for root in roots {
+            for file in list_image_files(&root.join(KEEPFAVORITE_DIR)) {
+                let key = file.display().to_string();
+                if !wanted(&key) {
+                    continue;
+                }
+                let urls = index.by_name(&file.file_name().unwrap_or_default().to_string_lossy());
+                if urls.is_empty() {
+                    report.unmatched.push(key.clone());
+                    report.retryable.push(key);
+                } else {
+                    mark_unprocessed(conn, urls, ImageStatus::KeepFavorite)?;
+                    report.favorites += 1;
+                }
+            }
+
+            // Unprocessed rows stay in the rotation as they are
+            for file in list_image_files(&root.join(UNPROCESSED_DIR)) {
+                let key = file.display().to_string();
+                if !wanted(&key) {
+                    continue;
+                }
+                if index.by_name(&file.file_name().unwrap_or_default().to_string_lossy()).is_empty() {
+                    report.unmatched.push(key.clone());
+                    report.retryable.push(key);
+                } else {
+                    report.unprocessed += 1;
+                }
+            }
+
+            if only.is_some() {
+                continue;
+            }
+            let path = root.join(MARKETCODES_FILE);
+            for entry in read_entries(&path)? {
+                let Some((code, last_used)) = parse_market_line(&entry) else {
+                    report.unmatched.push(format!("{}: {}", path.display(), entry));
+                    continue;
+                };
+
+                operations::ensure_market_codes(conn, &[code.as_str()])?;
+                if let Some(last_used) = last_used.filter(|&ts| ts > 0) {
+                    operations::set_market_last_used(conn, &code, last_used.min(i32::MAX as i64) as i32)?;
+                }
+                report.markets += 1;
+            }
+        }
+
+        Ok::<_, anyhow::Error>(())
+    })?;
+
+    log::info!("Legacy import: {}", report.summary());
+    for unmatched in &report.unmatched {
+        log::warn!("Legacy import: no match for {}", unmatched);
+    }
+
+    Ok(report)
+}
+
+/// Import the legacy layout once per database
+///
+/// Returns None when it already ran or there is nothing to import. Files are
+/// matched against known images, so while the image table is still empty the
+/// import waits for a later call (after the first archive import or download).
+/// Files and blacklist lines that match nothing yet are retried on later calls
+/// (and only those), until they all matched or the whole GitHub archive was
+/// imported before the attempt. Retries that match nothing return None.
+pub fn import_legacy_layout_once_sync(
+    conn: &mut SqliteConnection,
+    roots: &[PathBuf],
+) -> Result<Option<LegacyImportReport>> {
+    if operations::get_config(conn, LEGACY_IMPORT_DONE_KEY)?.is_some() {
+        return Ok(None);
+    }
+
+    let done = |conn: &mut SqliteConnection| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        operations::set_config(conn, LEGACY_IMPORT_DONE_KEY, &now.to_string())
+    };
+
+    if !roots.iter().any(|root| has_legacy_files(root)) {
+        done(conn)?;
+        return Ok(None);
+    }
+
+    if operations::get_urls_and_hashes(conn)?.is_empty() {
+        log::info!("Legacy layout found, importing once images are known");
+        return Ok(None);
+    }
+
+    let pending: Option<Vec<String>> = operations::get_config(conn, LEGACY_IMPORT_PENDING_KEY)?
+        .and_then(|json| serde_json::from_str(&json).ok());
+    // Once every archive row is known, nothing unmatched will match later
+    let archive_imported =
+        operations::get_last_download_timestamp(conn, super::commands::GITHUB_ARCHIVE_MANIFEST)?.is_some();
+
+    let report = import_entries_sync(conn, roots, pending.as_deref())?;
+    if report.retryable.is_empty() || archive_imported {
+        done(conn)?;
+    } else {
+        log::info!("Legacy import: {} entries left to match later", report.retryable.len());
+        operations::set_config(conn, LEGACY_IMPORT_PENDING_KEY, &serde_json::to_string(&report.retryable)?)?;
+    }
+
+    let matched = report.favorites + report.blacklisted + report.unprocessed + report.markets;
+    Ok((pending.is_none() || matched > 0).then_some(report))
+}
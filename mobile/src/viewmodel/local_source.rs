@@ -0,0 +1,272 @@
+//! Local directory image source
+//!
+//! Scans user-chosen directories for JPEG/PNG files. Files are stored in
+//! `bing_images` with `file://` URLs and the "local" market code, and are read
+//! in place instead of being copied into the image cache.
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::sources::{FetchRequest, ImageProvider, ProviderHealth, SourceSettings};
+use crate::db::{operations, ImageStatus};
+use crate::BingImage;
+
+/// Provider name of `LocalDirectorySource`
+pub const LOCAL_DIRECTORY_PROVIDER: &str = "local_directory";
+
+/// Market code marking rows that come from local directories
+pub const LOCAL_MARKET_CODE: &str = "local";
+
+const FILE_URL_PREFIX: &str = "file://";
+
+/// Build a `file://` URL for a local path (percent-encoded)
+pub fn file_url(path: &Path) -> String {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(url) = url::Url::from_file_path(path) {
+        return url.into();
+    }
+    format!("{}{}", FILE_URL_PREFIX, path.to_string_lossy())
+}
+
+/// Get the local path of a `file://` URL (None for network URLs)
+///
+/// Rows stored before URLs were percent-encoded hold the raw path; those are
+/// not in canonical form and are read verbatim.
+pub fn local_path_from_url(url: &str) -> Option<PathBuf> {
+    let raw = url.strip_prefix(FILE_URL_PREFIX)?;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(parsed) = url::Url::parse(url) {
+        if parsed.as_str() == url && parsed.query().is_none() && parsed.fragment().is_none() {
+            if let Ok(path) = parsed.to_file_path() {
+                return Some(path);
+            }
+        }
+    }
+    Some(PathBuf::from(raw))
+}
+
+/// Check if a URL points to a local file
+pub fn is_local_url(url: &str) -> bool {
+    url.starts_with(FILE_URL_PREFIX)
+}
+
+fn is_supported_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| matches!(ext.to_ascii_lowercase().as_str(), "jpg" | "jpeg" | "png"))
+        .unwrap_or(false)
+}
+
+/// Files found by a directory scan
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryScan {
+    pub files: Vec<PathBuf>,
+    /// Directories that could not be listed; their contents are unknown
+    pub unreadable: Vec<PathBuf>,
+}
+
+impl DirectoryScan {
+    /// Whether `path` lies in a directory that could not be listed
+    pub fn is_unknown(&self, path: &Path) -> bool {
+        self.unreadable.iter().any(|dir| path.starts_with(dir))
+    }
+}
+
+/// Recursively collect JPEG/PNG files under a directory (hidden entries skipped)
+pub fn scan_directory(dir: &Path) -> DirectoryScan {
+    let mut scan = DirectoryScan::default();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        let entries = match std::fs::read_dir(&current) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("Failed to read directory {:?}: {}", current, e);
+                scan.unreadable.push(current);
+                continue;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if hidden {
+                continue;
+            }
+
+            if path.is_dir() {
+                pending.push(path);
+            } else if is_supported_image(&path) {
+                scan.files.push(path);
+            }
+        }
+    }
+
+    scan.files.sort();
+    scan
+}
+
+fn image_for_path(path: &Path) -> BingImage {
+    let title = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().replace(['_', '-'], " "))
+        .unwrap_or_else(|| "Local image".to_string());
+
+    // The file's modification time stands in for the publication date
+    let modified = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(chrono::DateTime::<chrono::Utc>::from);
+
+    BingImage {
+        url: file_url(path),
+        title,
+        market_code: Some(LOCAL_MARKET_CODE.to_string()),
+        startdate: modified.map(|t| t.format("%Y%m%d").to_string()),
+        fullstartdate: modified.map(|t| t.format("%Y%m%d%H%M").to_string()),
+        ..Default::default()
+    }
+}
+
+/// Image provider reading JPEG/PNG files from local directories
+pub struct LocalDirectorySource {
+    dirs: Vec<PathBuf>,
+}
+
+impl LocalDirectorySource {
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        Self { dirs }
+    }
+
+    /// All image files in the configured directories
+    pub fn scan(&self) -> Vec<PathBuf> {
+        self.scan_dirs().files
+    }
+
+    /// Scan every configured directory, keeping track of unreadable ones
+    pub fn scan_dirs(&self) -> DirectoryScan {
+        let mut all = DirectoryScan::default();
+        for dir in &self.dirs {
+            let scan = scan_directory(dir);
+            all.files.extend(scan.files);
+            all.unreadable.extend(scan.unreadable);
+        }
+        all
+    }
+}
+
+impl ImageProvider for LocalDirectorySource {
+    fn name(&self) -> &str {
+        LOCAL_DIRECTORY_PROVIDER
+    }
+
+    fn priority(&self) -> i32 {
+        30
+    }
+
+    fn fetch(&self, _request: &FetchRequest) -> Result<Vec<BingImage>> {
+        Ok(self.scan().iter().map(|path| image_for_path(path)).collect())
+    }
+
+    fn health(&self) -> ProviderHealth {
+        let missing: Vec<String> = self.dirs
+            .iter()
+            .filter(|dir| !dir.is_dir())
+            .map(|dir| dir.display().to_string())
+            .collect();
+
+        if self.dirs.is_empty() {
+            ProviderHealth::Unavailable("No directories configured".to_string())
+        } else if missing.len() == self.dirs.len() {
+            ProviderHealth::Unavailable(format!("Directories not found: {}", missing.join(", ")))
+        } else if !missing.is_empty() {
+            ProviderHealth::Degraded(format!("Directories not found: {}", missing.join(", ")))
+        } else {
+            ProviderHealth::Healthy
+        }
+    }
+}
+
+/// Result of a local directory rescan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LocalScanResult {
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Sync `bing_images` with the configured local directories
+///
+/// New files are inserted as unprocessed (and already cached), rows whose file
+/// is gone or whose directory was removed from settings are deleted. Rows in a
+/// directory that could not be listed (e.g. an unmounted drive) are kept. Does
+/// nothing while the local provider is disabled.
+pub fn sync_local_images_sync(conn: &mut SqliteConnection, settings: &SourceSettings) -> Result<LocalScanResult> {
+    if !settings.is_enabled(LOCAL_DIRECTORY_PROVIDER) {
+        return Ok(LocalScanResult::default());
+    }
+
+    let dirs: Vec<PathBuf> = settings.local_dirs.iter().map(PathBuf::from).collect();
+    let scan = LocalDirectorySource::new(dirs).scan_dirs();
+    let found: HashSet<&Path> = scan.files.iter().map(PathBuf::as_path).collect();
+
+    // Compared by path, rows may hold URLs from before percent-encoding
+    let mut existing: HashSet<PathBuf> = HashSet::new();
+    let mut gone: Vec<String> = Vec::new();
+    for url in operations::get_urls_by_market_code(conn, LOCAL_MARKET_CODE)? {
+        match local_path_from_url(&url) {
+            Some(path) if found.contains(path.as_path()) || scan.is_unknown(&path) => {
+                existing.insert(path);
+            }
+            _ => gone.push(url),
+        }
+    }
+
+    let mut result = LocalScanResult::default();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i32;
+
+    for path in &scan.files {
+        if existing.contains(path) {
+            continue;
+        }
+        let image = image_for_path(path);
+
+        // Newest photos first, like fetched Bing images
+        let modified = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i32)
+            .unwrap_or(now);
+
+        let new_image = image.to_new_record(
+            LOCAL_MARKET_CODE,
+            ImageStatus::Unprocessed.as_str(),
+            modified,
+            now,
+        );
+        operations::upsert_image(conn, &new_image)?;
+        operations::mark_image_cached(conn, &image.url, now)?;
+        if let Ok(bytes) = std::fs::read(path) {
+            super::dedup::try_record_image_hash_sync(conn, &image.url, &bytes);
+        }
+        result.added += 1;
+    }
+
+    for url in &gone {
+        operations::delete_image(conn, url)?;
+        result.removed += 1;
+    }
+
+    if result != LocalScanResult::default() {
+        log::info!("Local directories rescanned: {} added, {} removed", result.added, result.removed);
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,161 @@
+//! Bing market rotation backed by the `market_codes` table
+//!
+//! Each market serves about 16 days of images through HPImageArchive, 8 per page.
+//! Markets are visited least recently used first and walked page by page until
+//! drained; a market is skipped until 7 days after its last visit.
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::db::{operations, MarketCode};
+
+/// Market used when no rotation state is available
+pub const DEFAULT_MARKET_CODE: &str = "en-US";
+
+/// Seconds before a visited market becomes eligible again (7 days)
+pub const MARKET_REVISIT_SECS: i32 = 7 * 24 * 60 * 60;
+
+/// Images per HPImageArchive page (Bing API max for `n`)
+pub const MARKET_PAGE_SIZE: u32 = 8;
+
+/// Highest `idx` HPImageArchive accepts (larger values are clamped by Bing)
+pub const MAX_ARCHIVE_IDX: u32 = 7;
+
+/// Offset at which a market's archive is fully walked (~16 days)
+pub const MARKET_DRAINED_OFFSET: u32 = 16;
+
+/// Bing markets seeded into an empty `market_codes` table
+pub const DEFAULT_MARKET_CODES: &[&str] = &[
+    "en-US", "en-GB", "en-CA", "en-AU", "en-NZ", "en-IN", "en-IE", "en-ZA", "en-SG", "en-MY",
+    "en-PH", "en-ID", "es-US", "es-ES", "es-MX", "es-AR", "es-CL", "pt-BR", "pt-PT", "fr-FR",
+    "fr-CA", "fr-BE", "fr-CH", "de-DE", "de-AT", "de-CH", "nl-NL", "nl-BE", "it-IT", "da-DK",
+    "fi-FI", "no-NO", "sv-SE", "pl-PL", "ru-RU", "tr-TR", "he-IL", "ja-JP", "ko-KR", "zh-CN",
+    "zh-HK", "zh-TW", "th-TH",
+];
+
+/// A page of a market's archive to fetch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketPage {
+    pub market_code: String,
+    pub offset: u32,
+}
+
+impl MarketPage {
+    /// `idx` query parameter for this page
+    pub fn idx(&self) -> u32 {
+        self.offset.min(MAX_ARCHIVE_IDX)
+    }
+}
+
+fn current_timestamp() -> i32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i32
+}
+
+/// Seed the market list with `DEFAULT_MARKET_CODES` if it is empty
+pub fn seed_market_codes_sync(conn: &mut SqliteConnection) -> Result<()> {
+    if operations::get_market_codes(conn)?.is_empty() {
+        let inserted = operations::ensure_market_codes(conn, DEFAULT_MARKET_CODES)?;
+        log::info!("Seeded {} default market codes", inserted);
+    }
+    Ok(())
+}
+
+/// Get the configured market list (least recently used first)
+pub fn get_market_list_sync(conn: &mut SqliteConnection) -> Result<Vec<MarketCode>> {
+    seed_market_codes_sync(conn)?;
+    operations::get_market_codes(conn)
+}
+
+/// Replace the configured market list
+pub fn set_market_list_sync(conn: &mut SqliteConnection, codes: &[&str]) -> Result<()> {
+    if codes.is_empty() {
+        anyhow::bail!("Market list cannot be empty");
+    }
+    operations::set_market_codes(conn, codes)
+}
+
+/// Pick the next market to visit
+/// Returns None when every market was visited within the last 7 days
+pub fn next_market_sync(conn: &mut SqliteConnection) -> Result<Option<String>> {
+    seed_market_codes_sync(conn)?;
+
+    let used_before = current_timestamp() - MARKET_REVISIT_SECS;
+    let next = operations::get_next_market_code(conn, used_before)?;
+
+    match &next {
+        Some(market) => log::info!("Next market in rotation: {}", market.code),
+        None => log::info!("All markets visited within the last 7 days"),
+    }
+
+    Ok(next.map(|market| market.code))
+}
+
+/// Record a market visit and remember it as the current market
+pub fn mark_market_used_sync(conn: &mut SqliteConnection, market_code: &str) -> Result<()> {
+    operations::touch_market_code(conn, market_code)?;
+    operations::set_config(conn, "market_code", market_code)?;
+    Ok(())
+}
+
+/// Pick the next archive page to fetch
+/// Continues a partially walked market first, so each market is drained before moving on.
+/// Returns None when every market is drained and was visited within the last 7 days.
+pub fn next_market_page_sync(conn: &mut SqliteConnection) -> Result<Option<MarketPage>> {
+    seed_market_codes_sync(conn)?;
+
+    if let Some(market) = operations::get_market_in_progress(conn, MARKET_DRAINED_OFFSET as i32)? {
+        log::info!("Continuing market {} at offset {}", market.code, market.page_offset);
+        return Ok(Some(MarketPage {
+            market_code: market.code,
+            offset: market.page_offset as u32,
+        }));
+    }
+
+    Ok(next_market_sync(conn)?.map(|market_code| MarketPage { market_code, offset: 0 }))
+}
+
+/// Page to fetch for an explicitly requested market
+/// Continues the market's walk, restarting from the newest page once it is drained.
+/// An empty market code picks the next page in rotation (or the default market).
+pub fn market_page_for_sync(conn: &mut SqliteConnection, market_code: &str) -> Result<MarketPage> {
+    if market_code.is_empty() {
+        return Ok(next_market_page_sync(conn)?.unwrap_or_else(|| MarketPage {
+            market_code: DEFAULT_MARKET_CODE.to_string(),
+            offset: 0,
+        }));
+    }
+
+    let offset = operations::get_market_codes(conn)?
+        .into_iter()
+        .find(|market| market.code == market_code)
+        .map(|market| market.page_offset as u32)
+        .filter(|&offset| offset < MARKET_DRAINED_OFFSET)
+        .unwrap_or(0);
+
+    Ok(MarketPage {
+        market_code: market_code.to_string(),
+        offset,
+    })
+}
+
+/// Record that a page was fetched and move the market to its next page
+pub fn advance_market_page_sync(conn: &mut SqliteConnection, page: &MarketPage) -> Result<()> {
+    if page.offset == 0 {
+        // The 7-day revisit window starts when a market walk begins
+        operations::touch_market_code(conn, &page.market_code)?;
+    }
+
+    let next_offset = (page.offset + MARKET_PAGE_SIZE).min(MARKET_DRAINED_OFFSET);
+    operations::set_market_page_offset(conn, &page.market_code, next_offset as i32)?;
+    super::commands::save_market_state_sync(conn, &page.market_code, next_offset)?;
+
+    if next_offset >= MARKET_DRAINED_OFFSET {
+        log::info!("Market {} drained", page.market_code);
+    }
+
+    Ok(())
+}
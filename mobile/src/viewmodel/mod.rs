@@ -1,14 +1,26 @@
-use crate::db::{BingImage, ImageStatus};
-use diesel::prelude::*;
+use crate::db::{operations, BingImage, Collection, ImageStatus, Tag, WallpaperSource};
 use std::sync::mpsc::{Sender, Receiver};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use serde::{Serialize, Deserialize};
 
 pub mod background;
+pub mod collections;
 pub mod commands;
+pub mod countries;
+pub mod dedup;
+pub mod history;
+pub mod legacy_import;
+pub mod local_source;
+pub mod markets;
+pub mod resolution;
+pub mod rotation;
+pub mod scheduler;
+pub mod search;
 pub mod sources;
 pub mod cache_manager;
+pub mod undo;
 
 /// Crop coordinates (normalized 0.0-1.0 relative to image dimensions)
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -68,6 +80,46 @@ pub enum ViewModelCommand {
         url: String,
         coords: CropCoords,
     },
+
+    // Wallpaper history
+    SetPreviousWallpaper,
+    LoadHistoryPage { page: usize },
+
+    // Status changes (restore the wallpaper they replaced too)
+    UndoLastAction,
+    Redo,
+
+    // Full-text search (titles, copyrights, locations), best match first
+    Search {
+        query: String,
+        filter: Option<ImageStatus>,  // None = All
+        page: usize,  // 0-indexed, 20 items per page
+    },
+
+    // Tags and collections
+    TagImage { url: String, tag: String },
+    UntagImage { url: String, tag_id: i32 },
+    LoadImageGroups { url: String },
+    LoadCollections,
+    CreateCollection { name: String },
+    AddToCollection { url: String, collection_id: i32 },
+    RemoveFromCollection { url: String, collection_id: i32 },
+    SetRotationCollection { collection_id: Option<i32> },  // None = all favorites
+    LoadCollectionPage {
+        collection_id: i32,
+        page: usize,  // 0-indexed, 20 items per page (answered with CarouselPageLoaded)
+    },
+    // Countries parsed from copyrights
+    LoadCountries,
+    SetRotationCountries { countries: Vec<String> },  // empty = all countries
+    LoadCountryPage {
+        country: String,
+        page: usize,  // 0-indexed, 20 items per page (answered with CarouselPageLoaded)
+    },
+
+    // Star ratings (0 clears)
+    LoadRating { url: String },
+    SetRating { url: String, rating: i32 },
 }
 
 /// Events sent from ViewModel background thread to UI
@@ -100,21 +152,66 @@ pub enum ViewModelEvent {
     CropCoordsSaved {
         url: String,
     },
+
+    // Historical import (runs in background after startup)
+    ImportProgress { current: usize, total: usize },
+    ImportComplete { added: usize, cancelled: bool },
+
+    // Wallpaper history
+    HistoryPageLoaded { history: history::HistoryPage },
+
+    // Undo or redo of a status change (None = nothing to undo/redo)
+    UndoApplied { outcome: Option<undo::UndoOutcome> },
+
+    // Search responses (like CarouselPageLoaded, for `query`)
+    SearchResultsLoaded {
+        query: String,
+        page: usize,
+        images: Vec<BingImage>,
+        total_count: usize,
+    },
+
+    // Tags and collections of `url`
+    ImageGroupsLoaded { url: String, groups: collections::ImageGroups },
+    // All collections and the one rotation is restricted to
+    CollectionsLoaded {
+        collections: Vec<collections::CollectionSummary>,
+        rotation_collection_id: Option<i32>,
+    },
+
+    // Countries with images and the ones rotation is limited to
+    CountriesLoaded {
+        countries: Vec<countries::CountrySummary>,
+        rotation_countries: Vec<String>,
+    },
+
+    // Star rating of `url`
+    RatingLoaded { url: String, rating: i32 },
+
+    // Files of older releases imported into image statuses and markets
+    LegacyImportComplete { report: legacy_import::LegacyImportReport },
 }
 
 /// Result returned when setting wallpaper (CLI)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct WallpaperSetResult {
     pub title: String,
     pub url: String,
 }
 
+fn import_legacy_layout_once(pool: &crate::db::DbPool) -> Result<Option<legacy_import::LegacyImportReport>> {
+    let roots = crate::paths::legacy_layout_roots_for(pool.db_path())?;
+    legacy_import::import_legacy_layout_once_sync(&mut *pool.get()?, &roots)
+}
+
 /// ViewModel struct
 pub struct ViewModel {
-    db_path: PathBuf,
+    pool: crate::db::DbPool,
     command_tx: Option<Sender<ViewModelCommand>>,
     event_rx: Option<Receiver<ViewModelEvent>>,
     cache_manager: Option<Arc<cache_manager::CacheManager>>,
+    /// Stops the background historical import (GUI only)
+    import_cancel: Arc<AtomicBool>,
 }
 
 use anyhow::Result;
@@ -129,50 +226,88 @@ impl ViewModel {
         // Initialize database FIRST (synchronously) to avoid race conditions
         // This ensures WAL mode is set before any background threads start
         log::info!("Initializing database before background threads...");
-        let _init_conn = crate::db::establish_connection(&db_path);
-        drop(_init_conn); // Close the connection
+        let pool = crate::db::DbPool::shared(&db_path)?;
+        let mut init_conn = pool.get()?;
+        let sources = Arc::new(commands::image_source_from_settings_sync(&mut init_conn)?);
+        drop(init_conn); // Return the connection to the pool
 
         // Initialize cache manager
-        let cache_dir = db_path.parent()
-            .ok_or_else(|| anyhow::anyhow!("Invalid db_path"))?
-            .join("cache")
-            .join("images");
+        let cache_dir = crate::paths::image_cache_dir_for(&db_path)?;
 
-        let sources = Arc::new(sources::ImageSource::new(None));
         let cache_manager = Arc::new(cache_manager::CacheManager::new(
             cache_dir,
-            db_path.clone(),
+            pool.clone(),
             Some(sources),
         ));
 
-        // Populate database with historical data SYNCHRONOUSLY
-        // This must complete BEFORE UI loads to ensure carousel has data
-        log::info!("Populating database with historical data (synchronous)...");
-        if let Err(e) = cache_manager.populate_historical_images_sync() {
-            log::error!("Failed to populate historical images: {}", e);
-        }
-
-        // Cache 3 images in background (can be async since it's just for wallpaper)
+        // Import historical data in background, then cache 3 images for the wallpaper
+        // (in that order, so the cache step does not start a second archive sync)
+        let import_cancel = Arc::new(AtomicBool::new(false));
         let cache_clone = cache_manager.clone();
+        let cancel_clone = import_cancel.clone();
+        let import_tx = evt_tx.clone();
+        let legacy_pool = pool.clone();
         std::thread::spawn(move || {
+            let result = cache_clone.import_historical_images(&cancel_clone, |current, total| {
+                import_tx.send(ViewModelEvent::ImportProgress { current, total }).ok();
+            });
+            match result {
+                Ok(import) => {
+                    import_tx.send(ViewModelEvent::ImportComplete {
+                        added: import.added,
+                        cancelled: import.cancelled,
+                    }).ok();
+
+                    // Legacy files are matched against the imported rows
+                    if !import.cancelled {
+                        match import_legacy_layout_once(&legacy_pool) {
+                            Ok(Some(report)) => {
+                                import_tx.send(ViewModelEvent::LegacyImportComplete { report }).ok();
+                            }
+                            Ok(None) => {}
+                            Err(e) => log::error!("Failed to import legacy files: {}", e),
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::error!("Failed to import historical images: {}", e);
+                    import_tx.send(ViewModelEvent::Error {
+                        message: format!("Historical import failed: {}", e)
+                    }).ok();
+                }
+            }
+
             if let Err(e) = cache_clone.cache_initial_images() {
                 log::error!("Cache initialization failed: {}", e);
             }
         });
 
-        let db_path_clone = db_path.clone();
+        let pool_clone = pool.clone();
         std::thread::spawn(move || {
-            background::run_background_loop(db_path_clone, cmd_rx, evt_tx);
+            background::run_background_loop(pool_clone, cmd_rx, evt_tx);
         });
 
         Ok(Self {
-            db_path,
+            pool,
             command_tx: Some(cmd_tx),
             event_rx: Some(evt_rx),
             cache_manager: Some(cache_manager),
+            import_cancel,
         })
     }
 
+    /// Stop the background historical import after the current chunk
+    /// The import resumes from its last checkpoint on the next launch.
+    pub fn cancel_import(&self) {
+        self.import_cancel.store(true, Ordering::SeqCst);
+    }
+
+    /// Import the file-based layout of older releases, once per database
+    /// Returns None when it already ran, there is nothing to import, or no images are known yet.
+    pub fn import_legacy_layout_once(&self) -> Result<Option<legacy_import::LegacyImportReport>> {
+        import_legacy_layout_once(&self.pool)
+    }
+
     /// Send command to background thread
     pub fn send_command(&self, cmd: ViewModelCommand) -> Result<()> {
         self.command_tx.as_ref()
@@ -192,23 +327,23 @@ impl ViewModel {
     /// Create sync ViewModel (CLI only)
     pub fn new_sync(db_path: PathBuf) -> Result<Self> {
         // Initialize cache manager
-        let cache_dir = db_path.parent()
-            .ok_or_else(|| anyhow::anyhow!("Invalid db_path"))?
-            .join("cache")
-            .join("images");
+        let cache_dir = crate::paths::image_cache_dir_for(&db_path)?;
 
-        let sources = Arc::new(sources::ImageSource::new(None));
+        let pool = crate::db::DbPool::shared(&db_path)?;
+        let mut conn = pool.get()?;
+        let sources = Arc::new(commands::image_source_from_settings_sync(&mut conn)?);
         let cache_manager = Arc::new(cache_manager::CacheManager::new(
             cache_dir,
-            db_path.clone(),
+            pool.clone(),
             Some(sources),
         ));
 
         Ok(Self {
-            db_path,
+            pool,
             command_tx: None,
             event_rx: None,
             cache_manager: Some(cache_manager),
+            import_cancel: Arc::new(AtomicBool::new(false)),
         })
     }
 
@@ -228,109 +363,322 @@ impl ViewModel {
 
     /// Download images synchronously (CLI only)
     pub fn download_images_sync(&self, market_code: &str) -> Result<usize> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
+        let mut conn = self.pool.get()?;
         commands::download_images_sync(&mut conn, market_code)
     }
 
     /// Get images by status synchronously (CLI only)
     pub fn get_images_by_status_sync(&self, status: ImageStatus) -> Result<Vec<BingImage>> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
+        let mut conn = self.pool.get()?;
         crate::db::operations::get_images_by_status(&mut conn, status)
     }
 
     /// Set wallpaper synchronously (CLI only)
     pub fn set_wallpaper_sync(&self, url: &str) -> Result<bool> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
-        commands::set_wallpaper_sync(&mut conn, url)
+        let mut conn = self.pool.get()?;
+        commands::set_wallpaper_sync(&mut conn, url, WallpaperSource::Cli)
     }
 
     /// Toggle favorite synchronously (CLI only)
     pub fn toggle_favorite_sync(&self, url: &str) -> Result<()> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
-        commands::toggle_favorite_sync(&mut conn, url)
+        let mut conn = self.pool.get()?;
+        commands::toggle_favorite_sync(&mut conn, url, WallpaperSource::Cli)
     }
 
     /// Blacklist image synchronously (CLI only)
     pub fn blacklist_image_sync(&self, url: &str) -> Result<()> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
-        commands::blacklist_image_sync(&mut conn, url)
+        let mut conn = self.pool.get()?;
+        commands::blacklist_image_sync(&mut conn, url, WallpaperSource::Cli)
     }
 
     /// Get current desktop wallpaper URL by matching to database (CLI only)
     #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
     pub fn get_current_desktop_wallpaper_url_sync(&self) -> Result<Option<String>> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
+        let mut conn = self.pool.get()?;
         commands::get_current_desktop_wallpaper_url_sync(&mut conn)
     }
 
     /// Download and set next wallpaper (CLI only)
     #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
     pub fn download_and_set_next_wallpaper_sync(&self) -> Result<WallpaperSetResult> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
-        commands::download_and_set_next_wallpaper_sync(&mut conn)
+        let mut conn = self.pool.get()?;
+        match self.cache_manager.as_ref().and_then(|c| c.sources()) {
+            Some(sources) => {
+                commands::download_and_set_next_wallpaper_with_sources_sync(&mut conn, &sources, WallpaperSource::Cli)
+            }
+            None => commands::download_and_set_next_wallpaper_sync(&mut conn, WallpaperSource::Cli),
+        }
     }
 
     /// Mark current desktop wallpaper as favorite (CLI only)
     #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
     pub fn keep_current_wallpaper_sync(&self) -> Result<Option<String>> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
-        commands::keep_current_wallpaper_sync(&mut conn)
+        let mut conn = self.pool.get()?;
+        commands::keep_current_wallpaper_sync(&mut conn, WallpaperSource::Cli)
     }
 
     /// Mark current desktop wallpaper as blacklisted (CLI only)
     #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
     pub fn blacklist_current_wallpaper_sync(&self) -> Result<Option<String>> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
-        commands::blacklist_current_wallpaper_sync(&mut conn)
+        let mut conn = self.pool.get()?;
+        commands::blacklist_current_wallpaper_sync(&mut conn, WallpaperSource::Cli)
     }
 
     /// Set a random favorite as desktop wallpaper (CLI only)
     #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
     pub fn set_random_favorite_wallpaper_sync(&self) -> Result<Option<String>> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
-        commands::set_random_favorite_wallpaper_sync(&mut conn)
+        let mut conn = self.pool.get()?;
+        commands::set_random_favorite_wallpaper_sync(&mut conn, WallpaperSource::Cli)
+    }
+
+    /// Go back to the wallpaper shown before the current one (CLI only)
+    /// Returns None at the start of the history.
+    pub fn set_previous_wallpaper_sync(&self) -> Result<Option<WallpaperSetResult>> {
+        let mut conn = self.pool.get()?;
+        commands::set_previous_wallpaper_sync(&mut conn, WallpaperSource::Cli)
+    }
+
+    /// Get a page of the wallpaper history, newest first
+    pub fn get_history_page_sync(&self, page: usize) -> Result<history::HistoryPage> {
+        let mut conn = self.pool.get()?;
+        history::get_history_page_sync(&mut conn, page)
+    }
+
+    /// Total seconds an image was shown as wallpaper
+    pub fn get_time_shown_sync(&self, url: &str) -> Result<i64> {
+        let mut conn = self.pool.get()?;
+        history::time_shown_sync(&mut conn, url)
+    }
+
+    /// Total seconds each image was shown as wallpaper, longest first
+    pub fn get_time_shown_per_image_sync(&self) -> Result<Vec<(String, i64)>> {
+        let mut conn = self.pool.get()?;
+        history::time_shown_per_image_sync(&mut conn)
+    }
+
+    /// Undo the latest status change and put back the wallpaper it replaced (CLI only)
+    /// Returns None when there is nothing to undo.
+    pub fn undo_last_action_sync(&self) -> Result<Option<undo::UndoOutcome>> {
+        let mut conn = self.pool.get()?;
+        undo::undo_last_action_sync(&mut conn, WallpaperSource::Cli)
+    }
+
+    /// Redo the latest undone status change (CLI only)
+    /// Returns None when there is nothing to redo.
+    pub fn redo_sync(&self) -> Result<Option<undo::UndoOutcome>> {
+        let mut conn = self.pool.get()?;
+        undo::redo_sync(&mut conn, WallpaperSource::Cli)
+    }
+
+    /// Search titles, copyrights and locations, best match first
+    pub fn search_sync(&self, query: &str, filter: Option<ImageStatus>, page: usize) -> Result<search::SearchPage> {
+        let mut conn = self.pool.get()?;
+        search::search_page_sync(&mut conn, query, filter, page)
+    }
+
+    /// Set the star rating of the current wallpaper, 0 clears it (CLI only)
+    /// Returns the image title, None if the wallpaper is not from BingTray.
+    #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+    pub fn rate_current_wallpaper_sync(&self, rating: i32) -> Result<Option<String>> {
+        let mut conn = self.pool.get()?;
+        commands::rate_current_wallpaper_sync(&mut conn, rating)
+    }
+
+    /// Star rating of the current wallpaper (CLI only)
+    #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+    pub fn get_current_wallpaper_rating_sync(&self) -> Result<Option<i32>> {
+        let mut conn = self.pool.get()?;
+        commands::get_current_wallpaper_rating_sync(&mut conn)
+    }
+
+    /// Countries with images, most images first
+    pub fn get_countries_sync(&self) -> Result<Vec<countries::CountrySummary>> {
+        let mut conn = self.pool.get()?;
+        countries::get_country_summaries_sync(&mut conn)
+    }
+
+    /// Country codes random favorite rotation is limited to (empty = all)
+    pub fn get_rotation_countries_sync(&self) -> Result<Vec<String>> {
+        let mut conn = self.pool.get()?;
+        countries::get_rotation_countries_sync(&mut conn)
+    }
+
+    /// Limit random favorite rotation to countries (empty = all)
+    pub fn set_rotation_countries_sync(&self, codes: &[String]) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        countries::set_rotation_countries_sync(&mut conn, codes)
+    }
+
+    /// Tag an image, creating the tag if needed
+    pub fn tag_image_sync(&self, url: &str, tag: &str) -> Result<Option<Tag>> {
+        let mut conn = self.pool.get()?;
+        operations::tag_image(&mut conn, url, tag)
+    }
+
+    /// Remove a tag from an image
+    pub fn untag_image_sync(&self, url: &str, tag_id: i32) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        operations::untag_image(&mut conn, url, tag_id)
+    }
+
+    /// Tags and collections of an image
+    pub fn get_image_groups_sync(&self, url: &str) -> Result<collections::ImageGroups> {
+        let mut conn = self.pool.get()?;
+        collections::get_image_groups_sync(&mut conn, url)
+    }
+
+    /// All collections by name, with their image counts
+    pub fn get_collections_sync(&self) -> Result<Vec<collections::CollectionSummary>> {
+        let mut conn = self.pool.get()?;
+        collections::get_collection_summaries_sync(&mut conn)
+    }
+
+    /// Get the collection called `name`, creating it if needed
+    pub fn create_collection_sync(&self, name: &str) -> Result<Collection> {
+        let mut conn = self.pool.get()?;
+        operations::create_collection(&mut conn, name)
+    }
+
+    /// Add an image to a collection (false if the image is unknown)
+    pub fn add_to_collection_sync(&self, url: &str, collection_id: i32) -> Result<bool> {
+        let mut conn = self.pool.get()?;
+        operations::add_image_to_collection(&mut conn, collection_id, url)
+    }
+
+    /// Collection random favorite rotation is restricted to (None = all favorites)
+    pub fn get_rotation_collection_sync(&self) -> Result<Option<Collection>> {
+        let mut conn = self.pool.get()?;
+        collections::get_rotation_collection_sync(&mut conn)
+    }
+
+    /// Restrict random favorite rotation to a collection (None = all favorites)
+    pub fn set_rotation_collection_sync(&self, collection_id: Option<i32>) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        collections::set_rotation_collection_sync(&mut conn, collection_id)
     }
 
     /// Get market state (market_code, offset) from config (CLI only)
     pub fn get_market_state_sync(&self) -> Result<(String, u32)> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
+        let mut conn = self.pool.get()?;
         commands::get_market_state_sync(&mut conn)
     }
 
     /// Save market state (market_code, offset) to config (CLI only)
     pub fn save_market_state_sync(&self, market_code: &str, offset: u32) -> Result<()> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
+        let mut conn = self.pool.get()?;
         commands::save_market_state_sync(&mut conn, market_code, offset)
     }
 
     /// Increment market offset by 8 (CLI only)
     pub fn increment_market_offset_sync(&self) -> Result<()> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
+        let mut conn = self.pool.get()?;
         commands::increment_market_offset_sync(&mut conn)
     }
 
+    /// Get the configured market rotation list, least recently used first (CLI only)
+    pub fn get_market_list_sync(&self) -> Result<Vec<crate::db::MarketCode>> {
+        let mut conn = self.pool.get()?;
+        markets::get_market_list_sync(&mut conn)
+    }
+
+    /// Replace the market rotation list (CLI only)
+    pub fn set_market_list_sync(&self, codes: &[&str]) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        markets::set_market_list_sync(&mut conn, codes)
+    }
+
+    /// Get image source settings (enabled providers and order)
+    pub fn get_source_settings_sync(&self) -> Result<sources::SourceSettings> {
+        let mut conn = self.pool.get()?;
+        commands::get_source_settings_sync(&mut conn)
+    }
+
+    /// Save image source settings and apply them to the cache manager
+    pub fn set_source_settings_sync(&self, settings: sources::SourceSettings) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        commands::save_source_settings_sync(&mut conn, &settings)?;
+        local_source::sync_local_images_sync(&mut conn, &settings)?;
+
+        if let Some(cache_manager) = &self.cache_manager {
+            cache_manager.set_sources(Arc::new(commands::image_source_from_settings_sync(&mut conn)?));
+        }
+        Ok(())
+    }
+
+    /// Get the automatic rotation schedule
+    pub fn get_rotation_schedule_sync(&self) -> Result<scheduler::RotationSchedule> {
+        let mut conn = self.pool.get()?;
+        scheduler::get_schedule_sync(&mut conn)
+    }
+
+    /// Save the automatic rotation schedule (run by the tray and background modes)
+    pub fn set_rotation_schedule_sync(&self, schedule: &scheduler::RotationSchedule) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        scheduler::set_schedule_sync(&mut conn, schedule)
+    }
+
+    /// Get the wallpaper download size setting
+    pub fn get_resolution_setting_sync(&self) -> Result<resolution::ResolutionSetting> {
+        let mut conn = self.pool.get()?;
+        resolution::get_resolution_setting_sync(&mut conn)
+    }
+
+    /// Force a wallpaper download size (or follow the screen with `Auto`)
+    pub fn set_resolution_setting_sync(&self, setting: resolution::ResolutionSetting) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        resolution::set_resolution_setting_sync(&mut conn, setting)
+    }
+
+    /// Hash cached images that predate deduplication and link near-duplicates
+    pub fn dedup_cached_images_sync(&self) -> Result<usize> {
+        let cache_manager = self.cache_manager.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Cache manager not available"))?;
+        cache_manager.hash_cached_images()
+    }
+
+    /// Rescan the configured local image directories
+    pub fn rescan_local_sources_sync(&self) -> Result<local_source::LocalScanResult> {
+        let mut conn = self.pool.get()?;
+        let settings = commands::get_source_settings_sync(&mut conn)?;
+        local_source::sync_local_images_sync(&mut conn, &settings)
+    }
+
+    /// Health of every registered image provider, in query order
+    pub fn source_health(&self) -> Vec<(String, sources::ProviderHealth)> {
+        self.cache_manager
+            .as_ref()
+            .and_then(|c| c.sources())
+            .map(|s| s.health_report())
+            .unwrap_or_default()
+    }
+
     /// Get database connection (for testing purposes)
-    pub fn db_connection(&self) -> Result<SqliteConnection> {
-        Ok(crate::db::establish_connection(&self.db_path))
+    pub fn db_connection(&self) -> Result<crate::db::PooledConnection> {
+        self.pool.get()
+    }
+
+    /// Shared connection pool of this ViewModel's database
+    pub fn pool(&self) -> &crate::db::DbPool {
+        &self.pool
     }
 
     /// Keep current wallpaper as favorite, set next instantly (CLI only)
     #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
     pub fn keep_current_wallpaper_instant_sync(&self) -> Result<String> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
+        let mut conn = self.pool.get()?;
         let cache_mgr = self.cache_manager.as_ref()
             .ok_or_else(|| anyhow::anyhow!("Cache manager not available"))?;
 
-        commands::keep_current_wallpaper_instant_sync(&mut conn, cache_mgr)
+        commands::keep_current_wallpaper_instant_sync(&mut conn, cache_mgr, WallpaperSource::Cli)
     }
 
     /// Blacklist current wallpaper, set next instantly (CLI only)
     #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
     pub fn blacklist_current_wallpaper_instant_sync(&self) -> Result<String> {
-        let mut conn = crate::db::establish_connection(&self.db_path);
+        let mut conn = self.pool.get()?;
         let cache_mgr = self.cache_manager.as_ref()
             .ok_or_else(|| anyhow::anyhow!("Cache manager not available"))?;
 
-        commands::blacklist_current_wallpaper_instant_sync(&mut conn, cache_mgr)
+        commands::blacklist_current_wallpaper_instant_sync(&mut conn, cache_mgr, WallpaperSource::Cli)
     }
 }
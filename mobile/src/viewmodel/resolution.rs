@@ -0,0 +1,177 @@
+//! Resolution-aware image variants
+//!
+//! Bing serves every image from its `urlbase` in several sizes
+//! (`{urlbase}_UHD.jpg`, `{urlbase}_1920x1080.jpg`, `{urlbase}_1080x1920.jpg`).
+//! The variant is picked from the screen size reported by the UI, unless the
+//! user forces one in settings.
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+use std::sync::Mutex;
+
+use crate::db::operations;
+
+/// Config key of the resolution setting
+pub const RESOLUTION_CONFIG_KEY: &str = "image_resolution";
+
+/// Image size variant served by Bing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageResolution {
+    /// Ultra HD (3840x2160 or larger)
+    Uhd,
+    /// Full HD landscape
+    Landscape1080,
+    /// Full HD portrait, for phones
+    Portrait1080,
+}
+
+impl ImageResolution {
+    pub const ALL: [ImageResolution; 3] = [
+        ImageResolution::Uhd,
+        ImageResolution::Landscape1080,
+        ImageResolution::Portrait1080,
+    ];
+
+    /// Suffix Bing appends to `urlbase` (also used in cache filenames)
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            ImageResolution::Uhd => "UHD",
+            ImageResolution::Landscape1080 => "1920x1080",
+            ImageResolution::Portrait1080 => "1080x1920",
+        }
+    }
+
+    pub fn from_suffix(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.suffix() == s)
+    }
+
+    /// Best variant for a screen of the given physical size
+    pub fn for_screen(width: i32, height: i32) -> Self {
+        if height > width {
+            ImageResolution::Portrait1080
+        } else if width > 1920 || height > 1080 {
+            ImageResolution::Uhd
+        } else {
+            ImageResolution::Landscape1080
+        }
+    }
+}
+
+/// User choice of image size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResolutionSetting {
+    /// Follow the screen size
+    #[default]
+    Auto,
+    /// Always download this variant
+    Fixed(ImageResolution),
+}
+
+impl ResolutionSetting {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResolutionSetting::Auto => "auto",
+            ResolutionSetting::Fixed(resolution) => resolution.suffix(),
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "auto" => Some(ResolutionSetting::Auto),
+            other => ImageResolution::from_suffix(other).map(ResolutionSetting::Fixed),
+        }
+    }
+}
+
+static SCREEN_SIZE: Mutex<Option<(i32, i32)>> = Mutex::new(None);
+
+/// Record the physical screen size (reported by the UI's ScreenSizeProvider)
+pub fn set_screen_size(width: i32, height: i32) {
+    if width > 0 && height > 0 {
+        *SCREEN_SIZE.lock().unwrap() = Some((width, height));
+    }
+}
+
+/// Last recorded physical screen size
+pub fn screen_size() -> Option<(i32, i32)> {
+    *SCREEN_SIZE.lock().unwrap()
+}
+
+fn default_screen_size() -> (i32, i32) {
+    #[cfg(target_os = "android")]
+    {
+        (1080, 1920)
+    }
+    #[cfg(not(target_os = "android"))]
+    {
+        (1920, 1080)
+    }
+}
+
+/// Get the resolution setting from config (defaults to Auto)
+pub fn get_resolution_setting_sync(conn: &mut SqliteConnection) -> Result<ResolutionSetting> {
+    Ok(operations::get_config(conn, RESOLUTION_CONFIG_KEY)?
+        .and_then(|s| ResolutionSetting::from_str(&s))
+        .unwrap_or_default())
+}
+
+/// Save the resolution setting to config
+pub fn set_resolution_setting_sync(conn: &mut SqliteConnection, setting: ResolutionSetting) -> Result<()> {
+    operations::set_config(conn, RESOLUTION_CONFIG_KEY, setting.as_str())
+}
+
+/// Resolution to download, from the setting or the current screen
+pub fn preferred_resolution_sync(conn: &mut SqliteConnection) -> Result<ImageResolution> {
+    Ok(match get_resolution_setting_sync(conn)? {
+        ResolutionSetting::Fixed(resolution) => resolution,
+        ResolutionSetting::Auto => {
+            let (width, height) = screen_size().unwrap_or_else(default_screen_size);
+            ImageResolution::for_screen(width, height)
+        }
+    })
+}
+
+/// Check if a `_`-separated URL part is a Bing size suffix ("UHD", "1920x1080", ...)
+pub fn is_resolution_suffix(s: &str) -> bool {
+    s == "UHD"
+        || s.split_once('x').is_some_and(|(w, h)| {
+            !w.is_empty()
+                && !h.is_empty()
+                && w.chars().all(|c| c.is_ascii_digit())
+                && h.chars().all(|c| c.is_ascii_digit())
+        })
+}
+
+/// Get the size suffix of a Bing image URL ("..._UHD.jpg" -> "UHD")
+pub fn resolution_suffix(url: &str) -> Option<&str> {
+    let id = url.split("th?id=").nth(1)?;
+    let id = id.split('&').next().unwrap_or(id);
+    let stem = [".jpg", ".jpeg", ".png"]
+        .iter()
+        .find_map(|ext| id.strip_suffix(ext))
+        .unwrap_or(id);
+
+    stem.rsplit_once('_')
+        .map(|(_, suffix)| suffix)
+        .filter(|suffix| is_resolution_suffix(suffix))
+}
+
+/// URL of an image in the given resolution
+///
+/// Uses the stored `urlbase`, or derives it from the URL for older rows.
+/// Non-Bing URLs (local files, other providers) are returned unchanged.
+pub fn variant_url(url: &str, urlbase: Option<&str>, resolution: ImageResolution) -> String {
+    if super::local_source::is_local_url(url) {
+        return url.to_string();
+    }
+
+    let base = urlbase
+        .map(str::to_string)
+        .or_else(|| super::sources::urlbase_from_url(url));
+
+    match base {
+        Some(base) if base.starts_with("http") => format!("{}_{}.jpg", base, resolution.suffix()),
+        Some(base) => format!("https://www.bing.com{}_{}.jpg", base, resolution.suffix()),
+        None => url.to_string(),
+    }
+}
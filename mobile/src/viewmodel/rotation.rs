@@ -0,0 +1,91 @@
+//! Weighted random favorite rotation
+//!
+//! Candidates are weighted by star rating (unrated counts once, five stars six
+//! times) and by time since they were last shown: an image shown within the last
+//! week is weighted down in proportion, one never shown counts as a week ago. The
+//! current wallpaper is only picked again when it is the only candidate.
+//! Candidates are the favorites (or the rotation collection) of the rotation
+//! countries.
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::db::models::MAX_RATING;
+use crate::db::{operations, BingImage};
+
+/// Seconds after which a shown image counts as fully rested
+pub const RECENCY_WINDOW_SECS: i64 = 7 * 86_400;
+
+/// Recency factor of an image that was just shown
+const MIN_RECENCY: f64 = 0.05;
+
+/// Rotation weight of an image with `rating` stars, last shown `secs_since_shown` ago
+pub fn rotation_weight(rating: i32, secs_since_shown: Option<i64>) -> f64 {
+    let rating = f64::from(rating.clamp(0, MAX_RATING) + 1);
+    let recency = secs_since_shown.map_or(1.0, |secs| {
+        (secs.max(0) as f64 / RECENCY_WINDOW_SECS as f64).min(1.0)
+    });
+    rating * recency.max(MIN_RECENCY)
+}
+
+/// Pick a candidate by rotation weight, skipping `current_url` when there is another
+///
+/// `last_shown` maps urls to when they were last set (seconds since the epoch).
+pub fn pick_weighted<'a, R: Rng + ?Sized>(
+    candidates: &'a [BingImage],
+    last_shown: &HashMap<String, i32>,
+    current_url: Option<&str>,
+    now: i32,
+    rng: &mut R,
+) -> Option<&'a BingImage> {
+    let others: Vec<&BingImage> = candidates
+        .iter()
+        .filter(|image| Some(image.url.as_str()) != current_url)
+        .collect();
+    if others.is_empty() {
+        return candidates.first();
+    }
+
+    let weights = others.iter().map(|image| {
+        let since = last_shown.get(&image.url).map(|set_at| i64::from(now - set_at));
+        rotation_weight(image.rating, since)
+    });
+    let index = WeightedIndex::new(weights).ok()?;
+    Some(others[index.sample(rng)])
+}
+
+/// Pick the next image of favorite rotation (favorites or the rotation collection,
+/// limited to the rotation countries)
+/// Returns None when there are no candidates.
+pub fn pick_rotation_image_sync(conn: &mut SqliteConnection) -> Result<Option<BingImage>> {
+    let candidates = super::collections::get_rotation_candidates_sync(conn)?;
+    pick_candidate_sync(conn, candidates)
+}
+
+/// Pick the next image of a collection (limited to the rotation countries)
+/// Returns None when the collection has no images to show.
+pub fn pick_collection_image_sync(conn: &mut SqliteConnection, collection_id: i32) -> Result<Option<BingImage>> {
+    let candidates = operations::get_collection_rotation_images(conn, collection_id)?;
+    pick_candidate_sync(conn, candidates)
+}
+
+fn pick_candidate_sync(conn: &mut SqliteConnection, candidates: Vec<BingImage>) -> Result<Option<BingImage>> {
+    let candidates = super::countries::filter_rotation_countries_sync(conn, candidates)?;
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+
+    let last_shown = operations::get_last_shown_times(conn)?;
+    let current_url = operations::get_config(conn, "current_wallpaper_url")?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i32;
+
+    let mut rng = rand::thread_rng();
+    Ok(pick_weighted(&candidates, &last_shown, current_url.as_deref(), now, &mut rng).cloned())
+}
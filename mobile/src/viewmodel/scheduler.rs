@@ -0,0 +1,349 @@
+//! Automatic wallpaper rotation
+//!
+//! The schedule lives in `config_kv`, so the GUI, tray and CLI share it. It is
+//! run by the tray and background modes: a thread wakes up every
+//! `CHECK_INTERVAL` and compares the wall clock with the time of the last
+//! scheduled rotation. Timers would stop counting during suspend; the wall
+//! clock does not, so a rotation that fell due while the machine slept runs
+//! once right after resume. Missed slots are not replayed one by one.
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use diesel::SqliteConnection;
+
+use crate::db::operations;
+
+const INTERVAL_KEY: &str = "schedule_interval_minutes";
+const TIMES_KEY: &str = "schedule_times";
+const ON_LOGIN_KEY: &str = "schedule_on_login";
+const POOL_KEY: &str = "schedule_pool";
+/// Seconds since the epoch of the last scheduled rotation
+const LAST_RUN_KEY: &str = "schedule_last_run";
+
+/// How often the scheduler thread looks at the clock
+pub const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Attempts of a due rotation (one per check), e.g. while the network comes back after resume
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Images scheduled rotation picks from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RotationPool {
+    /// The next new image of the market, downloading more when needed
+    #[default]
+    NewImages,
+    /// Weighted random favorite (or rotation collection) image
+    Favorites,
+    /// Weighted random image of a collection
+    Collection(i32),
+}
+
+impl RotationPool {
+    pub fn to_config(&self) -> String {
+        match self {
+            RotationPool::NewImages => "new".to_string(),
+            RotationPool::Favorites => "favorites".to_string(),
+            RotationPool::Collection(id) => format!("collection:{}", id),
+        }
+    }
+
+    pub fn from_config(s: &str) -> Option<Self> {
+        match s {
+            "new" => Some(RotationPool::NewImages),
+            "favorites" => Some(RotationPool::Favorites),
+            other => other
+                .strip_prefix("collection:")
+                .and_then(|id| id.parse().ok())
+                .map(RotationPool::Collection),
+        }
+    }
+}
+
+/// When and from where wallpapers rotate
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RotationSchedule {
+    /// Minutes between rotations (0 = off)
+    pub interval_minutes: u32,
+    /// Local times of day to rotate at, sorted
+    pub times: Vec<NaiveTime>,
+    /// Rotate when the tray or background mode starts (at login when autostarted)
+    pub on_login: bool,
+    pub pool: RotationPool,
+}
+
+impl RotationSchedule {
+    pub fn is_enabled(&self) -> bool {
+        self.interval_minutes > 0 || !self.times.is_empty() || self.on_login
+    }
+
+    /// Add a time of day (kept sorted, duplicates ignored)
+    pub fn add_time(&mut self, time: NaiveTime) {
+        if let Err(index) = self.times.binary_search(&time) {
+            self.times.insert(index, time);
+        }
+    }
+}
+
+/// Parse comma-separated "HH:MM" times, sorted and without duplicates
+pub fn parse_times(s: &str) -> Result<Vec<NaiveTime>> {
+    let mut times = s
+        .split(',')
+        .map(str::trim)
+        .filter(|time| !time.is_empty())
+        .map(|time| {
+            NaiveTime::parse_from_str(time, "%H:%M")
+                .map_err(|_| anyhow::anyhow!("Invalid time of day '{}' (expected HH:MM)", time))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    times.sort();
+    times.dedup();
+    Ok(times)
+}
+
+/// Format times as comma-separated "HH:MM"
+pub fn format_times(times: &[NaiveTime]) -> String {
+    times
+        .iter()
+        .map(|time| time.format("%H:%M").to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// When the next rotation falls due after one at `last_run` (None when only on login)
+pub fn next_rotation<Tz: TimeZone>(schedule: &RotationSchedule, last_run: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let by_interval = (schedule.interval_minutes > 0)
+        .then(|| last_run.clone() + TimeDelta::minutes(i64::from(schedule.interval_minutes)));
+    let by_time = schedule
+        .times
+        .iter()
+        .filter_map(|time| next_time_of_day(last_run, *time))
+        .min();
+    by_interval.into_iter().chain(by_time).min()
+}
+
+/// Whether a rotation fell due between `last_run` and `now`
+pub fn is_due<Tz: TimeZone>(schedule: &RotationSchedule, last_run: &DateTime<Tz>, now: &DateTime<Tz>) -> bool {
+    next_rotation(schedule, last_run).is_some_and(|at| at <= *now)
+}
+
+/// First occurrence of a local time of day after `after`
+fn next_time_of_day<Tz: TimeZone>(after: &DateTime<Tz>, time: NaiveTime) -> Option<DateTime<Tz>> {
+    let tz = after.timezone();
+    let date = after.date_naive();
+    (0..=2)
+        .filter_map(|days| {
+            let local = (date + TimeDelta::days(days)).and_time(time);
+            // A time skipped by a daylight saving change runs when the clock jumps past it
+            tz.from_local_datetime(&local)
+                .earliest()
+                .or_else(|| tz.from_local_datetime(&(local + TimeDelta::hours(1))).earliest())
+        })
+        .find(|at| at > after)
+}
+
+/// Load the schedule (defaults to off)
+pub fn get_schedule_sync(conn: &mut SqliteConnection) -> Result<RotationSchedule> {
+    let interval_minutes = operations::get_config(conn, INTERVAL_KEY)?
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0);
+    let times = operations::get_config(conn, TIMES_KEY)?
+        .map(|value| parse_times(&value))
+        .transpose()
+        .unwrap_or_else(|e| {
+            log::warn!("Ignoring rotation times: {}", e);
+            None
+        })
+        .unwrap_or_default();
+    let on_login = operations::get_config(conn, ON_LOGIN_KEY)?.as_deref() == Some("true");
+    let pool = operations::get_config(conn, POOL_KEY)?
+        .and_then(|value| RotationPool::from_config(&value))
+        .unwrap_or_default();
+
+    Ok(RotationSchedule { interval_minutes, times, on_login, pool })
+}
+
+/// Save the schedule; a changed schedule counts its interval from now
+pub fn set_schedule_sync(conn: &mut SqliteConnection, schedule: &RotationSchedule) -> Result<()> {
+    if get_schedule_sync(conn)? == *schedule {
+        return Ok(());
+    }
+
+    operations::set_config(conn, INTERVAL_KEY, &schedule.interval_minutes.to_string())?;
+    operations::set_config(conn, TIMES_KEY, &format_times(&schedule.times))?;
+    operations::set_config(conn, ON_LOGIN_KEY, if schedule.on_login { "true" } else { "false" })?;
+    operations::set_config(conn, POOL_KEY, &schedule.pool.to_config())?;
+    record_run_sync(conn, &Utc::now())
+}
+
+/// Time of the last scheduled rotation
+pub fn get_last_run_sync<Tz: TimeZone>(conn: &mut SqliteConnection, tz: &Tz) -> Result<Option<DateTime<Tz>>> {
+    Ok(operations::get_config(conn, LAST_RUN_KEY)?
+        .and_then(|value| value.parse::<i64>().ok())
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map(|time| time.with_timezone(tz)))
+}
+
+/// Remember when a scheduled rotation ran
+pub fn record_run_sync<Tz: TimeZone>(conn: &mut SqliteConnection, at: &DateTime<Tz>) -> Result<()> {
+    operations::set_config(conn, LAST_RUN_KEY, &at.timestamp().to_string())
+}
+
+/// Check whether a rotation is due at `now` and claim it
+///
+/// The first check, and one after the clock was set back, only starts counting.
+pub fn take_due_sync<Tz: TimeZone>(conn: &mut SqliteConnection, now: &DateTime<Tz>) -> Result<bool> {
+    let schedule = get_schedule_sync(conn)?;
+    match get_last_run_sync(conn, &now.timezone())? {
+        Some(last_run) if last_run <= *now => {
+            if !is_due(&schedule, &last_run, now) {
+                return Ok(false);
+            }
+        }
+        _ => {
+            record_run_sync(conn, now)?;
+            return Ok(false);
+        }
+    }
+
+    record_run_sync(conn, now)?;
+    Ok(true)
+}
+
+/// Set the next wallpaper of a pool
+/// Returns the image title, None when the pool has nothing to show.
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub fn run_rotation_sync(
+    conn: &mut SqliteConnection,
+    pool: RotationPool,
+    source: crate::db::WallpaperSource,
+) -> Result<Option<String>> {
+    use super::commands;
+
+    // A deleted collection falls back to favorites
+    let collection = match pool {
+        RotationPool::Collection(id) => operations::get_collection(conn, id)?.map(|collection| collection.id),
+        _ => None,
+    };
+
+    match (pool, collection) {
+        (RotationPool::NewImages, _) => {
+            commands::download_and_set_next_wallpaper_sync(conn, source).map(|result| Some(result.title))
+        }
+        (RotationPool::Collection(_), Some(id)) => commands::set_random_collection_wallpaper_sync(conn, id, source),
+        _ => commands::set_random_favorite_wallpaper_sync(conn, source),
+    }
+}
+
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+pub use thread::{take_rotated, Scheduler};
+
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+mod thread {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread::JoinHandle;
+
+    use anyhow::Result;
+    use chrono::{Local, Utc};
+
+    use super::{get_schedule_sync, record_run_sync, run_rotation_sync, take_due_sync, CHECK_INTERVAL, MAX_ATTEMPTS};
+    use crate::db::{DbPool, WallpaperSource};
+
+    static ROTATED: AtomicBool = AtomicBool::new(false);
+
+    /// Whether the scheduler changed the wallpaper since the last call (for menus showing it)
+    pub fn take_rotated() -> bool {
+        ROTATED.swap(false, Ordering::Relaxed)
+    }
+
+    /// Scheduler thread, stopped when dropped
+    pub struct Scheduler {
+        stop: Arc<AtomicBool>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl Scheduler {
+        /// Start rotating, first of all when the schedule rotates on login
+        pub fn start(pool: DbPool) -> Self {
+            let stop = Arc::new(AtomicBool::new(false));
+            let thread_stop = stop.clone();
+            let thread = std::thread::Builder::new()
+                .name("rotation-scheduler".to_string())
+                .spawn(move || run(pool, thread_stop))
+                .map_err(|e| log::error!("Failed to start the rotation scheduler: {}", e))
+                .ok();
+            Self { stop, thread }
+        }
+    }
+
+    impl Drop for Scheduler {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+            if let Some(thread) = self.thread.take() {
+                thread.thread().unpark();
+                let _ = thread.join();
+            }
+        }
+    }
+
+    fn run(pool: DbPool, stop: Arc<AtomicBool>) {
+        log::info!("Rotation scheduler started");
+        let mut pending = match pool.get().and_then(|mut conn| get_schedule_sync(&mut conn)) {
+            Ok(schedule) => schedule.on_login,
+            Err(e) => {
+                log::error!("Failed to load the rotation schedule: {}", e);
+                false
+            }
+        };
+        let mut attempts = 0;
+
+        while !stop.load(Ordering::Relaxed) {
+            if !pending {
+                pending = pool
+                    .get()
+                    .and_then(|mut conn| take_due_sync(&mut conn, &Local::now()))
+                    .unwrap_or_else(|e| {
+                        log::error!("Failed to check the rotation schedule: {}", e);
+                        false
+                    });
+            }
+
+            if pending {
+                attempts += 1;
+                match rotate(&pool) {
+                    Ok(()) => pending = false,
+                    Err(e) if attempts < MAX_ATTEMPTS => {
+                        log::warn!("Scheduled rotation failed (attempt {}/{}): {}", attempts, MAX_ATTEMPTS, e);
+                    }
+                    Err(e) => {
+                        log::error!("Scheduled rotation failed, skipping it: {}", e);
+                        pending = false;
+                    }
+                }
+                if !pending {
+                    attempts = 0;
+                }
+            }
+
+            std::thread::park_timeout(CHECK_INTERVAL);
+        }
+        log::info!("Rotation scheduler stopped");
+    }
+
+    fn rotate(pool: &DbPool) -> Result<()> {
+        let mut conn = pool.get()?;
+        let schedule = get_schedule_sync(&mut conn)?;
+        let title = run_rotation_sync(&mut conn, schedule.pool, WallpaperSource::Scheduler)?;
+        // The interval counts from the rotation, also for late and login rotations
+        record_run_sync(&mut conn, &Utc::now())?;
+
+        match title {
+            Some(title) => {
+                log::info!("Scheduled rotation set: {}", title);
+                ROTATED.store(true, Ordering::Relaxed);
+            }
+            None => log::info!("Scheduled rotation found no image to set"),
+        }
+        Ok(())
+    }
+}
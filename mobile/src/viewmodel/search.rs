@@ -0,0 +1,48 @@
+//! Full-text search over image titles, copyrights and locations
+//!
+//! Backed by the `bing_images_fts` FTS5 index, which triggers keep in sync with
+//! `bing_images`. Every word of the query matches as a prefix, results are
+//! ranked best match first.
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+
+use crate::db::{operations, BingImage, ImageStatus};
+
+/// Search results per page (same as a carousel page)
+pub const SEARCH_PAGE_SIZE: usize = 20;
+
+/// A page of search results
+#[derive(Debug, Clone, Default)]
+pub struct SearchPage {
+    pub query: String,
+    pub page: usize,
+    pub images: Vec<BingImage>,
+    pub total_count: usize,
+}
+
+impl SearchPage {
+    /// Number of pages for all results
+    pub fn page_count(&self) -> usize {
+        self.total_count.div_ceil(SEARCH_PAGE_SIZE)
+    }
+}
+
+/// Search images, optionally only those with `filter` status
+pub fn search_page_sync(
+    conn: &mut SqliteConnection,
+    query: &str,
+    filter: Option<ImageStatus>,
+    page: usize,
+) -> Result<SearchPage> {
+    let offset = (page * SEARCH_PAGE_SIZE) as i64;
+    let images = operations::search_images(conn, query, filter, SEARCH_PAGE_SIZE as i64, offset)?;
+    let total_count = operations::count_search_results(conn, query, filter)? as usize;
+
+    Ok(SearchPage {
+        query: query.to_string(),
+        page,
+        images,
+        total_count,
+    })
+}
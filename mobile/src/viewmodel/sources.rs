@@ -1,8 +1,12 @@
 use crate::{BingImage, BingResponse};
 use anyhow::{Context, Result};
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::sync::mpsc;
 
+use super::markets::MarketPage;
+
 /// Extract identifier from Bing URL (e.g., "OHR.Hnausapollur" from full URL)
 pub fn extract_identifier(url: &str) -> Option<String> {
     // URL format: https://www.bing.com/th?id=OHR.Hnausapollur_EN-US2080493040_1920x1080.jpg
@@ -13,6 +17,19 @@ pub fn extract_identifier(url: &str) -> Option<String> {
         .map(|s| s.to_string())
 }
 
+/// Extract market code from Bing URL (e.g., "en-US" from "OHR.Hnausapollur_EN-US2080493040_1920x1080.jpg")
+pub fn extract_market_code(url: &str) -> Option<String> {
+    let segment = url.split("th?id=").nth(1)?.split('_').nth(1)?;
+    let code: String = segment.chars().take_while(|c| !c.is_ascii_digit()).collect();
+    let (lang, region) = code.split_once('-')?;
+
+    if lang.len() != 2 || region.len() != 2 || !lang.chars().chain(region.chars()).all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    Some(format!("{}-{}", lang.to_lowercase(), region.to_uppercase()))
+}
+
 /// Extract markdown content from HTML/JSON response (for GitHub blob URLs with ?plain=1)
 /// Returns the original text if it's already markdown (not HTML/JSON)
 fn extract_markdown_from_html(text: &str) -> std::borrow::Cow<str> {
@@ -123,7 +140,109 @@ fn extract_markdown_from_html(text: &str) -> std::borrow::Cow<str> {
     }
 }
 
-/// Bing API image source (en-US market only)
+/// Parameters for a single provider fetch
+#[derive(Debug, Clone, Copy)]
+pub struct FetchRequest<'a> {
+    /// Bing market page to query (None = no market due for a visit)
+    pub market_page: Option<&'a MarketPage>,
+    /// Number of new images the caller wants
+    pub count: usize,
+}
+
+/// New images from `ImageSource::fetch_images`
+#[derive(Debug, Clone, Default)]
+pub struct FetchedImages {
+    pub images: Vec<BingImage>,
+    /// Providers that answered (successfully, possibly with no images)
+    pub fetched_by: Vec<String>,
+}
+
+impl FetchedImages {
+    /// Whether the named provider answered
+    pub fn answered(&self, provider: &str) -> bool {
+        self.fetched_by.iter().any(|name| name == provider)
+    }
+}
+
+/// Health of an image provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProviderHealth {
+    Healthy,
+    /// Usable, but the last fetch failed
+    Degraded(String),
+    /// Not usable right now (skipped by the registry)
+    Unavailable(String),
+}
+
+/// A source of wallpaper images
+///
+/// Implement this to plug additional sources into the registry
+/// (see `register_provider`).
+pub trait ImageProvider: Send + Sync {
+    /// Stable identifier used in source settings (e.g. "bing_api")
+    fn name(&self) -> &str;
+
+    /// Default priority, lower wins when duplicates are merged
+    fn priority(&self) -> i32;
+
+    /// Fetch images, newest first
+    fn fetch(&self, request: &FetchRequest) -> Result<Vec<BingImage>>;
+
+    /// Whether there is anything to fetch for `request`; providers that are
+    /// not due are skipped and do not count as attempted
+    fn is_due(&self, _request: &FetchRequest) -> bool {
+        true
+    }
+
+    /// Cheap self check run before each fetch
+    fn health(&self) -> ProviderHealth {
+        ProviderHealth::Healthy
+    }
+}
+
+/// Providers registered by the embedding application
+static EXTRA_PROVIDERS: OnceLock<Mutex<Vec<Arc<dyn ImageProvider>>>> = OnceLock::new();
+
+/// Register an additional provider for every registry created afterwards
+pub fn register_provider(provider: Arc<dyn ImageProvider>) {
+    let providers = EXTRA_PROVIDERS.get_or_init(|| Mutex::new(Vec::new()));
+    let mut providers = providers.lock().unwrap();
+    providers.retain(|p| p.name() != provider.name());
+    log::info!("Registered image provider: {}", provider.name());
+    providers.push(provider);
+}
+
+/// Enabled/disabled state and order of image providers (stored in config_kv)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceSettings {
+    /// Provider names in preferred order; unlisted providers follow by priority
+    #[serde(default)]
+    pub order: Vec<String>,
+    /// Provider names that are never queried
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    /// Directories scanned by the local directory provider
+    #[serde(default)]
+    pub local_dirs: Vec<String>,
+}
+
+impl SourceSettings {
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.iter().any(|d| d == name)
+    }
+
+    fn position(&self, name: &str) -> usize {
+        self.order.iter().position(|o| o == name).unwrap_or(usize::MAX)
+    }
+}
+
+/// Provider name of `BingApiSource`
+pub const BING_API_PROVIDER: &str = "bing_api";
+
+/// Provider name of `GitHubArchiveSource`
+pub const GITHUB_ARCHIVE_PROVIDER: &str = "github_archive";
+
+/// Bing API image source
 pub struct BingApiSource {
     ehttp_cache: Option<Arc<crate::ehttp_cache::EhttpCache>>,
 }
@@ -133,11 +252,23 @@ impl BingApiSource {
         Self { ehttp_cache }
     }
 
-    /// Fetch images from Bing API (en-US, offset=0, n=count)
+    /// Fetch images from Bing API (default market, offset=0, n=count)
     pub fn fetch(&self, count: u32) -> Result<Vec<BingImage>> {
+        self.fetch_market(super::markets::DEFAULT_MARKET_CODE, count)
+    }
+
+    /// Fetch images from Bing API for a market (offset=0, n=count)
+    pub fn fetch_market(&self, market_code: &str, count: u32) -> Result<Vec<BingImage>> {
+        self.fetch_page(market_code, 0, count)
+    }
+
+    /// Fetch images from Bing API for a market starting `idx` days back (n=count)
+    pub fn fetch_page(&self, market_code: &str, idx: u32, count: u32) -> Result<Vec<BingImage>> {
         let url = format!(
-            "https://www.bing.com/HPImageArchive.aspx?format=js&idx=0&n={}&mkt=en-US",
-            count.min(8) // Bing API max is 8
+            "https://www.bing.com/HPImageArchive.aspx?format=js&idx={}&n={}&mkt={}",
+            idx.min(super::markets::MAX_ARCHIVE_IDX),
+            count.min(8), // Bing API max is 8
+            market_code
         );
 
         log::info!("Fetching from Bing API: {}", url);
@@ -183,18 +314,37 @@ impl BingApiSource {
 
                 BingImage {
                     url: full_url,
-                    title: img.title,
-                    copyright: img.copyright,
-                    copyright_link: img.copyright_link,
+                    market_code: Some(market_code.to_string()),
+                    ..img
                 }
+                .normalize_title()
             })
             .collect();
 
-        log::info!("Fetched {} images from Bing API", images.len());
+        log::info!("Fetched {} images from Bing API ({})", images.len(), market_code);
         Ok(images)
     }
 }
 
+impl ImageProvider for BingApiSource {
+    fn name(&self) -> &str {
+        BING_API_PROVIDER
+    }
+
+    fn priority(&self) -> i32 {
+        10
+    }
+
+    fn fetch(&self, request: &FetchRequest) -> Result<Vec<BingImage>> {
+        let page = request.market_page.context("No Bing market page to fetch")?;
+        self.fetch_page(&page.market_code, page.idx(), 8)
+    }
+
+    fn is_due(&self, request: &FetchRequest) -> bool {
+        request.market_page.is_some()
+    }
+}
+
 /// Parse a markdown row from GitHub archive
 /// Format: YYYY-MM-DD | [Title (© Copyright)](URL)
 pub fn parse_markdown_row(row: &str) -> Option<BingImage> {
@@ -243,35 +393,171 @@ pub fn parse_markdown_row(row: &str) -> Option<BingImage> {
         title_query, startdate
     );
 
+    let market_code = extract_market_code(&normalized_url);
+    let urlbase = urlbase_from_url(&normalized_url);
+
+    // The archive lists one image per day, replaced the next day
+    let enddate = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.succ_opt())
+        .map(|d| d.format("%Y%m%d").to_string());
+
     Some(BingImage {
         url: normalized_url,
         title: title.to_string(),
         copyright,
         copyright_link: Some(copyright_link),
+        market_code,
+        startdate: Some(startdate.to_string()),
+        fullstartdate: Some(fullstartdate.clone()),
+        enddate,
+        urlbase,
+        ..Default::default()
     })
 }
 
+/// Derive Bing's `urlbase` from an image URL
+/// "https://www.bing.com/th?id=OHR.Name_EN-US123_1920x1080.jpg&rf=x" -> "/th?id=OHR.Name_EN-US123"
+pub fn urlbase_from_url(url: &str) -> Option<String> {
+    let start = url.find("/th?id=")?;
+    let path = &url[start..];
+    let path = path.split('&').next().unwrap_or(path);
+
+    // Drop the extension and the resolution suffix (_1920x1080, _UHD, ...)
+    let stem = [".jpg", ".jpeg", ".png"]
+        .iter()
+        .find_map(|ext| path.strip_suffix(ext))
+        .unwrap_or(path);
+    let base = match stem.rsplit_once('_') {
+        Some((base, suffix)) if super::resolution::is_resolution_suffix(suffix) => base,
+        _ => stem,
+    };
+
+    Some(base.to_string())
+}
+
+/// Raw markdown of the GitHub archive (plain text, supports ETag/Last-Modified)
+pub const GITHUB_ARCHIVE_URL: &str =
+    "https://raw.githubusercontent.com/v5tech/bing-wallpaper/main/bing-wallpaper.md";
+
+/// Conditional-request and incremental-parse state of the GitHub archive
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveSyncState {
+    /// `ETag` of the last downloaded archive
+    #[serde(default)]
+    pub etag: Option<String>,
+    /// `Last-Modified` of the last downloaded archive
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    /// Newest row date already ingested (YYYY-MM-DD); older rows are not parsed
+    #[serde(default)]
+    pub watermark: Option<String>,
+    /// Whether the archive should be checked (see `should_download_manifest`)
+    #[serde(skip, default = "default_true")]
+    pub due: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Result of a conditional archive fetch
+#[derive(Debug)]
+pub enum ArchiveFetch {
+    /// Server answered 304, nothing changed
+    NotModified,
+    /// Rows newer than the watermark, with the state to store
+    Modified {
+        images: Vec<BingImage>,
+        state: ArchiveSyncState,
+    },
+}
+
+/// Parse only the archive rows dated after `watermark`
+/// Returns the images and the newest row date seen.
+pub fn parse_archive_since(markdown: &str, watermark: Option<&str>) -> (Vec<BingImage>, Option<String>) {
+    let mut newest = watermark.map(str::to_string);
+    let images = markdown
+        .lines()
+        .filter(|line| {
+            // Rows start with "YYYY-MM-DD |"; compare the date before parsing the row
+            let date = line.trim().get(..10).unwrap_or("");
+            watermark.map_or(true, |mark| date > mark)
+        })
+        .filter_map(|line| {
+            let image = parse_markdown_row(line)?;
+            let date = line.trim()[..10].to_string();
+            if newest.as_deref().map_or(true, |n| date.as_str() > n) {
+                newest = Some(date);
+            }
+            Some(image)
+        })
+        .collect();
+
+    (images, newest)
+}
+
+/// Archive row date (YYYY-MM-DD) of an image, as used for the watermark
+pub fn archive_row_date(image: &BingImage) -> Option<String> {
+    let date = image.startdate.as_deref()?;
+    if date.len() != 8 || !date.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..]))
+}
+
 /// GitHub archive image source
 pub struct GitHubArchiveSource {
     ehttp_cache: Option<Arc<crate::ehttp_cache::EhttpCache>>,
+    state: Mutex<ArchiveSyncState>,
+    /// Set when a check ran and `state` should be persisted
+    checked: std::sync::atomic::AtomicBool,
 }
 
 impl GitHubArchiveSource {
     pub fn new(ehttp_cache: Option<Arc<crate::ehttp_cache::EhttpCache>>) -> Self {
-        Self { ehttp_cache }
+        Self {
+            ehttp_cache,
+            state: Mutex::new(ArchiveSyncState { due: true, ..Default::default() }),
+            checked: std::sync::atomic::AtomicBool::new(false),
+        }
     }
 
-    /// Fetch images from GitHub archive (cached 7 days)
-    pub fn fetch(&self) -> Result<Vec<BingImage>> {
-        let url = "https://github.com/v5tech/bing-wallpaper/blob/main/bing-wallpaper.md?plain=1";
+    /// Current sync state
+    pub fn state(&self) -> ArchiveSyncState {
+        self.state.lock().unwrap().clone()
+    }
 
-        log::info!("Fetching from GitHub archive: {}", url);
+    /// Restore a persisted sync state
+    pub fn set_state(&self, state: ArchiveSyncState) {
+        *self.state.lock().unwrap() = state;
+    }
+
+    /// Returns true once after each archive check (the state then needs saving)
+    pub fn take_checked(&self) -> bool {
+        self.checked.swap(false, std::sync::atomic::Ordering::SeqCst)
+    }
 
-        let mut request = ehttp::Request::get(url);
+    /// Flag the state as checked so the next `save_archive_state_sync` persists it
+    pub fn mark_checked(&self) {
+        self.checked.store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// Fetch the archive unless unchanged since `state`, parsing only rows past the watermark
+    pub fn fetch_since(&self, state: &ArchiveSyncState) -> Result<ArchiveFetch> {
+        log::info!("Fetching from GitHub archive: {}", GITHUB_ARCHIVE_URL);
+
+        let mut request = ehttp::Request::get(GITHUB_ARCHIVE_URL);
         request.headers.insert(
             "User-Agent".to_string(),
             format!("bingtray/{}", env!("CARGO_PKG_VERSION")),
         );
+        if let Some(etag) = &state.etag {
+            request.headers.insert("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &state.last_modified {
+            request.headers.insert("If-Modified-Since", last_modified);
+        }
 
         // Fetch synchronously
         let (tx, rx) = mpsc::channel();
@@ -285,40 +571,91 @@ impl GitHubArchiveSource {
 
         let resp = response.map_err(|e| anyhow::anyhow!("Network error: {}", e))?;
 
+        if resp.status == 304 {
+            log::info!("GitHub archive not modified");
+            return Ok(ArchiveFetch::NotModified);
+        }
+
         if !resp.ok {
             anyhow::bail!("HTTP {}: {}", resp.status, resp.status_text);
         }
 
-        // Parse markdown - extract from HTML if needed
+        // Parse markdown - extract from HTML if needed (e.g. a mirror serving the blob page)
         let text = resp.text().context("Invalid UTF-8")?;
-        let markdown_text = extract_markdown_from_html(&text);
-        let images: Vec<BingImage> = markdown_text
-            .lines()
-            .filter_map(parse_markdown_row)
-            .collect();
+        let markdown_text = extract_markdown_from_html(text);
+        let (images, watermark) = parse_archive_since(&markdown_text, state.watermark.as_deref());
+
+        log::info!("Parsed {} new images from GitHub archive", images.len());
+        Ok(ArchiveFetch::Modified {
+            images,
+            state: ArchiveSyncState {
+                etag: resp.headers.get("etag").map(str::to_string),
+                last_modified: resp.headers.get("last-modified").map(str::to_string),
+                watermark,
+                due: false,
+            },
+        })
+    }
+
+    /// Fetch new archive rows if a check is due
+    ///
+    /// The watermark is left alone: callers keep only some of the rows, so it
+    /// only moves in `import_github_archive_sync`, which stores all of them.
+    pub fn fetch(&self) -> Result<Vec<BingImage>> {
+        let state = self.state();
+        if !state.due {
+            log::info!("GitHub archive checked recently, skipping");
+            return Ok(Vec::new());
+        }
 
-        log::info!("Parsed {} images from GitHub archive", images.len());
+        let images = match self.fetch_since(&state)? {
+            ArchiveFetch::NotModified => Vec::new(),
+            ArchiveFetch::Modified { images, .. } => images,
+        };
+
+        self.set_state(ArchiveSyncState { due: false, ..state });
         Ok(images)
     }
 }
 
-/// Check if two images are duplicates (identifier match OR title match)
+impl ImageProvider for GitHubArchiveSource {
+    fn name(&self) -> &str {
+        GITHUB_ARCHIVE_PROVIDER
+    }
+
+    fn priority(&self) -> i32 {
+        20
+    }
+
+    fn fetch(&self, _request: &FetchRequest) -> Result<Vec<BingImage>> {
+        GitHubArchiveSource::fetch(self)
+    }
+
+    fn is_due(&self, _request: &FetchRequest) -> bool {
+        self.state().due
+    }
+}
+
+/// Check if two listings are the same image before any bytes are downloaded
+///
+/// Bing URLs are compared by OHR identifier only: different photos often share
+/// a generic title. Other URLs match when both title and copyright match.
+/// The same photo republished under a new id is caught later by `dedup`.
 pub fn is_duplicate(img1: &BingImage, img2: &BingImage) -> bool {
-    // Try identifier match
     if let (Some(id1), Some(id2)) = (extract_identifier(&img1.url), extract_identifier(&img2.url)) {
-        if id1 == id2 {
-            return true;
-        }
+        return id1 == id2;
     }
 
-    // Try title match (case-insensitive, trimmed)
-    let title1 = img1.title.to_lowercase().trim().to_string();
-    let title2 = img2.title.to_lowercase().trim().to_string();
+    // Fall back to title + copyright (case-insensitive, trimmed)
+    let normalize = |s: &str| s.trim().to_lowercase();
+    let title1 = normalize(&img1.title);
 
-    !title1.is_empty() && title1 == title2
+    !title1.is_empty()
+        && title1 == normalize(&img2.title)
+        && img1.copyright.as_deref().map(normalize) == img2.copyright.as_deref().map(normalize)
 }
 
-/// Deduplicate images, preferring Bing API results over GitHub
+/// Deduplicate images, preferring `bing_images` (higher-priority source) over `github_images`
 pub fn deduplicate(bing_images: Vec<BingImage>, github_images: Vec<BingImage>) -> Vec<BingImage> {
     let mut result = bing_images;
 
@@ -333,46 +670,173 @@ pub fn deduplicate(bing_images: Vec<BingImage>, github_images: Vec<BingImage>) -
     result
 }
 
-/// Main image source interface with dual-source fetching
+/// Registry of image providers, queried in settings order
 pub struct ImageSource {
-    bing_api: BingApiSource,
-    github_archive: GitHubArchiveSource,
+    providers: Vec<Arc<dyn ImageProvider>>,
+    settings: SourceSettings,
+    last_errors: Mutex<HashMap<String, String>>,
+    archive: Option<Arc<GitHubArchiveSource>>,
 }
 
 impl ImageSource {
+    /// Create a registry with the built-in and registered providers in default order
     pub fn new(ehttp_cache: Option<Arc<crate::ehttp_cache::EhttpCache>>) -> Self {
+        Self::with_settings(ehttp_cache, SourceSettings::default())
+    }
+
+    /// Create a registry with the built-in and registered providers, ordered by `settings`
+    pub fn with_settings(
+        ehttp_cache: Option<Arc<crate::ehttp_cache::EhttpCache>>,
+        settings: SourceSettings,
+    ) -> Self {
+        let mut source = Self::empty(settings);
+        source.register(Arc::new(BingApiSource::new(ehttp_cache.clone())));
+        let archive = Arc::new(GitHubArchiveSource::new(ehttp_cache));
+        source.register(archive.clone());
+        source.archive = Some(archive);
+
+        if !source.settings.local_dirs.is_empty() {
+            let dirs = source.settings.local_dirs.iter().map(std::path::PathBuf::from).collect();
+            source.register(Arc::new(super::local_source::LocalDirectorySource::new(dirs)));
+        }
+
+        if let Some(extra) = EXTRA_PROVIDERS.get() {
+            for provider in extra.lock().unwrap().iter() {
+                source.register(provider.clone());
+            }
+        }
+
+        source
+    }
+
+    /// Create a registry without any providers
+    pub fn empty(settings: SourceSettings) -> Self {
         Self {
-            bing_api: BingApiSource::new(ehttp_cache.clone()),
-            github_archive: GitHubArchiveSource::new(ehttp_cache),
+            providers: Vec::new(),
+            settings,
+            last_errors: Mutex::new(HashMap::new()),
+            archive: None,
         }
     }
 
-    /// Fetch images from both sources, merge and deduplicate
+    /// The built-in GitHub archive provider (for its sync state)
+    pub fn archive(&self) -> Option<&Arc<GitHubArchiveSource>> {
+        self.archive.as_ref()
+    }
+
+    /// Add a provider (replaces a provider with the same name)
+    pub fn register(&mut self, provider: Arc<dyn ImageProvider>) {
+        self.providers.retain(|p| p.name() != provider.name());
+        self.providers.push(provider);
+        self.sort_providers();
+    }
+
+    /// Replace the source settings
+    pub fn set_settings(&mut self, settings: SourceSettings) {
+        self.settings = settings;
+        self.sort_providers();
+    }
+
+    pub fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn sort_providers(&mut self) {
+        let settings = &self.settings;
+        self.providers
+            .sort_by_key(|p| (settings.position(p.name()), p.priority()));
+    }
+
+    /// All registered providers in query order (including disabled ones)
+    pub fn providers(&self) -> &[Arc<dyn ImageProvider>] {
+        &self.providers
+    }
+
+    /// Enabled providers in query order
+    pub fn enabled_providers(&self) -> impl Iterator<Item = &Arc<dyn ImageProvider>> {
+        self.providers
+            .iter()
+            .filter(|p| self.settings.is_enabled(p.name()))
+    }
+
+    /// Health of every registered provider, in query order
+    pub fn health_report(&self) -> Vec<(String, ProviderHealth)> {
+        let last_errors = self.last_errors.lock().unwrap();
+        self.providers
+            .iter()
+            .map(|p| {
+                let health = match (p.health(), last_errors.get(p.name())) {
+                    (ProviderHealth::Healthy, Some(error)) => ProviderHealth::Degraded(error.clone()),
+                    (health, _) => health,
+                };
+                (p.name().to_string(), health)
+            })
+            .collect()
+    }
+
+    /// Fetch images from every enabled provider, merge and deduplicate
+    ///
+    /// The result names the providers that answered, so callers only advance
+    /// the Bing market rotation when Bing was actually reached.
     ///
     /// # Arguments
+    /// * `market_page` - Bing market page to query (None = Bing API is not due, e.g. all markets recently visited)
     /// * `count` - Number of new images to return
     /// * `existing_urls` - URLs already in database (to skip)
-    pub fn fetch_images(&self, count: usize, existing_urls: &[String]) -> Result<Vec<BingImage>> {
-        // Fetch from Bing API (always fetch 8, the max)
-        let bing_images = match self.bing_api.fetch(8) {
-            Ok(imgs) => imgs,
-            Err(e) => {
-                log::warn!("Bing API failed: {}, continuing with GitHub only", e);
-                Vec::new()
+    pub fn fetch_images(
+        &self,
+        market_page: Option<&MarketPage>,
+        count: usize,
+        existing_urls: &[String],
+    ) -> Result<FetchedImages> {
+        let request = FetchRequest { market_page, count };
+        let mut merged: Vec<BingImage> = Vec::new();
+        let mut fetched_by = Vec::new();
+        let mut enabled = 0;
+        let mut attempted = 0;
+        let mut failed = 0;
+        let mut last_error = None;
+
+        for provider in self.enabled_providers() {
+            if let ProviderHealth::Unavailable(reason) = provider.health() {
+                log::info!("Skipping unavailable source {}: {}", provider.name(), reason);
+                continue;
             }
-        };
+            enabled += 1;
 
-        // Fetch from GitHub archive
-        let github_images = match self.github_archive.fetch() {
-            Ok(imgs) => imgs,
-            Err(e) => {
-                log::warn!("GitHub archive failed: {}, continuing with Bing only", e);
-                Vec::new()
+            if !provider.is_due(&request) {
+                log::info!("Nothing due from source {}, skipping", provider.name());
+                continue;
             }
-        };
 
-        // Merge and deduplicate (Bing takes priority)
-        let merged = deduplicate(bing_images, github_images);
+            attempted += 1;
+            match provider.fetch(&request) {
+                Ok(images) => {
+                    self.last_errors.lock().unwrap().remove(provider.name());
+                    log::info!("Source {} returned {} images", provider.name(), images.len());
+                    fetched_by.push(provider.name().to_string());
+                    // Earlier providers take priority
+                    merged = deduplicate(merged, images);
+                }
+                Err(e) => {
+                    log::warn!("Source {} failed: {}, continuing with other sources", provider.name(), e);
+                    self.last_errors.lock().unwrap().insert(provider.name().to_string(), e.to_string());
+                    failed += 1;
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if let Some(e) = last_error {
+            if failed == attempted {
+                // Nothing was fetched at all (likely offline) - let the caller retry later
+                return Err(e.context("All image sources failed"));
+            }
+        }
+
+        if enabled == 0 {
+            anyhow::bail!("No image sources enabled");
+        }
 
         // Filter out already-existing URLs and return requested count
         let new_images: Vec<BingImage> = merged
@@ -382,6 +846,6 @@ impl ImageSource {
             .collect();
 
         log::info!("Returning {} new images (filtered out existing URLs)", new_images.len());
-        Ok(new_images)
+        Ok(FetchedImages { images: new_images, fetched_by })
     }
 }
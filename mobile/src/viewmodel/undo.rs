@@ -0,0 +1,167 @@
+//! Undo and redo of image status changes, backed by the `status_log` table
+//!
+//! The log is append-only: undoing a change appends an `undo` entry restoring
+//! the old status, redoing it appends a `redo` entry. Only user actions (entries
+//! with a source) can be undone, automatic changes such as imports cannot. Keep
+//! and blacklist of the current wallpaper move on to the next wallpaper, so every
+//! step records the wallpaper it replaced and undo/redo put that one back.
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+
+use super::WallpaperSetResult;
+use crate::db::models::StatusLogDetails;
+use crate::db::{operations, ImageStatus, StatusLogAction, StatusLogEntry, WallpaperSource};
+
+/// What an undo or redo did
+#[derive(Debug, Clone)]
+pub struct UndoOutcome {
+    /// `Undo` or `Redo`
+    pub action: StatusLogAction,
+    pub url: String,
+    pub title: String,
+    /// Status the image has now
+    pub status: ImageStatus,
+    /// Wallpaper that was put back (None if the wallpaper stayed)
+    pub wallpaper: Option<WallpaperSetResult>,
+}
+
+/// Changes that can be undone and redone, latest last
+///
+/// Each item pairs the change entry with the entry that last applied (change or
+/// redo) or reverted (undo) it.
+#[derive(Default)]
+struct UndoStacks {
+    done: Vec<(StatusLogEntry, StatusLogEntry)>,
+    undone: Vec<(StatusLogEntry, StatusLogEntry)>,
+}
+
+fn load_stacks(conn: &mut SqliteConnection) -> Result<UndoStacks> {
+    let mut stacks = UndoStacks::default();
+
+    for entry in operations::get_user_status_log(conn)? {
+        match StatusLogAction::from_str(&entry.action) {
+            Some(StatusLogAction::Change) => {
+                // A new change drops what could be redone
+                stacks.undone.clear();
+                stacks.done.push((entry.clone(), entry));
+            }
+            Some(StatusLogAction::Undo) => {
+                if stacks.done.last().is_some_and(|(change, _)| Some(change.id) == entry.reverts_id) {
+                    let (change, _) = stacks.done.pop().unwrap();
+                    stacks.undone.push((change, entry));
+                }
+            }
+            Some(StatusLogAction::Redo) => {
+                if stacks.undone.last().is_some_and(|(change, _)| Some(change.id) == entry.reverts_id) {
+                    let (change, _) = stacks.undone.pop().unwrap();
+                    stacks.done.push((change, entry));
+                }
+            }
+            None => {}
+        }
+    }
+
+    Ok(stacks)
+}
+
+/// Change an image status as a user action, so it can be undone
+///
+/// `replaced_wallpaper_url` is the wallpaper the action moves away from, if it
+/// sets another wallpaper afterwards.
+pub fn record_status_change_sync(
+    conn: &mut SqliteConnection,
+    url: &str,
+    status: ImageStatus,
+    source: WallpaperSource,
+    replaced_wallpaper_url: Option<&str>,
+) -> Result<()> {
+    operations::update_image_status_logged(conn, url, status, &StatusLogDetails {
+        source: Some(source),
+        replaced_wallpaper_url,
+        ..Default::default()
+    })?;
+    Ok(())
+}
+
+/// Whether there is a status change to undo
+pub fn can_undo_sync(conn: &mut SqliteConnection) -> Result<bool> {
+    Ok(!load_stacks(conn)?.done.is_empty())
+}
+
+/// Whether there is an undone status change to redo
+pub fn can_redo_sync(conn: &mut SqliteConnection) -> Result<bool> {
+    Ok(!load_stacks(conn)?.undone.is_empty())
+}
+
+/// Undo the latest status change, putting back the wallpaper it replaced
+/// Returns None when there is nothing to undo.
+pub fn undo_last_action_sync(conn: &mut SqliteConnection, source: WallpaperSource) -> Result<Option<UndoOutcome>> {
+    let Some((change, applied)) = load_stacks(conn)?.done.pop() else {
+        return Ok(None);
+    };
+    apply_step(conn, &change, &applied, StatusLogAction::Undo, source).map(Some)
+}
+
+/// Redo the latest undone status change, putting back the wallpaper the undo replaced
+/// Returns None when there is nothing to redo.
+pub fn redo_sync(conn: &mut SqliteConnection, source: WallpaperSource) -> Result<Option<UndoOutcome>> {
+    let Some((change, reverted)) = load_stacks(conn)?.undone.pop() else {
+        return Ok(None);
+    };
+    apply_step(conn, &change, &reverted, StatusLogAction::Redo, source).map(Some)
+}
+
+/// Revert (`Undo`) or reapply (`Redo`) `change`; `previous` is the step being undone
+fn apply_step(
+    conn: &mut SqliteConnection,
+    change: &StatusLogEntry,
+    previous: &StatusLogEntry,
+    action: StatusLogAction,
+    source: WallpaperSource,
+) -> Result<UndoOutcome> {
+    let status = match action {
+        StatusLogAction::Undo => &change.old_status,
+        _ => &change.new_status,
+    };
+    let status = ImageStatus::from_str(status).unwrap_or(ImageStatus::Unprocessed);
+
+    let current = operations::get_config(conn, "current_wallpaper_url")?;
+    let restore = previous
+        .replaced_wallpaper_url
+        .clone()
+        .filter(|url| current.as_ref() != Some(url));
+
+    operations::update_image_status_logged(conn, &change.url, status, &StatusLogDetails {
+        action,
+        source: Some(source),
+        reverts_id: Some(change.id),
+        replaced_wallpaper_url: restore.as_ref().and(current.as_deref()),
+    })?;
+    log::info!("{} status of {}: now {}", action.as_str(), change.url, status.as_str());
+
+    // The status is restored even if the wallpaper can not be set
+    let wallpaper = match restore {
+        Some(url) => match super::commands::set_wallpaper_sync(conn, &url, source) {
+            Ok(true) => {
+                let title = operations::get_image(conn, &url)?.map(|image| image.title).unwrap_or_default();
+                Some(WallpaperSetResult { title, url })
+            }
+            Ok(false) => None,
+            Err(e) => {
+                log::warn!("Failed to put back wallpaper {}: {}", url, e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    let title = operations::get_image(conn, &change.url)?.map(|image| image.title).unwrap_or_default();
+    Ok(UndoOutcome {
+        action,
+        url: change.url.clone(),
+        title,
+        status,
+        wallpaper,
+    })
+}
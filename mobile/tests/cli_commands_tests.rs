@@ -0,0 +1,129 @@
+use bingtray::cli_commands::{self, Invocation, Subcommand, EXIT_FAILURE, EXIT_NOTHING};
+use bingtray::db::{models::*, operations, ImageStatus};
+use bingtray::viewmodel::ViewModel;
+use tempfile::TempDir;
+
+fn args(line: &str) -> Vec<String> {
+    std::iter::once("bingtray").chain(line.split_whitespace()).map(String::from).collect()
+}
+
+fn parse(line: &str) -> Result<Option<Invocation>, String> {
+    cli_commands::parse_args(&args(line))
+}
+
+fn command(line: &str) -> Subcommand {
+    parse(line).unwrap().unwrap().command
+}
+
+fn setup_viewmodel() -> (ViewModel, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let viewmodel = ViewModel::new_sync(temp_dir.path().join("test.db")).unwrap();
+    {
+        let mut conn = viewmodel.db_connection().unwrap();
+        for (url, title, status) in [
+            ("https://a", "Iceland waterfall", ImageStatus::Unprocessed),
+            ("https://b", "Desert dunes", ImageStatus::KeepFavorite),
+            ("https://c", "Iceland ponies", ImageStatus::Blacklisted),
+        ] {
+            operations::upsert_image(&mut conn, &NewBingImage {
+                url,
+                title,
+                market_code: "en-US",
+                status: status.as_str(),
+                ..Default::default()
+            })
+            .unwrap();
+        }
+    }
+    (viewmodel, temp_dir)
+}
+
+#[test]
+fn test_no_command_runs_a_mode() {
+    assert_eq!(parse("").unwrap(), None);
+    assert_eq!(parse("--tray --log").unwrap(), None);
+    assert_eq!(parse("--data-dir /tmp/data --portable").unwrap(), None);
+}
+
+#[test]
+fn test_parse_commands() {
+    assert_eq!(command("next"), Subcommand::Next);
+    assert_eq!(command("favorite random"), Subcommand::FavoriteRandom);
+    assert_eq!(command("list"), Subcommand::List { status: None });
+    assert_eq!(command("list --status keepfavorite"), Subcommand::List { status: Some(ImageStatus::KeepFavorite) });
+    assert_eq!(command("show 42"), Subcommand::Show { image: "42".to_string() });
+    assert_eq!(
+        command("search --status=blacklisted iceland ponies"),
+        Subcommand::Search { query: "iceland ponies".to_string(), status: Some(ImageStatus::Blacklisted) }
+    );
+    assert_eq!(command("--help"), Subcommand::Help);
+
+    // Flag values of main are not commands
+    let invocation = parse("--data-dir /tmp/data --log out.log stats --json").unwrap().unwrap();
+    assert_eq!(invocation, Invocation { command: Subcommand::Stats, json: true });
+}
+
+#[test]
+fn test_only_wallpaper_commands_change_it() {
+    assert!(command("next").changes_wallpaper());
+    assert!(command("set 42").changes_wallpaper());
+    assert!(command("favorite random").changes_wallpaper());
+    assert!(!command("list").changes_wallpaper());
+    assert!(!command("stats").changes_wallpaper());
+}
+
+#[test]
+fn test_usage_errors() {
+    assert!(parse("frobnicate").is_err());
+    assert!(parse("favorite").is_err());
+    assert!(parse("next now").is_err());
+    assert!(parse("set").is_err());
+    assert!(parse("search").is_err());
+    assert!(parse("list --status new").is_err());
+    assert!(parse("stats --status blacklisted").is_err());
+}
+
+#[test]
+fn test_list_and_search() {
+    let (viewmodel, _dir) = setup_viewmodel();
+
+    let output = cli_commands::execute(&viewmodel, &Subcommand::List { status: None }).unwrap();
+    assert_eq!(output.json.as_array().unwrap().len(), 3);
+    assert_eq!(output.text.lines().count(), 3);
+
+    let output =
+        cli_commands::execute(&viewmodel, &Subcommand::List { status: Some(ImageStatus::KeepFavorite) }).unwrap();
+    assert_eq!(output.json[0]["url"], "https://b");
+    assert!(output.text.ends_with("\tkeepfavorite\tDesert dunes\n"));
+
+    let search = Subcommand::Search { query: "iceland".to_string(), status: Some(ImageStatus::Unprocessed) };
+    let output = cli_commands::execute(&viewmodel, &search).unwrap();
+    assert_eq!(output.json.as_array().unwrap().len(), 1);
+    assert_eq!(output.json[0]["title"], "Iceland waterfall");
+}
+
+#[test]
+fn test_show_by_id_or_url() {
+    let (viewmodel, _dir) = setup_viewmodel();
+
+    let output = cli_commands::execute(&viewmodel, &Subcommand::Show { image: "https://c".to_string() }).unwrap();
+    assert_eq!(output.json["status"], "blacklisted");
+    let id = output.json["id"].as_i64().unwrap();
+
+    let output = cli_commands::execute(&viewmodel, &Subcommand::Show { image: id.to_string() }).unwrap();
+    assert_eq!(output.json["url"], "https://c");
+    assert!(output.text.contains("title: Iceland ponies\n"));
+
+    let error = cli_commands::execute(&viewmodel, &Subcommand::Show { image: "https://z".to_string() }).unwrap_err();
+    assert_eq!(cli_commands::exit_code(&error), EXIT_NOTHING);
+    assert_eq!(cli_commands::exit_code(&anyhow::anyhow!("Download failed")), EXIT_FAILURE);
+}
+
+#[test]
+fn test_stats() {
+    let (viewmodel, _dir) = setup_viewmodel();
+    let output = cli_commands::execute(&viewmodel, &Subcommand::Stats).unwrap();
+    assert_eq!(output.json["images"], 3);
+    assert_eq!(output.json["keepfavorite"], 1);
+    assert_eq!(output.json["history_entries"], 0);
+}
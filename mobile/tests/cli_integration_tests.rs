@@ -19,7 +19,7 @@ fn setup_test_db() -> (SqliteConnection, TempDir) {
     let db_path = temp_dir.path().join("test_bingtray.db");
     
     // Create database and run migrations
-    let conn = establish_connection(&db_path);
+    let conn = establish_connection(&db_path).unwrap();
     
     (conn, temp_dir)
 }
@@ -142,6 +142,7 @@ fn test_keep_and_blacklist_operations_persist() {
         status: ImageStatus::Unprocessed.as_str(),
         created_at: timestamp,
         updated_at: timestamp,
+        ..Default::default()
     };
     
     let img2 = NewBingImage {
@@ -154,6 +155,7 @@ fn test_keep_and_blacklist_operations_persist() {
         status: ImageStatus::Unprocessed.as_str(),
         created_at: timestamp,
         updated_at: timestamp,
+        ..Default::default()
     };
     
     operations::upsert_image(&mut conn, &img1).expect("Failed to insert image 1");
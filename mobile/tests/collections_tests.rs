@@ -0,0 +1,129 @@
+use bingtray::db::{self, models::*, operations, ImageStatus};
+use bingtray::viewmodel::collections::{self, COLLECTION_PAGE_SIZE};
+use diesel::prelude::*;
+use tempfile::TempDir;
+
+const FJORD: &str = "https://example.com/fjord.jpg";
+const DESERT: &str = "https://example.com/desert.jpg";
+const PEAK: &str = "https://example.com/peak.jpg";
+
+fn setup_test_db() -> (SqliteConnection, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let mut conn = db::establish_connection(&db_path).unwrap();
+
+    for url in [FJORD, DESERT, PEAK] {
+        insert_image(&mut conn, url);
+    }
+
+    (conn, temp_dir)
+}
+
+fn insert_image(conn: &mut SqliteConnection, url: &str) {
+    let image = NewBingImage {
+        url,
+        title: "Test Image",
+        market_code: "en-US",
+        status: ImageStatus::Unprocessed.as_str(),
+        ..Default::default()
+    };
+    operations::upsert_image(conn, &image).unwrap();
+}
+
+fn urls(images: Vec<BingImage>) -> Vec<String> {
+    let mut urls: Vec<String> = images.into_iter().map(|image| image.url).collect();
+    urls.sort();
+    urls
+}
+
+#[test]
+fn test_tag_and_untag() {
+    let (mut conn, _dir) = setup_test_db();
+
+    let tag = operations::tag_image(&mut conn, FJORD, " Mountains ").unwrap().unwrap();
+    assert_eq!(tag.name, "Mountains");
+    // Names are case-insensitive, tagging twice keeps one link
+    let same = operations::tag_image(&mut conn, FJORD, "mountains").unwrap().unwrap();
+    assert_eq!(same.id, tag.id);
+    operations::tag_image(&mut conn, PEAK, "MOUNTAINS").unwrap();
+    operations::tag_image(&mut conn, FJORD, "Water").unwrap();
+
+    assert_eq!(operations::get_tags(&mut conn).unwrap().len(), 2);
+    assert_eq!(urls(operations::get_images_by_tag(&mut conn, tag.id).unwrap()), [FJORD, PEAK]);
+    let names: Vec<String> = operations::get_image_tags(&mut conn, FJORD).unwrap().into_iter().map(|t| t.name).collect();
+    assert_eq!(names, ["Mountains", "Water"]);
+
+    operations::untag_image(&mut conn, FJORD, tag.id).unwrap();
+    assert_eq!(urls(operations::get_images_by_tag(&mut conn, tag.id).unwrap()), [PEAK]);
+
+    // Unknown images and empty names are not tagged
+    assert!(operations::tag_image(&mut conn, "https://missing", "Kids").unwrap().is_none());
+    assert!(operations::tag_image(&mut conn, FJORD, "  ").is_err());
+}
+
+#[test]
+fn test_collection_crud() {
+    let (mut conn, _dir) = setup_test_db();
+
+    let office = operations::create_collection(&mut conn, "Office screens").unwrap();
+    assert!(operations::add_image_to_collection(&mut conn, office.id, FJORD).unwrap());
+    assert!(operations::add_image_to_collection(&mut conn, office.id, DESERT).unwrap());
+    assert!(operations::add_image_to_collection(&mut conn, office.id, DESERT).unwrap());
+    assert!(!operations::add_image_to_collection(&mut conn, office.id, "https://missing").unwrap());
+    assert_eq!(operations::count_collection_images(&mut conn, office.id).unwrap(), 2);
+
+    operations::rename_collection(&mut conn, office.id, "Office").unwrap();
+    let collections = operations::get_image_collections(&mut conn, DESERT).unwrap();
+    assert_eq!(collections[0].name, "Office");
+
+    operations::remove_image_from_collection(&mut conn, office.id, DESERT).unwrap();
+    assert_eq!(urls(operations::get_collection_images(&mut conn, office.id, 20, 0).unwrap()), [FJORD]);
+
+    // Deleting an image or the collection removes the links, not the images
+    operations::delete_image(&mut conn, FJORD).unwrap();
+    assert_eq!(operations::count_collection_images(&mut conn, office.id).unwrap(), 0);
+    operations::add_image_to_collection(&mut conn, office.id, PEAK).unwrap();
+    operations::delete_collection(&mut conn, office.id).unwrap();
+    assert!(operations::get_collection(&mut conn, office.id).unwrap().is_none());
+    assert!(operations::get_image(&mut conn, PEAK).unwrap().is_some());
+}
+
+#[test]
+fn test_collection_pages() {
+    let (mut conn, _dir) = setup_test_db();
+    let kids = operations::create_collection(&mut conn, "Kids").unwrap();
+    for i in 0..COLLECTION_PAGE_SIZE + 2 {
+        let url = format!("https://img{}", i);
+        insert_image(&mut conn, &url);
+        operations::add_image_to_collection(&mut conn, kids.id, &url).unwrap();
+    }
+
+    let (images, total) = collections::get_collection_page_sync(&mut conn, kids.id, 1).unwrap();
+    assert_eq!(images.len(), 2);
+    assert_eq!(total, COLLECTION_PAGE_SIZE + 2);
+
+    let summaries = collections::get_collection_summaries_sync(&mut conn).unwrap();
+    assert_eq!(summaries[0].image_count, COLLECTION_PAGE_SIZE + 2);
+}
+
+#[test]
+fn test_rotation_collection() {
+    let (mut conn, _dir) = setup_test_db();
+    operations::update_image_status(&mut conn, FJORD, ImageStatus::KeepFavorite).unwrap();
+    operations::update_image_status(&mut conn, PEAK, ImageStatus::Blacklisted).unwrap();
+
+    // Without a collection rotation uses the favorites
+    assert_eq!(urls(collections::get_rotation_candidates_sync(&mut conn).unwrap()), [FJORD]);
+
+    // A collection replaces them, blacklisted images are left out
+    let desk = operations::create_collection(&mut conn, "Desk").unwrap();
+    operations::add_image_to_collection(&mut conn, desk.id, DESERT).unwrap();
+    operations::add_image_to_collection(&mut conn, desk.id, PEAK).unwrap();
+    collections::set_rotation_collection_sync(&mut conn, Some(desk.id)).unwrap();
+    assert_eq!(urls(collections::get_rotation_candidates_sync(&mut conn).unwrap()), [DESERT]);
+
+    // A deleted collection falls back to the favorites
+    operations::delete_collection(&mut conn, desk.id).unwrap();
+    assert!(collections::get_rotation_collection_sync(&mut conn).unwrap().is_none());
+    assert_eq!(urls(collections::get_rotation_candidates_sync(&mut conn).unwrap()), [FJORD]);
+}
@@ -0,0 +1,74 @@
+use bingtray::control::{self, ControlCommand, ControlServer};
+use bingtray::db::DbPool;
+use tempfile::TempDir;
+
+#[test]
+fn test_command_names_round_trip() {
+    for command in [
+        ControlCommand::Next,
+        ControlCommand::Keep,
+        ControlCommand::Blacklist,
+        ControlCommand::RandomFavorite,
+        ControlCommand::Previous,
+        ControlCommand::OpenGui,
+    ] {
+        assert_eq!(ControlCommand::from_str(&command.to_line()), Some(command));
+    }
+    assert_eq!(ControlCommand::from_str("quit"), None);
+
+    let set = ControlCommand::Set("https://www.bing.com/th?id=OHR.Fjord_EN-US123_UHD.jpg".to_string());
+    assert_eq!(set.to_line(), "set https://www.bing.com/th?id=OHR.Fjord_EN-US123_UHD.jpg");
+    assert_eq!(ControlCommand::from_str(&set.to_line()), Some(set));
+    assert_eq!(ControlCommand::from_str("set "), None);
+}
+
+#[test]
+fn test_replies_round_trip() {
+    assert_eq!(control::decode_reply(&control::encode_reply(&Ok(None))).unwrap(), None);
+
+    let reply = control::encode_reply(&Ok(Some("Hnausapollur\nIceland".to_string())));
+    assert_eq!(reply, "ok Hnausapollur Iceland\n");
+    assert_eq!(control::decode_reply(&reply).unwrap().as_deref(), Some("Hnausapollur Iceland"));
+
+    let reply = control::encode_reply(&Err(anyhow::anyhow!("No favorites")));
+    assert_eq!(control::decode_reply(&reply).unwrap_err().to_string(), "No favorites");
+    assert!(control::decode_reply("garbage").is_err());
+}
+
+#[test]
+fn test_socket_is_per_database() {
+    let name = control::socket_name(std::path::Path::new("/a/bingtray.db"));
+    assert_eq!(name, control::socket_name(std::path::Path::new("/a/bingtray.db")));
+    assert_ne!(name, control::socket_name(std::path::Path::new("/b/bingtray.db")));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_second_instance_is_refused() {
+    let temp_dir = TempDir::new().unwrap();
+    let pool = DbPool::shared(&temp_dir.path().join("test.db")).unwrap();
+
+    let first = ControlServer::start(pool.clone(), false).unwrap();
+    assert!(first.is_some());
+    assert!(ControlServer::start(pool.clone(), false).unwrap().is_none());
+
+    // Free again once the first instance stops
+    drop(first);
+    assert!(ControlServer::start(pool, false).unwrap().is_some());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_silent_client_does_not_block_shutdown() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let server = ControlServer::start(DbPool::shared(&db_path).unwrap(), false).unwrap().unwrap();
+
+    // Connects and never sends a command
+    let _silent = std::os::unix::net::UnixStream::connect(control::socket_name(&db_path)).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    let started = std::time::Instant::now();
+    drop(server);
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+}
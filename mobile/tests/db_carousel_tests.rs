@@ -6,7 +6,7 @@ use std::path::PathBuf;
 fn test_crop_coords_json_storage() {
     // Setup: In-memory database
     let db_path = PathBuf::from(":memory:");
-    let mut conn = establish_connection(&db_path);
+    let mut conn = establish_connection(&db_path).unwrap();
 
     // Run migrations
     use diesel_migrations::MigrationHarness;
@@ -24,6 +24,7 @@ fn test_crop_coords_json_storage() {
         fetched_at: 1234567890,
         created_at: 1234567890,
         updated_at: 1234567890,
+        ..Default::default()
     };
 
     operations::upsert_image(&mut conn, &test_image).expect("insert");
@@ -43,7 +44,7 @@ fn test_crop_coords_json_storage() {
 #[test]
 fn test_crop_coords_null_handling() {
     let db_path = PathBuf::from(":memory:");
-    let mut conn = establish_connection(&db_path);
+    let mut conn = establish_connection(&db_path).unwrap();
 
     use diesel_migrations::MigrationHarness;
     conn.run_pending_migrations(bingtray::db::MIGRATIONS).expect("migrations");
@@ -59,6 +60,7 @@ fn test_crop_coords_null_handling() {
         fetched_at: 1234567890,
         created_at: 1234567890,
         updated_at: 1234567890,
+        ..Default::default()
     };
 
     operations::upsert_image(&mut conn, &test_image).expect("insert");
@@ -5,7 +5,7 @@ use tempfile::TempDir;
 fn setup_test_db() -> (SqliteConnection, TempDir) {
     let temp_dir = TempDir::new().unwrap();
     let db_path = temp_dir.path().join("test.db");
-    let conn = db::establish_connection(&db_path);
+    let conn = db::establish_connection(&db_path).unwrap();
     (conn, temp_dir)
 }
 
@@ -20,6 +20,7 @@ fn create_test_image(url: &str, status: ImageStatus) -> NewBingImage {
         status: status.as_str(),
         created_at: 1234567890,
         updated_at: 1234567890,
+        ..Default::default()
     }
 }
 
@@ -137,3 +138,164 @@ fn test_config_operations() {
     let value = db::operations::get_config(&mut conn, "non_existent").unwrap();
     assert_eq!(value, None);
 }
+
+#[test]
+fn test_bing_metadata_persisted_and_kept_on_upsert() {
+    let (mut conn, _dir) = setup_test_db();
+
+    let url = "https://www.bing.com/th?id=OHR.Test_EN-US123_1920x1080.jpg";
+    let img = NewBingImage {
+        startdate: Some("20260601"),
+        fullstartdate: Some("202606010700"),
+        enddate: Some("20260602"),
+        urlbase: Some("/th?id=OHR.Test_EN-US123"),
+        hsh: Some("abc123"),
+        ..create_test_image(url, ImageStatus::Unprocessed)
+    };
+    db::operations::upsert_image(&mut conn, &img).unwrap();
+
+    // A source without metadata must not erase it
+    db::operations::upsert_image(&mut conn, &create_test_image(url, ImageStatus::Unprocessed)).unwrap();
+
+    let stored = db::operations::get_image(&mut conn, url).unwrap().unwrap();
+    assert_eq!(stored.fullstartdate.as_deref(), Some("202606010700"));
+    assert_eq!(stored.urlbase.as_deref(), Some("/th?id=OHR.Test_EN-US123"));
+    assert_eq!(stored.hsh.as_deref(), Some("abc123"));
+    assert_eq!(stored.publication_date(), chrono::NaiveDate::from_ymd_opt(2026, 6, 1));
+}
+
+#[test]
+fn test_images_sorted_by_publication_date() {
+    let (mut conn, _dir) = setup_test_db();
+
+    // Fetched in the opposite order of publication
+    for (url, date, fetched_at) in [
+        ("https://example.com/new.jpg", Some("202606030700"), 100),
+        ("https://example.com/old.jpg", Some("202606010700"), 300),
+        ("https://example.com/undated.jpg", None, 200),
+    ] {
+        let img = NewBingImage {
+            fullstartdate: date,
+            fetched_at,
+            ..create_test_image(url, ImageStatus::Unprocessed)
+        };
+        db::operations::upsert_image(&mut conn, &img).unwrap();
+    }
+
+    let images = db::operations::get_images_by_status(&mut conn, ImageStatus::Unprocessed).unwrap();
+    let urls: Vec<&str> = images.iter().map(|i| i.url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec!["https://example.com/new.jpg", "https://example.com/old.jpg", "https://example.com/undated.jpg"]
+    );
+}
+
+#[test]
+fn test_upsert_batch_returns_rows_and_keeps_status() {
+    let (mut conn, _dir) = setup_test_db();
+
+    let existing = db::operations::upsert_image(
+        &mut conn,
+        &NewBingImage {
+            urlbase: Some("/th?id=OHR.First_EN-US1"),
+            ..create_test_image("https://example.com/first.jpg", ImageStatus::KeepFavorite)
+        },
+    )
+    .unwrap();
+
+    let records = vec![
+        NewBingImage {
+            title: "Renamed",
+            ..create_test_image("https://example.com/first.jpg", ImageStatus::Unprocessed)
+        },
+        create_test_image("https://example.com/second.jpg", ImageStatus::Unprocessed),
+        create_test_image("https://example.com/third.jpg", ImageStatus::Unprocessed),
+    ];
+    let rows = db::operations::upsert_images_batch(&mut conn, &records).unwrap();
+
+    let urls: Vec<&str> = rows.iter().map(|row| row.url.as_str()).collect();
+    assert_eq!(urls, vec![
+        "https://example.com/first.jpg",
+        "https://example.com/second.jpg",
+        "https://example.com/third.jpg",
+    ]);
+
+    // Updated in place: same id, new title, status and metadata kept
+    assert_eq!(rows[0].id, existing.id);
+    assert_eq!(rows[0].title, "Renamed");
+    assert_eq!(rows[0].status, "keepfavorite");
+    assert_eq!(rows[0].urlbase.as_deref(), Some("/th?id=OHR.First_EN-US1"));
+
+    // Inserted rows get their own ids (not just "the highest id")
+    assert_ne!(rows[1].id, rows[2].id);
+    let third = db::operations::get_image(&mut conn, "https://example.com/third.jpg").unwrap().unwrap();
+    assert_eq!(third.id, rows[2].id);
+
+    let known = db::operations::get_existing_urls(
+        &mut conn,
+        &["https://example.com/second.jpg", "https://example.com/missing.jpg"],
+    )
+    .unwrap();
+    assert_eq!(known, vec!["https://example.com/second.jpg".to_string()]);
+}
+
+#[test]
+fn test_establish_connection_reports_bad_databases() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let missing_dir = temp_dir.path().join("missing").join("test.db");
+    assert!(db::establish_connection(&missing_dir).is_err());
+
+    let corrupted = temp_dir.path().join("corrupted.db");
+    std::fs::write(&corrupted, vec![0x42u8; 4096]).unwrap();
+    assert!(db::establish_connection(&corrupted).is_err());
+    assert!(db::DbPool::open(&corrupted).is_err());
+}
+
+#[test]
+fn test_pool_reuses_connections() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    let pool = db::DbPool::shared(&db_path).unwrap();
+    {
+        let mut conn = pool.get().unwrap();
+        db::operations::upsert_image(
+            &mut conn,
+            &create_test_image("https://example.com/pooled.jpg", ImageStatus::Unprocessed),
+        )
+        .unwrap();
+    }
+    // The connection went back to the pool
+    assert_eq!(pool.idle_count(), 1);
+
+    // Same file, same pool: the row is visible through any of its connections
+    let shared = db::DbPool::shared(&db_path).unwrap();
+    assert_eq!(shared.db_path(), db_path.as_path());
+    let mut first = shared.get().unwrap();
+    assert_eq!(pool.idle_count(), 0); // the idle connection was taken, not a new one opened
+    let mut second = shared.get().unwrap();
+    assert!(db::operations::get_image(&mut first, "https://example.com/pooled.jpg").unwrap().is_some());
+    assert!(db::operations::get_image(&mut second, "https://example.com/pooled.jpg").unwrap().is_some());
+
+    drop(first);
+    drop(second);
+    assert_eq!(pool.idle_count(), 2);
+    let _reused = pool.get().unwrap();
+    assert_eq!(pool.idle_count(), 1);
+}
+
+#[test]
+fn test_pool_caches_images_next_to_its_database() {
+    let first_dir = TempDir::new().unwrap();
+    let second_dir = TempDir::new().unwrap();
+    let first = db::DbPool::open(&first_dir.path().join("test.db")).unwrap();
+    let second = db::DbPool::open(&second_dir.path().join("test.db")).unwrap();
+
+    let url = "https://example.com/cached.jpg";
+    bingtray::viewmodel::commands::save_to_cache(&mut first.get().unwrap(), url, b"jpg").unwrap();
+
+    let filename = bingtray::viewmodel::cache_manager::get_cache_filename(url);
+    assert!(first_dir.path().join("cache").join("images").join(&filename).exists());
+    assert_eq!(bingtray::viewmodel::commands::load_cached_image(&mut second.get().unwrap(), url).unwrap(), None);
+}
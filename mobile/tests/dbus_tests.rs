@@ -0,0 +1,72 @@
+//! Runs against a private `dbus-daemon --session` (skipped when it is not installed)
+#![cfg(target_os = "linux")]
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+
+use bingtray::db::{models::*, operations, DbPool, ImageStatus};
+use bingtray::dbus::{DbusService, BUS_NAME, OBJECT_PATH};
+use tempfile::TempDir;
+
+struct PrivateBus(Child);
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Start a session bus and point this process at it
+fn start_private_bus() -> Option<PrivateBus> {
+    let mut daemon = Command::new("dbus-daemon")
+        .args(["--session", "--nofork", "--print-address"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .ok()?;
+    let mut address = String::new();
+    BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+    std::env::set_var("DBUS_SESSION_BUS_ADDRESS", address.trim());
+    Some(PrivateBus(daemon))
+}
+
+#[test]
+fn test_service_on_private_bus() {
+    let Some(_bus) = start_private_bus() else {
+        eprintln!("dbus-daemon not available, skipping");
+        return;
+    };
+
+    let temp_dir = TempDir::new().unwrap();
+    let pool = DbPool::shared(&temp_dir.path().join("test.db")).unwrap();
+    {
+        let mut conn = pool.get().unwrap();
+        operations::upsert_image(&mut conn, &NewBingImage {
+            url: "https://example.com/OHR.Test_EN-US123_UHD.jpg",
+            title: "Test Image",
+            copyright: Some("Somewhere (© Someone)"),
+            market_code: "en-US",
+            status: ImageStatus::Unprocessed.as_str(),
+            ..Default::default()
+        })
+        .unwrap();
+        operations::set_config(&mut conn, "current_wallpaper_url", "https://example.com/OHR.Test_EN-US123_UHD.jpg")
+            .unwrap();
+    }
+
+    let _service = DbusService::start(pool.clone()).expect("service should own the name");
+    // One owner per session
+    assert!(DbusService::start(pool).is_none());
+
+    let connection = zbus::blocking::Connection::session().unwrap();
+    let proxy = zbus::blocking::Proxy::new(&connection, BUS_NAME, OBJECT_PATH, BUS_NAME).unwrap();
+
+    let title: String = proxy.get_property("Title").unwrap();
+    assert_eq!(title, "Test Image");
+    let copyright: String = proxy.get_property("Copyright").unwrap();
+    assert_eq!(copyright, "Somewhere (© Someone)");
+
+    // Nothing in the history to go back to
+    let result: zbus::Result<String> = proxy.call("Previous", &());
+    assert!(result.is_err());
+}
@@ -0,0 +1,110 @@
+use bingtray::db::{self, models::NewBingImage, operations, ImageStatus};
+use bingtray::viewmodel::dedup;
+use bingtray::viewmodel::sources::is_duplicate;
+use bingtray::BingImage;
+use diesel::prelude::*;
+use std::io::Cursor;
+use tempfile::TempDir;
+
+fn setup_test_db() -> (SqliteConnection, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let conn = db::establish_connection(&db_path).unwrap();
+    (conn, temp_dir)
+}
+
+/// Encode a synthetic "photo" as PNG: a horizontal gradient or a checkerboard
+fn png(width: u32, height: u32, checkerboard: bool, brightness: u8) -> Vec<u8> {
+    let img = image::GrayImage::from_fn(width, height, |x, y| {
+        let value = if checkerboard {
+            if (x * 8 / width + y * 8 / height) % 2 == 0 { 40 } else { 200 }
+        } else {
+            (255 - x * 200 / width) as u8
+        };
+        image::Luma([value.saturating_add(brightness)])
+    });
+
+    let mut bytes = Vec::new();
+    img.write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png).unwrap();
+    bytes
+}
+
+fn insert(conn: &mut SqliteConnection, url: &str) -> i32 {
+    let img = NewBingImage {
+        url,
+        title: "Same title",
+        market_code: "en-US",
+        status: ImageStatus::Unprocessed.as_str(),
+        ..Default::default()
+    };
+    operations::upsert_image(conn, &img).unwrap().id
+}
+
+#[test]
+fn test_dhash_tolerates_resize_and_brightness() {
+    let original = dedup::dhash(&png(192, 108, false, 0)).unwrap();
+    let resized = dedup::dhash(&png(384, 216, false, 10)).unwrap();
+    let different = dedup::dhash(&png(192, 108, true, 0)).unwrap();
+
+    assert!(dedup::hamming_distance(original, resized) <= dedup::DUPLICATE_DISTANCE);
+    assert!(dedup::hamming_distance(original, different) > dedup::DUPLICATE_DISTANCE);
+    assert_eq!(dedup::hash_from_hex(&dedup::hash_to_hex(original)), Some(original));
+}
+
+#[test]
+fn test_near_duplicate_linked_to_canonical_row() {
+    let (mut conn, _dir) = setup_test_db();
+    let first = insert(&mut conn, "https://www.bing.com/th?id=OHR.Lake_EN-US1_1920x1080.jpg");
+    let second = insert(&mut conn, "https://www.bing.com/th?id=OHR.LakeAgain_JA-JP2_1920x1080.jpg");
+    let other = insert(&mut conn, "https://www.bing.com/th?id=OHR.Castle_EN-US3_1920x1080.jpg");
+
+    let none = dedup::record_image_hash_sync(&mut conn, "https://www.bing.com/th?id=OHR.Lake_EN-US1_1920x1080.jpg", &png(192, 108, false, 0)).unwrap();
+    assert_eq!(none, None);
+
+    let linked = dedup::record_image_hash_sync(&mut conn, "https://www.bing.com/th?id=OHR.LakeAgain_JA-JP2_1920x1080.jpg", &png(384, 216, false, 5)).unwrap();
+    assert_eq!(linked, Some(first));
+
+    // Same generic title, different photo: stays canonical
+    let unrelated = dedup::record_image_hash_sync(&mut conn, "https://www.bing.com/th?id=OHR.Castle_EN-US3_1920x1080.jpg", &png(192, 108, true, 0)).unwrap();
+    assert_eq!(unrelated, None);
+
+    assert_eq!(operations::get_duplicate_ids(&mut conn, first).unwrap(), vec![second]);
+    assert_eq!(operations::get_duplicate_ids(&mut conn, other).unwrap(), Vec::<i32>::new());
+}
+
+#[test]
+fn test_deleting_canonical_promotes_duplicate() {
+    let (mut conn, _dir) = setup_test_db();
+    let urls = [
+        "https://example.com/a.jpg",
+        "https://example.com/b.jpg",
+        "https://example.com/c.jpg",
+    ];
+    let ids: Vec<i32> = urls.iter().map(|url| insert(&mut conn, url)).collect();
+    for url in &urls {
+        dedup::record_image_hash_sync(&mut conn, url, &png(192, 108, false, 0)).unwrap();
+    }
+    assert_eq!(operations::get_duplicate_ids(&mut conn, ids[0]).unwrap(), vec![ids[1], ids[2]]);
+
+    operations::delete_image(&mut conn, urls[0]).unwrap();
+
+    let promoted = operations::get_image(&mut conn, urls[1]).unwrap().unwrap();
+    assert_eq!(promoted.canonical_id, None);
+    assert_eq!(operations::get_duplicate_ids(&mut conn, ids[1]).unwrap(), vec![ids[2]]);
+}
+
+#[test]
+fn test_generic_titles_are_not_duplicates() {
+    let image = |url: &str, title: &str| BingImage {
+        url: url.to_string(),
+        title: title.to_string(),
+        ..Default::default()
+    };
+
+    let a = image("https://www.bing.com/th?id=OHR.Fjord_EN-US1_1920x1080.jpg", "Info");
+    let b = image("https://www.bing.com/th?id=OHR.Desert_EN-US2_1920x1080.jpg", "Info");
+    let a_other_market = image("https://www.bing.com/th?id=OHR.Fjord_DE-DE9_1920x1080.jpg", "Fjord");
+
+    assert!(!is_duplicate(&a, &b));
+    assert!(is_duplicate(&a, &a_other_market));
+}
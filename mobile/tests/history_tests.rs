@@ -0,0 +1,114 @@
+use bingtray::db::{self, models::*, operations, WallpaperSource};
+use bingtray::viewmodel::commands::crop_image_bytes;
+use bingtray::viewmodel::history::{self, HISTORY_PAGE_SIZE};
+use bingtray::viewmodel::CropCoords;
+use diesel::prelude::*;
+use tempfile::TempDir;
+
+fn setup_test_db() -> (SqliteConnection, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let conn = db::establish_connection(&db_path).unwrap();
+    (conn, temp_dir)
+}
+
+/// Add a history entry at a fixed time and make it the current wallpaper
+fn add_entry(conn: &mut SqliteConnection, url: &str, set_at: i32) -> WallpaperHistoryEntry {
+    let entry = operations::insert_history_entry(conn, &NewWallpaperHistoryEntry {
+        url,
+        set_at,
+        source: WallpaperSource::Tray.as_str(),
+        monitor: None,
+        crop_coords: None,
+    })
+    .unwrap();
+    operations::set_config(conn, "current_wallpaper_url", url).unwrap();
+    entry
+}
+
+#[test]
+fn test_record_tracks_current_wallpaper() {
+    let (mut conn, _dir) = setup_test_db();
+    let crop = CropCoords { x: 0.1, y: 0.2, width: 0.5, height: 0.5 };
+
+    let entry = history::record_wallpaper_set_sync(&mut conn, "https://a", WallpaperSource::Cli, Some("HDMI-1"), Some(&crop))
+        .unwrap();
+
+    assert_eq!(entry.source, "cli");
+    assert_eq!(entry.monitor.as_deref(), Some("HDMI-1"));
+    assert_eq!(CropCoords::from_json(entry.crop_coords.as_deref().unwrap()).unwrap(), crop);
+    assert_eq!(operations::get_config(&mut conn, "current_wallpaper_url").unwrap().as_deref(), Some("https://a"));
+}
+
+#[test]
+fn test_previous_walks_back_through_history() {
+    let (mut conn, _dir) = setup_test_db();
+    add_entry(&mut conn, "https://a", 100);
+    add_entry(&mut conn, "https://b", 200);
+    add_entry(&mut conn, "https://c", 300);
+
+    let previous = history::previous_entry_sync(&mut conn).unwrap().unwrap();
+    assert_eq!(previous.url, "https://b");
+    history::record_previous_set_sync(&mut conn, &previous, WallpaperSource::Gui).unwrap();
+
+    // Going back again continues before the entry we went back to
+    let previous = history::previous_entry_sync(&mut conn).unwrap().unwrap();
+    assert_eq!(previous.url, "https://a");
+    history::record_previous_set_sync(&mut conn, &previous, WallpaperSource::Gui).unwrap();
+    assert!(history::previous_entry_sync(&mut conn).unwrap().is_none());
+
+    // A new wallpaper starts over from the newest entry
+    history::record_wallpaper_set_sync(&mut conn, "https://d", WallpaperSource::Cli, None, None).unwrap();
+    let previous = history::previous_entry_sync(&mut conn).unwrap().unwrap();
+    assert_eq!(previous.url, "https://a");
+}
+
+#[test]
+fn test_history_page_durations() {
+    let (mut conn, _dir) = setup_test_db();
+    let count = HISTORY_PAGE_SIZE + 2;
+    for i in 0..count {
+        add_entry(&mut conn, &format!("https://img{}", i), 1000 + (i as i32) * 60);
+    }
+
+    let first = history::get_history_page_sync(&mut conn, 0).unwrap();
+    assert_eq!(first.total, count);
+    assert_eq!(first.items.len(), HISTORY_PAGE_SIZE);
+    assert_eq!(first.items[0].entry.url, format!("https://img{}", count - 1));
+    assert_eq!(first.items[1].shown_secs, 60);
+
+    // The first entry of a later page was shown until the last entry of the page before
+    let second = history::get_history_page_sync(&mut conn, 1).unwrap();
+    assert_eq!(second.items.len(), 2);
+    assert_eq!(second.items[0].entry.url, "https://img1");
+    assert_eq!(second.items[0].shown_secs, 60);
+    assert_eq!(second.items[1].entry.url, "https://img0");
+}
+
+#[test]
+fn test_time_shown_per_image() {
+    let (mut conn, _dir) = setup_test_db();
+    add_entry(&mut conn, "https://a", 100);
+    add_entry(&mut conn, "https://b", 400);
+    add_entry(&mut conn, "https://a", 500);
+    add_entry(&mut conn, "https://c", 600);
+
+    assert_eq!(history::time_shown_sync(&mut conn, "https://a").unwrap(), 400);
+    assert_eq!(history::time_shown_sync(&mut conn, "https://b").unwrap(), 100);
+    assert_eq!(history::time_shown_sync(&mut conn, "https://missing").unwrap(), 0);
+
+    // The current wallpaper is still being shown
+    let totals = history::time_shown_per_image_sync(&mut conn).unwrap();
+    assert_eq!(totals[0].0, "https://c");
+}
+
+#[test]
+fn test_crop_image_bytes() {
+    let image = image::RgbImage::from_pixel(200, 100, image::Rgb([10, 20, 30]));
+    let mut bytes = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png).unwrap();
+
+    let crop = CropCoords { x: 0.5, y: 0.0, width: 0.25, height: 0.5 };
+    let cropped = image::load_from_memory(&crop_image_bytes(&bytes, &crop).unwrap()).unwrap();
+    assert_eq!((cropped.width(), cropped.height()), (50, 50));
+}
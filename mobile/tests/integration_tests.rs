@@ -46,7 +46,7 @@ fn test_database_persists_across_connections() {
 
     // Insert data
     {
-        let mut conn = db::establish_connection(&db_path);
+        let mut conn = db::establish_connection(&db_path).unwrap();
         let new_img = NewBingImage {
             url: "https://example.com/persist.jpg",
             title: "Persist Test",
@@ -57,13 +57,14 @@ fn test_database_persists_across_connections() {
             status: ImageStatus::Unprocessed.as_str(),
             created_at: 1234567890,
             updated_at: 1234567890,
+            ..Default::default()
         };
         operations::upsert_image(&mut conn, &new_img).unwrap();
     }
 
     // Verify data persists
     {
-        let mut conn = db::establish_connection(&db_path);
+        let mut conn = db::establish_connection(&db_path).unwrap();
         let img = operations::get_image(&mut conn, "https://example.com/persist.jpg").unwrap();
         assert!(img.is_some());
         assert_eq!(img.unwrap().title, "Persist Test");
@@ -0,0 +1,170 @@
+use bingtray::db::{self, models::*, operations, ImageStatus};
+use bingtray::viewmodel::legacy_import::{
+    self, import_legacy_layout_once_sync, import_legacy_layout_sync, legacy_identifier, parse_market_line,
+};
+use diesel::prelude::*;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn setup_test_db() -> (SqliteConnection, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let conn = db::establish_connection(&db_path).unwrap();
+    (conn, temp_dir)
+}
+
+fn insert_image(conn: &mut SqliteConnection, url: &str, hsh: Option<&str>, status: ImageStatus) {
+    let image = NewBingImage {
+        url,
+        title: "Test Image",
+        market_code: "en-US",
+        hsh,
+        status: status.as_str(),
+        ..Default::default()
+    };
+    operations::upsert_image(conn, &image).unwrap();
+}
+
+fn status_of(conn: &mut SqliteConnection, url: &str) -> String {
+    operations::get_image(conn, url).unwrap().unwrap().status
+}
+
+fn write_file(path: &Path, contents: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+
+const FJORD_US: &str = "https://www.bing.com/th?id=OHR.Fjord_EN-US123_1920x1080.jpg";
+const FJORD_JP: &str = "https://www.bing.com/th?id=OHR.Fjord_JA-JP456_1920x1080.jpg";
+const DESERT: &str = "https://www.bing.com/th?id=OHR.Desert_EN-US789_1920x1080.jpg";
+const FOREST: &str = "https://www.bing.com/th?id=OHR.Forest_EN-US321_1920x1080.jpg";
+
+#[test]
+fn test_legacy_identifier() {
+    assert_eq!(legacy_identifier("OHR.Fjord_EN-US123_1920x1080.jpg").as_deref(), Some("OHR.Fjord"));
+    assert_eq!(legacy_identifier("th?id=OHR.Fjord_UHD.jpg").as_deref(), Some("OHR.Fjord"));
+    assert_eq!(legacy_identifier(FJORD_US).as_deref(), Some("OHR.Fjord"));
+    assert_eq!(legacy_identifier("Fjord.jpg").as_deref(), Some("OHR.Fjord"));
+    assert_eq!(legacy_identifier("my wallpaper.jpg"), None);
+}
+
+#[test]
+fn test_parse_market_line() {
+    assert_eq!(parse_market_line("en-US|1700000000"), Some(("en-US".to_string(), Some(1700000000))));
+    assert_eq!(parse_market_line("ja_jp = 5"), Some(("ja-JP".to_string(), Some(5))));
+    assert_eq!(parse_market_line("de-DE"), Some(("de-DE".to_string(), None)));
+    assert_eq!(parse_market_line("english|5"), None);
+}
+
+#[test]
+fn test_imports_statuses_and_markets() {
+    let (mut conn, dir) = setup_test_db();
+    let root = dir.path();
+
+    insert_image(&mut conn, FJORD_US, None, ImageStatus::Unprocessed);
+    insert_image(&mut conn, FJORD_JP, None, ImageStatus::Unprocessed);
+    insert_image(&mut conn, DESERT, Some("0123456789abcdef0123456789abcdef"), ImageStatus::Unprocessed);
+    insert_image(&mut conn, FOREST, None, ImageStatus::Blacklisted);
+
+    write_file(&root.join("keepfavorite/OHR.Fjord_EN-US123_1920x1080.jpg"), "jpg");
+    write_file(&root.join("keepfavorite/OHR.Forest_EN-US321_1920x1080.jpg"), "jpg");
+    write_file(&root.join("keepfavorite/OHR.Unknown_EN-US1_1920x1080.jpg"), "jpg");
+    write_file(&root.join("unprocessed/OHR.Desert_EN-US789_1920x1080.jpg"), "jpg");
+    write_file(&root.join("unprocessed/notes.txt"), "not an image");
+    write_file(
+        &root.join("blacklist.conf"),
+        "# blacklisted\n0123456789ABCDEF0123456789ABCDEF\nffffffffffffffffffffffffffffffff\n",
+    );
+    write_file(&root.join("marketcodes.conf"), "en-US|1700000000\nko-KR|1600000000\nnot a market\n");
+
+    let report = import_legacy_layout_sync(&mut conn, &[root.to_path_buf()]).unwrap();
+
+    // Every market's copy of a kept photo becomes a favorite
+    assert_eq!(status_of(&mut conn, FJORD_US), "keepfavorite");
+    assert_eq!(status_of(&mut conn, FJORD_JP), "keepfavorite");
+    // Blacklist lines match by Bing hash
+    assert_eq!(status_of(&mut conn, DESERT), "blacklisted");
+    // Choices already made in the database are kept
+    assert_eq!(status_of(&mut conn, FOREST), "blacklisted");
+
+    assert_eq!(report.favorites, 2);
+    assert_eq!(report.blacklisted, 1);
+    assert_eq!(report.unprocessed, 1);
+    assert_eq!(report.markets, 2);
+    assert_eq!(report.unmatched.len(), 3);
+    assert!(report.unmatched.iter().any(|item| item.contains("OHR.Unknown")));
+    assert!(report.unmatched.iter().any(|item| item.ends_with("ffffffffffffffffffffffffffffffff")));
+    assert!(report.unmatched.iter().any(|item| item.ends_with("not a market")));
+
+    let markets = operations::get_market_codes(&mut conn).unwrap();
+    let ko = markets.iter().find(|market| market.code == "ko-KR").unwrap();
+    assert_eq!(ko.last_used_at, 1600000000);
+}
+
+#[test]
+fn test_import_runs_once_after_images_are_known() {
+    let (mut conn, dir) = setup_test_db();
+    let roots = vec![dir.path().to_path_buf()];
+    write_file(&dir.path().join("keepfavorite/OHR.Fjord_EN-US123_1920x1080.jpg"), "jpg");
+
+    // Nothing to match against yet: wait for a later call
+    assert_eq!(import_legacy_layout_once_sync(&mut conn, &roots).unwrap(), None);
+    assert!(operations::get_config(&mut conn, legacy_import::LEGACY_IMPORT_DONE_KEY).unwrap().is_none());
+
+    insert_image(&mut conn, FJORD_US, None, ImageStatus::Unprocessed);
+    let report = import_legacy_layout_once_sync(&mut conn, &roots).unwrap().unwrap();
+    assert_eq!(report.favorites, 1);
+    assert_eq!(status_of(&mut conn, FJORD_US), "keepfavorite");
+
+    // Later changes are not overwritten by a second import
+    operations::update_image_status(&mut conn, FJORD_US, ImageStatus::Unprocessed).unwrap();
+    assert_eq!(import_legacy_layout_once_sync(&mut conn, &roots).unwrap(), None);
+    assert_eq!(status_of(&mut conn, FJORD_US), "unprocessed");
+}
+
+#[test]
+fn test_unmatched_files_are_retried() {
+    let (mut conn, dir) = setup_test_db();
+    let roots = vec![dir.path().to_path_buf()];
+    write_file(&dir.path().join("keepfavorite/OHR.Fjord_EN-US123_1920x1080.jpg"), "jpg");
+    write_file(&dir.path().join("keepfavorite/OHR.Desert_EN-US789_1920x1080.jpg"), "jpg");
+    write_file(&dir.path().join(legacy_import::MARKETCODES_FILE), "en-US|1700000000\n");
+    insert_image(&mut conn, FJORD_US, None, ImageStatus::Unprocessed);
+
+    let report = import_legacy_layout_once_sync(&mut conn, &roots).unwrap().unwrap();
+    assert_eq!((report.favorites, report.markets, report.retryable.len()), (1, 1, 1));
+    assert!(operations::get_config(&mut conn, legacy_import::LEGACY_IMPORT_DONE_KEY).unwrap().is_none());
+
+    // Only the remainder is retried: a later change to the matched image stays
+    operations::update_image_status(&mut conn, FJORD_US, ImageStatus::Unprocessed).unwrap();
+    assert_eq!(import_legacy_layout_once_sync(&mut conn, &roots).unwrap(), None);
+
+    insert_image(&mut conn, DESERT, None, ImageStatus::Unprocessed);
+    let report = import_legacy_layout_once_sync(&mut conn, &roots).unwrap().unwrap();
+    assert_eq!((report.favorites, report.markets), (1, 0));
+    assert_eq!(status_of(&mut conn, DESERT), "keepfavorite");
+    assert_eq!(status_of(&mut conn, FJORD_US), "unprocessed");
+    assert!(operations::get_config(&mut conn, legacy_import::LEGACY_IMPORT_DONE_KEY).unwrap().is_some());
+}
+
+#[test]
+fn test_unmatched_files_given_up_after_archive_import() {
+    let (mut conn, dir) = setup_test_db();
+    let roots = vec![dir.path().to_path_buf()];
+    write_file(&dir.path().join("keepfavorite/OHR.Unknown_EN-US1_1920x1080.jpg"), "jpg");
+    insert_image(&mut conn, FJORD_US, None, ImageStatus::Unprocessed);
+    operations::set_last_download_timestamp(&mut conn, "github_archive", 1700000000).unwrap();
+
+    let report = import_legacy_layout_once_sync(&mut conn, &roots).unwrap().unwrap();
+    assert_eq!(report.unmatched.len(), 1);
+    assert!(operations::get_config(&mut conn, legacy_import::LEGACY_IMPORT_DONE_KEY).unwrap().is_some());
+}
+
+#[test]
+fn test_no_legacy_files_marks_import_done() {
+    let (mut conn, dir) = setup_test_db();
+    std::fs::create_dir_all(dir.path().join("keepfavorite")).unwrap();
+
+    assert_eq!(import_legacy_layout_once_sync(&mut conn, &[dir.path().to_path_buf()]).unwrap(), None);
+    assert!(operations::get_config(&mut conn, legacy_import::LEGACY_IMPORT_DONE_KEY).unwrap().is_some());
+}
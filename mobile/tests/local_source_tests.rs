@@ -0,0 +1,194 @@
+use bingtray::db::{self, operations};
+use bingtray::viewmodel::commands;
+use bingtray::viewmodel::local_source::{
+    self, file_url, LocalDirectorySource, LOCAL_DIRECTORY_PROVIDER, LOCAL_MARKET_CODE,
+};
+use bingtray::viewmodel::sources::{
+    FetchRequest, ImageProvider, ProviderHealth, SourceSettings, BING_API_PROVIDER,
+    GITHUB_ARCHIVE_PROVIDER,
+};
+use diesel::prelude::*;
+use tempfile::TempDir;
+
+fn setup_test_db() -> (SqliteConnection, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let conn = db::establish_connection(&db_path).unwrap();
+    (conn, temp_dir)
+}
+
+fn local_settings(dir: &std::path::Path) -> SourceSettings {
+    SourceSettings {
+        local_dirs: vec![dir.to_string_lossy().to_string()],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_scan_finds_images_recursively() {
+    let photos = TempDir::new().unwrap();
+    std::fs::create_dir(photos.path().join("trip")).unwrap();
+    std::fs::write(photos.path().join("lake_sunset.jpg"), b"jpg").unwrap();
+    std::fs::write(photos.path().join("trip").join("Mountain.PNG"), b"png").unwrap();
+    std::fs::write(photos.path().join("notes.txt"), b"text").unwrap();
+    std::fs::write(photos.path().join(".hidden.jpg"), b"jpg").unwrap();
+
+    let source = LocalDirectorySource::new(vec![photos.path().to_path_buf()]);
+    assert_eq!(source.scan().len(), 2);
+    assert_eq!(source.health(), ProviderHealth::Healthy);
+
+    let images = source.fetch(&FetchRequest { market_page: None, count: 8 }).unwrap();
+    let lake = images.iter().find(|i| i.url.ends_with("lake_sunset.jpg")).unwrap();
+    assert_eq!(lake.title, "lake sunset");
+    assert_eq!(lake.market_code.as_deref(), Some(LOCAL_MARKET_CODE));
+}
+
+#[test]
+fn test_missing_directory_is_unavailable() {
+    let source = LocalDirectorySource::new(vec!["/nonexistent/bingtray/photos".into()]);
+    assert!(matches!(source.health(), ProviderHealth::Unavailable(_)));
+    assert!(source.scan().is_empty());
+}
+
+#[test]
+fn test_sync_adds_and_removes_local_images() {
+    let (mut conn, _dir) = setup_test_db();
+    let photos = TempDir::new().unwrap();
+    let first = photos.path().join("first.jpg");
+    let second = photos.path().join("second.jpeg");
+    std::fs::write(&first, b"jpg").unwrap();
+    std::fs::write(&second, b"jpg").unwrap();
+
+    let settings = local_settings(photos.path());
+    let result = local_source::sync_local_images_sync(&mut conn, &settings).unwrap();
+    assert_eq!((result.added, result.removed), (2, 0));
+
+    let image = operations::get_image(&mut conn, &file_url(&first)).unwrap().unwrap();
+    assert_eq!(image.status, "unprocessed");
+    assert!(image.cached_at.is_some());
+
+    // Unchanged directory is a no-op
+    let result = local_source::sync_local_images_sync(&mut conn, &settings).unwrap();
+    assert_eq!((result.added, result.removed), (0, 0));
+
+    std::fs::remove_file(&second).unwrap();
+    let result = local_source::sync_local_images_sync(&mut conn, &settings).unwrap();
+    assert_eq!((result.added, result.removed), (0, 1));
+    assert!(operations::get_image(&mut conn, &file_url(&second)).unwrap().is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sync_keeps_rows_of_unreadable_directory() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (mut conn, _dir) = setup_test_db();
+    let photos = TempDir::new().unwrap();
+    let trip = photos.path().join("trip");
+    std::fs::create_dir(&trip).unwrap();
+    std::fs::write(photos.path().join("first.jpg"), b"jpg").unwrap();
+    std::fs::write(trip.join("second.jpg"), b"jpg").unwrap();
+
+    let settings = local_settings(photos.path());
+    let result = local_source::sync_local_images_sync(&mut conn, &settings).unwrap();
+    assert_eq!((result.added, result.removed), (2, 0));
+
+    std::fs::set_permissions(&trip, std::fs::Permissions::from_mode(0o000)).unwrap();
+    let listable = std::fs::read_dir(&trip).is_ok(); // e.g. running as root
+    let result = local_source::sync_local_images_sync(&mut conn, &settings).unwrap();
+    std::fs::set_permissions(&trip, std::fs::Permissions::from_mode(0o755)).unwrap();
+    if !listable {
+        assert_eq!((result.added, result.removed), (0, 0));
+    }
+
+    // A root that is gone (e.g. an unmounted drive) keeps its rows too
+    let unmounted = photos.path().with_extension("unmounted");
+    std::fs::rename(photos.path(), &unmounted).unwrap();
+    let result = local_source::sync_local_images_sync(&mut conn, &settings).unwrap();
+    std::fs::rename(&unmounted, photos.path()).unwrap();
+    assert_eq!((result.added, result.removed), (0, 0));
+}
+
+#[test]
+fn test_file_urls_are_percent_encoded() {
+    let path = std::env::temp_dir().join("my photos").join("#1.jpg");
+    let url = file_url(&path);
+    assert!(url.starts_with("file://"));
+    assert!(!url.contains(' ') && !url.contains('#'));
+    assert_eq!(local_source::local_path_from_url(&url), Some(path.clone()));
+
+    // Rows stored before URLs were encoded
+    let legacy = format!("file://{}", path.display());
+    assert_eq!(local_source::local_path_from_url(&legacy), Some(path));
+}
+
+#[test]
+fn test_sync_skips_disabled_provider() {
+    let (mut conn, _dir) = setup_test_db();
+    let photos = TempDir::new().unwrap();
+    std::fs::write(photos.path().join("first.jpg"), b"jpg").unwrap();
+
+    let mut settings = local_settings(photos.path());
+    settings.disabled = vec![LOCAL_DIRECTORY_PROVIDER.to_string()];
+
+    let result = local_source::sync_local_images_sync(&mut conn, &settings).unwrap();
+    assert_eq!(result, Default::default());
+    assert!(operations::get_urls_by_market_code(&mut conn, LOCAL_MARKET_CODE).unwrap().is_empty());
+}
+
+#[test]
+fn test_local_images_read_in_place() {
+    let (mut conn, _dir) = setup_test_db();
+    let photos = TempDir::new().unwrap();
+    let path = photos.path().join("first.jpg");
+    std::fs::write(&path, b"jpg bytes").unwrap();
+
+    let url = file_url(&path);
+    assert_eq!(commands::load_cached_image(&mut conn, &url).unwrap(), Some(b"jpg bytes".to_vec()));
+    assert_eq!(commands::download_image(&url).unwrap(), b"jpg bytes".to_vec());
+}
+
+#[test]
+fn test_configured_dirs_register_local_provider() {
+    let (mut conn, _dir) = setup_test_db();
+    let photos = TempDir::new().unwrap();
+
+    commands::save_source_settings_sync(&mut conn, &local_settings(photos.path())).unwrap();
+    let source = commands::image_source_from_settings_sync(&mut conn).unwrap();
+    assert!(source
+        .enabled_providers()
+        .any(|p| p.name() == LOCAL_DIRECTORY_PROVIDER));
+}
+
+#[test]
+fn test_download_images_reports_progress() {
+    let (mut conn, _dir) = setup_test_db();
+    let photos = TempDir::new().unwrap();
+    std::fs::write(photos.path().join("first.jpg"), b"jpg").unwrap();
+    std::fs::write(photos.path().join("second.jpg"), b"jpg").unwrap();
+
+    // Local directories only, so the test stays offline
+    let mut settings = local_settings(photos.path());
+    settings.disabled = vec![BING_API_PROVIDER.to_string(), GITHUB_ARCHIVE_PROVIDER.to_string()];
+    commands::save_source_settings_sync(&mut conn, &settings).unwrap();
+
+    let mut progress = Vec::new();
+    let count = commands::download_images_with_progress_sync(&mut conn, "en-US", |current, total| {
+        progress.push((current, total));
+    })
+    .unwrap();
+
+    assert_eq!(count, 2);
+    assert_eq!(progress, vec![(1, 2), (2, 2)]);
+
+    let urls = operations::get_urls_by_market_code(&mut conn, LOCAL_MARKET_CODE).unwrap();
+    assert_eq!(urls.len(), 2);
+    for url in urls {
+        let image = operations::get_image(&mut conn, &url).unwrap().unwrap();
+        assert_eq!(image.status, "unprocessed");
+        assert!(image.cached_at.is_some());
+    }
+
+    // Nothing new on the second run
+    assert_eq!(commands::download_images_sync(&mut conn, "en-US").unwrap(), 0);
+}
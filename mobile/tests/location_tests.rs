@@ -0,0 +1,128 @@
+use bingtray::db::{self, models::*, operations, ImageStatus};
+use bingtray::location::{self, parse_location, Location};
+use bingtray::viewmodel::countries;
+use diesel::prelude::*;
+use tempfile::TempDir;
+
+fn setup_test_db() -> (SqliteConnection, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let conn = db::establish_connection(&db_path).unwrap();
+    (conn, temp_dir)
+}
+
+fn insert_image(conn: &mut SqliteConnection, url: &str, title: &str, copyright: Option<&str>) -> BingImage {
+    let image = NewBingImage {
+        url,
+        title,
+        copyright,
+        market_code: "en-US",
+        status: ImageStatus::KeepFavorite.as_str(),
+        ..Default::default()
+    };
+    operations::upsert_image(conn, &image).unwrap()
+}
+
+fn location(place: &str, country: Option<&'static str>) -> Location {
+    Location { place: place.to_string(), country }
+}
+
+#[test]
+fn test_parse_bing_copyrights() {
+    let credit = Some("Hnausapollur, Iceland (© Marco Bottigelli/Getty Images)");
+    assert_eq!(parse_location("Info", credit), location("Hnausapollur", Some("IS")));
+    // GitHub archive rows keep the place in the title
+    assert_eq!(
+        parse_location("Hnausapollur, Iceland", Some("© Marco Bottigelli/Getty Images")),
+        location("Hnausapollur", Some("IS"))
+    );
+    assert_eq!(
+        parse_location("", Some("Lofoten, Nordland, Norway (© Someone)")),
+        location("Lofoten, Nordland", Some("NO"))
+    );
+    assert_eq!(parse_location("", Some("Iceland (© Someone)")), location("Iceland", Some("IS")));
+    assert_eq!(parse_location("", Some("Somewhere (© Someone)")), location("Somewhere", None));
+    assert_eq!(parse_location("photo.jpg", None), Location::default());
+}
+
+#[test]
+fn test_parse_localized_markets_and_regions() {
+    assert_eq!(parse_location("", Some("Hnausapollur, Island (© X)")), location("Hnausapollur", Some("IS")));
+    assert_eq!(parse_location("", Some("Hnausapollur, Islande (© X)")), location("Hnausapollur", Some("IS")));
+    assert_eq!(parse_location("", Some("フナウサポットル、アイスランド (© X)")).country, Some("IS"));
+    // Chinese copyrights name the country first
+    assert_eq!(parse_location("", Some("冰岛，Hnausapollur (© X)")), location("Hnausapollur", Some("IS")));
+    // Regions imply their country and stay part of the place
+    assert_eq!(
+        parse_location("", Some("Arches National Park, Utah (© X)")),
+        location("Arches National Park, Utah", Some("US"))
+    );
+    assert_eq!(location::country("is").unwrap().name, "Iceland");
+}
+
+#[test]
+fn test_location_is_stored_on_upsert() {
+    let (mut conn, _dir) = setup_test_db();
+
+    let image = insert_image(&mut conn, "https://a", "Info", Some("Hnausapollur, Iceland (© X)"));
+    assert_eq!(image.place.as_deref(), Some("Hnausapollur"));
+    assert_eq!(image.country.as_deref(), Some("IS"));
+
+    // A new copyright updates the location, no copyright stores an empty place
+    let image = insert_image(&mut conn, "https://a", "Info", Some("Preikestolen, Norway (© X)"));
+    assert_eq!(image.country.as_deref(), Some("NO"));
+    let image = insert_image(&mut conn, "https://b", "photo.jpg", None);
+    assert_eq!((image.place.as_deref(), image.country), (Some(""), None));
+}
+
+#[test]
+fn test_backfill_parses_unparsed_rows() {
+    let (mut conn, _dir) = setup_test_db();
+    insert_image(&mut conn, "https://a", "Info", Some("Hnausapollur, Iceland (© X)"));
+    diesel::sql_query("UPDATE bing_images SET place = NULL, country = NULL").execute(&mut conn).unwrap();
+
+    assert_eq!(operations::backfill_locations(&mut conn).unwrap(), 1);
+    assert_eq!(operations::get_image(&mut conn, "https://a").unwrap().unwrap().country.as_deref(), Some("IS"));
+    assert_eq!(operations::backfill_locations(&mut conn).unwrap(), 0);
+}
+
+#[test]
+fn test_opening_pool_backfills_locations() {
+    let (mut conn, dir) = setup_test_db();
+    insert_image(&mut conn, "https://a", "Info", Some("Hnausapollur, Iceland (© X)"));
+    diesel::sql_query("UPDATE bing_images SET place = NULL, country = NULL").execute(&mut conn).unwrap();
+
+    // Plain connections leave the rows alone, the pool parses them once when opened
+    drop(conn);
+    let mut conn = db::establish_connection(&dir.path().join("test.db")).unwrap();
+    assert_eq!(operations::get_image(&mut conn, "https://a").unwrap().unwrap().country, None);
+
+    let pool = db::DbPool::open(&dir.path().join("test.db")).unwrap();
+    let image = operations::get_image(&mut pool.get().unwrap(), "https://a").unwrap().unwrap();
+    assert_eq!(image.country.as_deref(), Some("IS"));
+}
+
+#[test]
+fn test_country_pages_and_rotation() {
+    let (mut conn, _dir) = setup_test_db();
+    insert_image(&mut conn, "https://is1", "Info", Some("Hnausapollur, Iceland (© X)"));
+    insert_image(&mut conn, "https://is2", "Info", Some("Skógafoss, Iceland (© X)"));
+    insert_image(&mut conn, "https://no", "Info", Some("Preikestolen, Norway (© X)"));
+    insert_image(&mut conn, "https://unknown", "Info", Some("Somewhere (© X)"));
+
+    let summaries = countries::get_country_summaries_sync(&mut conn).unwrap();
+    let summary: Vec<(&str, usize)> = summaries.iter().map(|s| (s.name.as_str(), s.image_count)).collect();
+    assert_eq!(summary, [("Iceland", 2), ("Norway", 1)]);
+
+    let (images, total) = countries::get_country_page_sync(&mut conn, "IS", 0).unwrap();
+    assert_eq!((images.len(), total), (2, 2));
+
+    // Rotation keeps the favorites of the chosen countries
+    let favorites = operations::get_images_by_status(&mut conn, ImageStatus::KeepFavorite).unwrap();
+    countries::set_rotation_countries_sync(&mut conn, &["NO".to_string()]).unwrap();
+    let kept = countries::filter_rotation_countries_sync(&mut conn, favorites.clone()).unwrap();
+    assert_eq!(kept.iter().map(|image| image.url.as_str()).collect::<Vec<_>>(), ["https://no"]);
+
+    countries::set_rotation_countries_sync(&mut conn, &[]).unwrap();
+    assert_eq!(countries::filter_rotation_countries_sync(&mut conn, favorites).unwrap().len(), 4);
+}
@@ -0,0 +1,143 @@
+use bingtray::db::{self, operations};
+use bingtray::viewmodel::{commands, markets};
+use bingtray::viewmodel::sources::{extract_market_code, parse_markdown_row, urlbase_from_url};
+use diesel::prelude::*;
+use tempfile::TempDir;
+
+fn setup_test_db() -> (SqliteConnection, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let conn = db::establish_connection(&db_path).unwrap();
+    (conn, temp_dir)
+}
+
+#[test]
+fn test_market_list_seeded_with_defaults() {
+    let (mut conn, _dir) = setup_test_db();
+
+    let list = markets::get_market_list_sync(&mut conn).unwrap();
+    assert_eq!(list.len(), markets::DEFAULT_MARKET_CODES.len());
+    assert!(list.iter().all(|m| m.last_used_at == 0));
+}
+
+#[test]
+fn test_rotation_skips_recently_used_markets() {
+    let (mut conn, _dir) = setup_test_db();
+    markets::set_market_list_sync(&mut conn, &["en-US", "ja-JP"]).unwrap();
+
+    let first = markets::next_market_sync(&mut conn).unwrap().unwrap();
+    assert_eq!(first, "en-US");
+    markets::mark_market_used_sync(&mut conn, &first).unwrap();
+
+    let second = markets::next_market_sync(&mut conn).unwrap().unwrap();
+    assert_eq!(second, "ja-JP");
+    markets::mark_market_used_sync(&mut conn, &second).unwrap();
+
+    // Both visited within the last 7 days
+    assert_eq!(markets::next_market_sync(&mut conn).unwrap(), None);
+
+    // Current market is remembered in config
+    assert_eq!(operations::get_config(&mut conn, "market_code").unwrap().as_deref(), Some("ja-JP"));
+}
+
+#[test]
+fn test_set_market_list_keeps_existing_timestamps() {
+    let (mut conn, _dir) = setup_test_db();
+    markets::set_market_list_sync(&mut conn, &["en-US", "de-DE"]).unwrap();
+    markets::mark_market_used_sync(&mut conn, "en-US").unwrap();
+
+    markets::set_market_list_sync(&mut conn, &["en-US", "fr-FR"]).unwrap();
+
+    let list = markets::get_market_list_sync(&mut conn).unwrap();
+    let codes: Vec<&str> = list.iter().map(|m| m.code.as_str()).collect();
+    assert_eq!(codes, vec!["fr-FR", "en-US"]);
+    assert!(list[1].last_used_at > 0);
+}
+
+#[test]
+fn test_set_market_list_rejects_empty() {
+    let (mut conn, _dir) = setup_test_db();
+    assert!(markets::set_market_list_sync(&mut conn, &[]).is_err());
+}
+
+#[test]
+fn test_market_pages_drain_before_next_market() {
+    let (mut conn, _dir) = setup_test_db();
+    markets::set_market_list_sync(&mut conn, &["en-US", "ja-JP"]).unwrap();
+
+    let mut visited = Vec::new();
+    while let Some(page) = markets::next_market_page_sync(&mut conn).unwrap() {
+        visited.push((page.market_code.clone(), page.offset, page.idx()));
+        markets::advance_market_page_sync(&mut conn, &page).unwrap();
+    }
+
+    assert_eq!(
+        visited,
+        vec![
+            ("en-US".to_string(), 0, 0),
+            ("en-US".to_string(), 8, 7),
+            ("ja-JP".to_string(), 0, 0),
+            ("ja-JP".to_string(), 8, 7),
+        ]
+    );
+
+    // Walk progress is remembered per market and mirrored in the market state
+    let list = markets::get_market_list_sync(&mut conn).unwrap();
+    assert!(list.iter().all(|m| m.page_offset == markets::MARKET_DRAINED_OFFSET as i32));
+    let (market_code, offset) = commands::get_market_state_sync(&mut conn).unwrap();
+    assert_eq!(market_code, "ja-JP");
+    assert_eq!(offset, markets::MARKET_DRAINED_OFFSET);
+}
+
+#[test]
+fn test_market_walk_resumes_after_restart() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    {
+        let mut conn = db::establish_connection(&db_path).unwrap();
+        markets::set_market_list_sync(&mut conn, &["en-US", "ja-JP"]).unwrap();
+        let page = markets::next_market_page_sync(&mut conn).unwrap().unwrap();
+        markets::advance_market_page_sync(&mut conn, &page).unwrap();
+    }
+
+    let mut conn = db::establish_connection(&db_path).unwrap();
+    let page = markets::next_market_page_sync(&mut conn).unwrap().unwrap();
+    assert_eq!(page.market_code, "en-US");
+    assert_eq!(page.offset, markets::MARKET_PAGE_SIZE);
+}
+
+#[test]
+fn test_extract_market_code_from_bing_url() {
+    let url = "https://www.bing.com/th?id=OHR.Hnausapollur_EN-US2080493040_1920x1080.jpg";
+    assert_eq!(extract_market_code(url), Some("en-US".to_string()));
+
+    let url = "https://www.bing.com/th?id=OHR.Kiyomizu_ZH-CN0123456789_UHD.jpg";
+    assert_eq!(extract_market_code(url), Some("zh-CN".to_string()));
+
+    assert_eq!(extract_market_code("https://example.com/image.jpg"), None);
+}
+
+#[test]
+fn test_markdown_row_carries_market_code() {
+    let row = "2026-06-01 | [Hnausapollur, Iceland (© Someone)](https://cn.bing.com/th?id=OHR.Hnausapollur_ZH-CN2080493040_1920x1080.jpg)";
+    let image = parse_markdown_row(row).unwrap();
+    assert_eq!(image.market_code.as_deref(), Some("zh-CN"));
+}
+
+#[test]
+fn test_markdown_row_carries_publication_metadata() {
+    let row = "2026-06-01 | [Hnausapollur, Iceland (© Someone)](https://cn.bing.com/th?id=OHR.Hnausapollur_ZH-CN2080493040_1920x1080.jpg)";
+    let image = parse_markdown_row(row).unwrap();
+    assert_eq!(image.startdate.as_deref(), Some("20260601"));
+    assert_eq!(image.fullstartdate.as_deref(), Some("202606010000"));
+    assert_eq!(image.enddate.as_deref(), Some("20260602"));
+    assert_eq!(image.urlbase.as_deref(), Some("/th?id=OHR.Hnausapollur_ZH-CN2080493040"));
+}
+
+#[test]
+fn test_urlbase_from_url() {
+    let url = "https://www.bing.com/th?id=OHR.Kiyomizu_ZH-CN0123456789_UHD.jpg&rf=LaDigue_UHD.jpg";
+    assert_eq!(urlbase_from_url(url).as_deref(), Some("/th?id=OHR.Kiyomizu_ZH-CN0123456789"));
+    assert_eq!(urlbase_from_url("https://example.com/image.jpg"), None);
+}
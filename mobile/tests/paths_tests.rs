@@ -0,0 +1,74 @@
+use bingtray::paths::{
+    self, migrate_legacy_database, resolve_data_root, DataPaths, PORTABLE_DIR, PORTABLE_MARKER,
+};
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn test_data_root_precedence() {
+    let exe_dir = TempDir::new().unwrap();
+    let flag = Path::new("/flag/data");
+    let env = Path::new("/env/data");
+
+    assert_eq!(resolve_data_root(Some(flag), Some(env), true, Some(exe_dir.path())), Some(flag.to_path_buf()));
+    assert_eq!(resolve_data_root(None, Some(env), true, Some(exe_dir.path())), Some(env.to_path_buf()));
+    assert_eq!(
+        resolve_data_root(None, Some(Path::new("")), true, Some(exe_dir.path())),
+        Some(exe_dir.path().join(PORTABLE_DIR))
+    );
+    assert_eq!(resolve_data_root(None, None, false, Some(exe_dir.path())), None);
+
+    // A marker file next to the executable turns on portable mode
+    std::fs::write(exe_dir.path().join(PORTABLE_MARKER), b"").unwrap();
+    assert_eq!(
+        resolve_data_root(None, None, false, Some(exe_dir.path())),
+        Some(exe_dir.path().join(PORTABLE_DIR))
+    );
+}
+
+#[test]
+fn test_data_root_layout() {
+    let paths = DataPaths::in_root(Path::new("/data/bingtray"));
+    assert_eq!(paths.db_path(), Path::new("/data/bingtray/bingtray.db"));
+    assert_eq!(paths.image_cache_dir(), Path::new("/data/bingtray/cache/images"));
+}
+
+#[test]
+fn test_migrates_newest_legacy_database() {
+    let dir = TempDir::new().unwrap();
+    let old = dir.path().join("old").join("bingtray.db");
+    let newer = dir.path().join("newer").join("bingtray.db");
+    std::fs::create_dir_all(old.parent().unwrap()).unwrap();
+    std::fs::create_dir_all(newer.parent().unwrap()).unwrap();
+
+    std::fs::write(&old, b"old").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    std::fs::write(&newer, b"newer").unwrap();
+    std::fs::write(dir.path().join("newer").join("bingtray.db-wal"), b"wal").unwrap();
+
+    let target = dir.path().join("target").join("bingtray.db");
+    let candidates = vec![old.clone(), newer.clone(), dir.path().join("missing.db")];
+
+    let migrated = migrate_legacy_database(&target, &candidates).unwrap();
+    assert_eq!(migrated, Some(newer.clone()));
+    assert_eq!(std::fs::read(&target).unwrap(), b"newer");
+    assert_eq!(std::fs::read(dir.path().join("target").join("bingtray.db-wal")).unwrap(), b"wal");
+    assert!(newer.exists(), "old database is left in place");
+
+    // An existing database is never overwritten
+    assert_eq!(migrate_legacy_database(&target, &candidates).unwrap(), None);
+}
+
+#[test]
+fn test_other_databases_keep_their_own_cache() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    assert_eq!(paths::image_cache_dir_for(&db_path).unwrap(), dir.path().join("cache").join("images"));
+}
+
+#[test]
+fn test_other_databases_look_for_legacy_files_next_to_them() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("test.db");
+    assert_eq!(paths::legacy_layout_roots_for(&db_path).unwrap(), vec![dir.path().to_path_buf()]);
+}
@@ -0,0 +1,123 @@
+use bingtray::db::{self, models::NewBingImage, operations, ImageStatus};
+use bingtray::viewmodel::cache_manager::{get_cache_filename, CacheManager};
+use bingtray::viewmodel::resolution::{self, ImageResolution, ResolutionSetting};
+use diesel::prelude::*;
+use tempfile::TempDir;
+
+fn setup_test_db() -> (SqliteConnection, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let conn = db::establish_connection(&db_path).unwrap();
+    (conn, temp_dir)
+}
+
+#[test]
+fn test_resolution_for_screen() {
+    assert_eq!(ImageResolution::for_screen(1080, 2400), ImageResolution::Portrait1080);
+    assert_eq!(ImageResolution::for_screen(1920, 1080), ImageResolution::Landscape1080);
+    assert_eq!(ImageResolution::for_screen(1366, 768), ImageResolution::Landscape1080);
+    assert_eq!(ImageResolution::for_screen(3840, 2160), ImageResolution::Uhd);
+    assert_eq!(ImageResolution::for_screen(2560, 1440), ImageResolution::Uhd);
+}
+
+#[test]
+fn test_variant_url_from_urlbase() {
+    let url = "https://www.bing.com/th?id=OHR.Hnausapollur_EN-US2080493040_1920x1080.jpg&rf=LaDigue_1920x1080.jpg";
+
+    assert_eq!(
+        resolution::variant_url(url, Some("/th?id=OHR.Hnausapollur_EN-US2080493040"), ImageResolution::Uhd),
+        "https://www.bing.com/th?id=OHR.Hnausapollur_EN-US2080493040_UHD.jpg"
+    );
+
+    // Older rows without urlbase derive it from the URL
+    assert_eq!(
+        resolution::variant_url(url, None, ImageResolution::Portrait1080),
+        "https://www.bing.com/th?id=OHR.Hnausapollur_EN-US2080493040_1080x1920.jpg"
+    );
+
+    // Non-Bing URLs are left alone
+    let other = "https://example.com/photo.jpg";
+    assert_eq!(resolution::variant_url(other, None, ImageResolution::Uhd), other);
+}
+
+#[test]
+fn test_cache_filename_includes_resolution() {
+    let uhd = "https://www.bing.com/th?id=OHR.Hnausapollur_EN-US2080493040_UHD.jpg";
+    let fhd = "https://www.bing.com/th?id=OHR.Hnausapollur_EN-US2080493040_1920x1080.jpg";
+
+    assert_eq!(get_cache_filename(uhd), "OHR_Hnausapollur_UHD.jpg");
+    assert_eq!(get_cache_filename(fhd), "OHR_Hnausapollur_1920x1080.jpg");
+    assert_eq!(resolution::resolution_suffix(fhd), Some("1920x1080"));
+}
+
+#[test]
+fn test_cache_filename_of_other_providers_is_per_url() {
+    let first = "https://images.example.com/daily/a.jpg";
+    let second = "https://images.example.com/daily/b.jpg?size=large";
+
+    assert_ne!(get_cache_filename(first), get_cache_filename(second));
+    assert_eq!(get_cache_filename(first), get_cache_filename(first));
+    assert!(get_cache_filename(second).ends_with(".jpg"));
+    assert!(!get_cache_filename(second).contains(['/', '?']));
+}
+
+#[test]
+fn test_forced_resolution_overrides_screen() {
+    let (mut conn, _dir) = setup_test_db();
+
+    assert_eq!(resolution::get_resolution_setting_sync(&mut conn).unwrap(), ResolutionSetting::Auto);
+
+    let forced = ResolutionSetting::Fixed(ImageResolution::Portrait1080);
+    resolution::set_resolution_setting_sync(&mut conn, forced).unwrap();
+
+    assert_eq!(resolution::get_resolution_setting_sync(&mut conn).unwrap(), forced);
+    assert_eq!(resolution::preferred_resolution_sync(&mut conn).unwrap(), ImageResolution::Portrait1080);
+}
+
+#[test]
+fn test_resolution_setting_round_trip() {
+    for setting in [
+        ResolutionSetting::Auto,
+        ResolutionSetting::Fixed(ImageResolution::Uhd),
+        ResolutionSetting::Fixed(ImageResolution::Landscape1080),
+        ResolutionSetting::Fixed(ImageResolution::Portrait1080),
+    ] {
+        assert_eq!(ResolutionSetting::from_str(setting.as_str()), Some(setting));
+    }
+    assert_eq!(ResolutionSetting::from_str("640x480"), None);
+}
+
+#[test]
+fn test_missing_cache_file_is_skipped_and_uncached() {
+    let temp_dir = TempDir::new().unwrap();
+    let pool = db::DbPool::shared(&temp_dir.path().join("test.db")).unwrap();
+    let cache_dir = temp_dir.path().join("cache");
+    let manager = CacheManager::new(cache_dir.clone(), pool.clone(), None);
+
+    let newer = "https://www.bing.com/th?id=OHR.Newer_EN-US1_UHD.jpg";
+    let older = "https://www.bing.com/th?id=OHR.Older_EN-US2_UHD.jpg";
+    {
+        let mut conn = pool.get().unwrap();
+        resolution::set_resolution_setting_sync(&mut conn, ResolutionSetting::Fixed(ImageResolution::Uhd)).unwrap();
+        for (url, date) in [(newer, "202606020000"), (older, "202606010000")] {
+            operations::upsert_image(&mut conn, &NewBingImage {
+                url,
+                title: "Test Image",
+                market_code: "en-US",
+                status: ImageStatus::Unprocessed.as_str(),
+                fullstartdate: Some(date),
+                ..Default::default()
+            })
+            .unwrap();
+            operations::mark_image_cached(&mut conn, url, 1).unwrap();
+        }
+    }
+    // Only the older image is still on disk
+    std::fs::write(cache_dir.join(get_cache_filename(older)), b"jpg").unwrap();
+
+    let next = manager.get_next_cached_image().unwrap().unwrap();
+    assert_eq!(next.url, older);
+
+    let mut conn = pool.get().unwrap();
+    assert_eq!(operations::get_image(&mut conn, newer).unwrap().unwrap().cached_at, None);
+}
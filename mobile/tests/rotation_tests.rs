@@ -0,0 +1,115 @@
+use bingtray::db::{self, models::*, operations, ImageStatus};
+use bingtray::viewmodel::rotation::{pick_weighted, rotation_weight, RECENCY_WINDOW_SECS};
+use diesel::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use tempfile::TempDir;
+
+fn setup_test_db() -> (SqliteConnection, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let conn = db::establish_connection(&db_path).unwrap();
+    (conn, temp_dir)
+}
+
+fn favorite(conn: &mut SqliteConnection, url: &str, rating: i32) -> BingImage {
+    let image = NewBingImage {
+        url,
+        title: "Test Image",
+        market_code: "en-US",
+        status: ImageStatus::KeepFavorite.as_str(),
+        ..Default::default()
+    };
+    operations::upsert_image(conn, &image).unwrap();
+    assert!(operations::set_image_rating(conn, url, rating).unwrap());
+    operations::get_image(conn, url).unwrap().unwrap()
+}
+
+#[test]
+fn test_rating_is_stored_and_validated() {
+    let (mut conn, _dir) = setup_test_db();
+    let image = favorite(&mut conn, "https://a", 4);
+    assert_eq!(image.rating, 4);
+
+    assert!(operations::set_image_rating(&mut conn, "https://a", 6).is_err());
+    assert!(operations::set_image_rating(&mut conn, "https://a", -1).is_err());
+    assert!(!operations::set_image_rating(&mut conn, "https://missing", 3).unwrap());
+
+    // Seeing the image again keeps its rating
+    operations::upsert_image(&mut conn, &NewBingImage {
+        url: "https://a",
+        title: "Renamed",
+        market_code: "en-US",
+        status: ImageStatus::Unprocessed.as_str(),
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(operations::get_image(&mut conn, "https://a").unwrap().unwrap().rating, 4);
+    assert_eq!(rating_stars(2), "★★☆☆☆");
+}
+
+#[test]
+fn test_rotation_weight() {
+    // Rating counts linearly, never shown counts as rested
+    assert_eq!(rotation_weight(0, None), 1.0);
+    assert_eq!(rotation_weight(5, None), 6.0);
+    assert_eq!(rotation_weight(5, Some(RECENCY_WINDOW_SECS * 2)), 6.0);
+    assert_eq!(rotation_weight(1, Some(RECENCY_WINDOW_SECS / 2)), 1.0);
+    // Just shown images keep a small chance
+    assert!(rotation_weight(5, Some(0)) > 0.0);
+    assert!(rotation_weight(5, Some(0)) < rotation_weight(0, None));
+}
+
+#[test]
+fn test_pick_prefers_rated_and_rested_images() {
+    let (mut conn, _dir) = setup_test_db();
+    let candidates = vec![
+        favorite(&mut conn, "https://unrated", 0),
+        favorite(&mut conn, "https://five", 5),
+        favorite(&mut conn, "https://current", 5),
+    ];
+    let now = 1_000_000;
+    let last_shown: HashMap<String, i32> = [("https://current".to_string(), now - 60)].into();
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for _ in 0..600 {
+        let image = pick_weighted(&candidates, &last_shown, Some("https://current"), now, &mut rng).unwrap();
+        *counts.entry(image.url.clone()).or_default() += 1;
+    }
+
+    // The current wallpaper is never repeated back-to-back
+    assert_eq!(counts.get("https://current"), None);
+    assert!(counts["https://five"] > counts["https://unrated"] * 3);
+}
+
+#[test]
+fn test_pick_single_candidate() {
+    let (mut conn, _dir) = setup_test_db();
+    let candidates = vec![favorite(&mut conn, "https://only", 0)];
+    let mut rng = StdRng::seed_from_u64(1);
+
+    let image = pick_weighted(&candidates, &HashMap::new(), Some("https://only"), 0, &mut rng).unwrap();
+    assert_eq!(image.url, "https://only");
+    assert!(pick_weighted(&[], &HashMap::new(), None, 0, &mut rng).is_none());
+}
+
+#[test]
+fn test_last_shown_times() {
+    let (mut conn, _dir) = setup_test_db();
+    for (url, set_at) in [("https://a", 100), ("https://b", 200), ("https://a", 300)] {
+        operations::insert_history_entry(&mut conn, &NewWallpaperHistoryEntry {
+            url,
+            set_at,
+            source: "cli",
+            monitor: None,
+            crop_coords: None,
+        })
+        .unwrap();
+    }
+
+    let times = operations::get_last_shown_times(&mut conn).unwrap();
+    assert_eq!(times["https://a"], 300);
+    assert_eq!(times["https://b"], 200);
+}
@@ -0,0 +1,142 @@
+use bingtray::db::{self, models::*, operations, ImageStatus};
+use bingtray::viewmodel::rotation;
+use bingtray::viewmodel::scheduler::{self, RotationPool, RotationSchedule};
+use chrono::{DateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use diesel::prelude::*;
+use tempfile::TempDir;
+
+fn setup_test_db() -> (SqliteConnection, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let conn = db::establish_connection(&db_path).unwrap();
+    (conn, temp_dir)
+}
+
+fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 3, 10, hour, minute, 0).unwrap()
+}
+
+fn time(s: &str) -> NaiveTime {
+    NaiveTime::parse_from_str(s, "%H:%M").unwrap()
+}
+
+#[test]
+fn test_interval_schedule() {
+    let schedule = RotationSchedule { interval_minutes: 90, ..Default::default() };
+    assert_eq!(scheduler::next_rotation(&schedule, &at(8, 0)), Some(at(9, 30)));
+    assert!(!scheduler::is_due(&schedule, &at(8, 0), &at(9, 29)));
+    assert!(scheduler::is_due(&schedule, &at(8, 0), &at(9, 30)));
+}
+
+#[test]
+fn test_times_of_day() {
+    let schedule = RotationSchedule {
+        times: scheduler::parse_times("18:30, 07:00").unwrap(),
+        ..Default::default()
+    };
+    assert_eq!(schedule.times, [time("07:00"), time("18:30")]);
+    assert_eq!(scheduler::next_rotation(&schedule, &at(8, 0)), Some(at(18, 30)));
+    // After the last time of the day, the first one of tomorrow
+    assert_eq!(scheduler::next_rotation(&schedule, &at(18, 30)), Some(at(7, 0) + TimeDelta::days(1)));
+
+    // The earlier of interval and time of day wins
+    let schedule = RotationSchedule { interval_minutes: 60, ..schedule };
+    assert_eq!(scheduler::next_rotation(&schedule, &at(18, 0)), Some(at(18, 30)));
+
+    assert!(scheduler::parse_times("25:00").is_err());
+    assert_eq!(scheduler::format_times(&schedule.times), "07:00, 18:30");
+}
+
+#[test]
+fn test_times_follow_the_local_timezone() {
+    let seoul = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+    let schedule = RotationSchedule { times: vec![time("08:00")], ..Default::default() };
+    let last_run = seoul.with_ymd_and_hms(2026, 3, 10, 7, 0, 0).unwrap();
+    let next = scheduler::next_rotation(&schedule, &last_run).unwrap();
+    assert_eq!(next.with_timezone(&Utc), at(23, 0) - TimeDelta::days(1));
+}
+
+#[test]
+fn test_login_only_schedule_is_never_due() {
+    let schedule = RotationSchedule { on_login: true, ..Default::default() };
+    assert!(schedule.is_enabled());
+    assert_eq!(scheduler::next_rotation(&schedule, &at(8, 0)), None);
+    assert!(!RotationSchedule::default().is_enabled());
+}
+
+#[test]
+fn test_schedule_is_stored_in_config() {
+    let (mut conn, _dir) = setup_test_db();
+    assert_eq!(scheduler::get_schedule_sync(&mut conn).unwrap(), RotationSchedule::default());
+
+    let schedule = RotationSchedule {
+        interval_minutes: 120,
+        times: vec![time("09:15")],
+        on_login: true,
+        pool: RotationPool::Collection(3),
+    };
+    scheduler::set_schedule_sync(&mut conn, &schedule).unwrap();
+    assert_eq!(scheduler::get_schedule_sync(&mut conn).unwrap(), schedule);
+
+    assert_eq!(RotationPool::from_config("favorites"), Some(RotationPool::Favorites));
+    assert_eq!(RotationPool::from_config("collection:x"), None);
+}
+
+#[test]
+fn test_catch_up_after_suspend_runs_once() {
+    let (mut conn, _dir) = setup_test_db();
+    let schedule = RotationSchedule {
+        interval_minutes: 30,
+        times: vec![time("12:00")],
+        ..Default::default()
+    };
+    scheduler::set_schedule_sync(&mut conn, &schedule).unwrap();
+    scheduler::record_run_sync(&mut conn, &at(8, 0)).unwrap();
+
+    assert!(!scheduler::take_due_sync(&mut conn, &at(8, 20)).unwrap());
+
+    // Woken up hours later: one rotation for all missed slots
+    assert!(scheduler::take_due_sync(&mut conn, &at(14, 5)).unwrap());
+    assert!(!scheduler::take_due_sync(&mut conn, &at(14, 6)).unwrap());
+    assert!(scheduler::take_due_sync(&mut conn, &at(14, 35)).unwrap());
+}
+
+#[test]
+fn test_first_check_and_clock_changes_only_start_counting() {
+    let (mut conn, _dir) = setup_test_db();
+    let schedule = RotationSchedule { interval_minutes: 10, ..Default::default() };
+    scheduler::set_schedule_sync(&mut conn, &schedule).unwrap();
+
+    // Set back an hour: the next rotation counts from the new time
+    scheduler::record_run_sync(&mut conn, &at(9, 0)).unwrap();
+    assert!(!scheduler::take_due_sync(&mut conn, &at(8, 0)).unwrap());
+    assert_eq!(scheduler::get_last_run_sync(&mut conn, &Utc).unwrap(), Some(at(8, 0)));
+    assert!(scheduler::take_due_sync(&mut conn, &at(8, 10)).unwrap());
+
+    // Saving an unchanged schedule keeps the last run
+    scheduler::set_schedule_sync(&mut conn, &schedule).unwrap();
+    assert_eq!(scheduler::get_last_run_sync(&mut conn, &Utc).unwrap(), Some(at(8, 10)));
+}
+
+#[test]
+fn test_collection_pool_picks_its_images() {
+    let (mut conn, _dir) = setup_test_db();
+    for url in ["https://in", "https://out"] {
+        operations::upsert_image(&mut conn, &NewBingImage {
+            url,
+            title: "Test Image",
+            market_code: "en-US",
+            status: ImageStatus::KeepFavorite.as_str(),
+            ..Default::default()
+        })
+        .unwrap();
+    }
+    let collection = operations::create_collection(&mut conn, "Autumn").unwrap();
+    operations::add_image_to_collection(&mut conn, collection.id, "https://in").unwrap();
+
+    for _ in 0..10 {
+        let image = rotation::pick_collection_image_sync(&mut conn, collection.id).unwrap().unwrap();
+        assert_eq!(image.url, "https://in");
+    }
+    assert!(rotation::pick_collection_image_sync(&mut conn, collection.id + 1).unwrap().is_none());
+}
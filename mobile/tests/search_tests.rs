@@ -0,0 +1,95 @@
+use bingtray::db::{self, models::*, operations, ImageStatus};
+use bingtray::viewmodel::search::{search_page_sync, SEARCH_PAGE_SIZE};
+use diesel::prelude::*;
+use tempfile::TempDir;
+
+fn setup_test_db() -> (SqliteConnection, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let conn = db::establish_connection(&db_path).unwrap();
+    (conn, temp_dir)
+}
+
+fn insert_image(conn: &mut SqliteConnection, url: &str, title: &str, copyright: Option<&str>) {
+    let image = NewBingImage {
+        url,
+        title,
+        copyright,
+        market_code: "en-US",
+        status: ImageStatus::Unprocessed.as_str(),
+        ..Default::default()
+    };
+    operations::upsert_image(conn, &image).unwrap();
+}
+
+fn search_urls(conn: &mut SqliteConnection, query: &str, status: Option<ImageStatus>) -> Vec<String> {
+    operations::search_images(conn, query, status, 20, 0)
+        .unwrap()
+        .into_iter()
+        .map(|image| image.url)
+        .collect()
+}
+
+#[test]
+fn test_search_match_query() {
+    assert_eq!(operations::search_match_query("ice  fjord").as_deref(), Some("\"ice\"* \"fjord\"*"));
+    assert_eq!(operations::search_match_query("say \"hi\" OR").as_deref(), Some("\"say\"* \"hi\"* \"OR\"*"));
+    assert_eq!(operations::search_match_query("  \"\" "), None);
+}
+
+#[test]
+fn test_search_ranks_titles_first() {
+    let (mut conn, _dir) = setup_test_db();
+    insert_image(&mut conn, "https://a", "Waterfall", Some("Hnausapollur, Iceland (© Marco Bottigelli)"));
+    insert_image(&mut conn, "https://b", "Iceland ponies", Some("Horses (© Someone)"));
+    insert_image(&mut conn, "https://c", "Lights", Some("Reykjavík at night (© Photographer in Iceland)"));
+    insert_image(&mut conn, "https://d", "Desert", None);
+
+    assert_eq!(search_urls(&mut conn, "iceland", None), ["https://b", "https://a", "https://c"]);
+    // Prefixes and diacritics
+    assert_eq!(search_urls(&mut conn, "reykja", None), ["https://c"]);
+    assert_eq!(search_urls(&mut conn, "ice bottig", None), ["https://a"]);
+    assert!(search_urls(&mut conn, "", None).is_empty());
+}
+
+#[test]
+fn test_search_filters_by_status() {
+    let (mut conn, _dir) = setup_test_db();
+    insert_image(&mut conn, "https://a", "Fjord in spring", None);
+    insert_image(&mut conn, "https://b", "Fjord in winter", None);
+    operations::update_image_status(&mut conn, "https://b", ImageStatus::KeepFavorite).unwrap();
+
+    assert_eq!(search_urls(&mut conn, "fjord", Some(ImageStatus::KeepFavorite)), ["https://b"]);
+    assert_eq!(operations::count_search_results(&mut conn, "fjord", None).unwrap(), 2);
+}
+
+#[test]
+fn test_index_follows_updates_and_deletes() {
+    let (mut conn, _dir) = setup_test_db();
+    insert_image(&mut conn, "https://a", "Fjord", None);
+
+    // A source seeing the image again with a new title updates the index
+    insert_image(&mut conn, "https://a", "Glacier", Some("Svalbard, Norway (© Someone)"));
+    assert!(search_urls(&mut conn, "fjord", None).is_empty());
+    assert_eq!(search_urls(&mut conn, "svalbard", None), ["https://a"]);
+
+    operations::delete_image(&mut conn, "https://a").unwrap();
+    assert!(search_urls(&mut conn, "glacier", None).is_empty());
+}
+
+#[test]
+fn test_search_pages() {
+    let (mut conn, _dir) = setup_test_db();
+    for i in 0..SEARCH_PAGE_SIZE + 3 {
+        insert_image(&mut conn, &format!("https://img{}", i), &format!("Mountain {}", i), None);
+    }
+
+    let first = search_page_sync(&mut conn, "mountain", None, 0).unwrap();
+    assert_eq!(first.images.len(), SEARCH_PAGE_SIZE);
+    assert_eq!(first.total_count, SEARCH_PAGE_SIZE + 3);
+    assert_eq!(first.page_count(), 2);
+
+    let second = search_page_sync(&mut conn, "mountain", None, 1).unwrap();
+    assert_eq!(second.images.len(), 3);
+    assert!(second.images.iter().all(|image| !first.images.iter().any(|other| other.url == image.url)));
+}
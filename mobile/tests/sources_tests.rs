@@ -34,3 +34,266 @@ fn test_extract_identifier_no_match() {
     let id = extract_identifier(url);
     assert_eq!(id, None);
 }
+
+mod registry {
+    use anyhow::Result;
+    use bingtray::db;
+    use bingtray::viewmodel::commands;
+    use bingtray::viewmodel::sources::{
+        FetchRequest, ImageProvider, ImageSource, ProviderHealth, SourceSettings,
+        BING_API_PROVIDER, GITHUB_ARCHIVE_PROVIDER,
+    };
+    use bingtray::BingImage;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    struct FakeProvider {
+        name: &'static str,
+        priority: i32,
+        images: Vec<BingImage>,
+        fail: bool,
+    }
+
+    impl ImageProvider for FakeProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn priority(&self) -> i32 {
+            self.priority
+        }
+
+        fn fetch(&self, _request: &FetchRequest) -> Result<Vec<BingImage>> {
+            if self.fail {
+                anyhow::bail!("{} is down", self.name);
+            }
+            Ok(self.images.clone())
+        }
+    }
+
+    /// Never has anything due, like Bing with every market visited this week
+    struct IdleProvider;
+
+    impl ImageProvider for IdleProvider {
+        fn name(&self) -> &str {
+            "idle"
+        }
+
+        fn priority(&self) -> i32 {
+            5
+        }
+
+        fn fetch(&self, _request: &FetchRequest) -> Result<Vec<BingImage>> {
+            panic!("a provider that is not due must not be fetched");
+        }
+
+        fn is_due(&self, _request: &FetchRequest) -> bool {
+            false
+        }
+    }
+
+    fn image(url: &str, title: &str) -> BingImage {
+        BingImage {
+            url: url.to_string(),
+            title: title.to_string(),
+            copyright: None,
+            copyright_link: None,
+            market_code: None,
+            ..Default::default()
+        }
+    }
+
+    fn fake(name: &'static str, priority: i32, images: Vec<BingImage>) -> Arc<FakeProvider> {
+        Arc::new(FakeProvider { name, priority, images, fail: false })
+    }
+
+    fn names(source: &ImageSource) -> Vec<String> {
+        source.enabled_providers().map(|p| p.name().to_string()).collect()
+    }
+
+    #[test]
+    fn test_builtin_providers_in_default_order() {
+        let source = ImageSource::new(None);
+        assert_eq!(names(&source), vec![BING_API_PROVIDER, GITHUB_ARCHIVE_PROVIDER]);
+    }
+
+    #[test]
+    fn test_settings_order_and_disable_providers() {
+        let mut source = ImageSource::empty(SourceSettings::default());
+        source.register(fake("a", 10, vec![]));
+        source.register(fake("b", 20, vec![]));
+        source.register(fake("c", 30, vec![]));
+        assert_eq!(names(&source), vec!["a", "b", "c"]);
+
+        source.set_settings(SourceSettings {
+            order: vec!["c".to_string()],
+            disabled: vec!["a".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(names(&source), vec!["c", "b"]);
+        assert_eq!(source.providers().len(), 3);
+    }
+
+    #[test]
+    fn test_first_provider_wins_duplicates() {
+        let mut source = ImageSource::empty(SourceSettings::default());
+        source.register(fake("primary", 10, vec![image("https://example.com/1.jpg", "Shared")]));
+        source.register(fake("secondary", 20, vec![
+            image("https://mirror.example.com/1.jpg", "Shared"),
+            image("https://mirror.example.com/2.jpg", "Only here"),
+        ]));
+
+        let images = source.fetch_images(None, 10, &[]).unwrap().images;
+        let urls: Vec<&str> = images.iter().map(|i| i.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://example.com/1.jpg", "https://mirror.example.com/2.jpg"]);
+    }
+
+    #[test]
+    fn test_failing_provider_reported_in_health() {
+        let mut source = ImageSource::empty(SourceSettings::default());
+        source.register(fake("ok", 10, vec![image("https://example.com/1.jpg", "One")]));
+        source.register(Arc::new(FakeProvider { name: "down", priority: 20, images: vec![], fail: true }));
+
+        let fetched = source.fetch_images(None, 10, &[]).unwrap();
+        assert_eq!(fetched.images.len(), 1);
+        assert!(fetched.answered("ok"));
+        assert!(!fetched.answered("down"));
+
+        let health = source.health_report();
+        assert_eq!(health[0], ("ok".to_string(), ProviderHealth::Healthy));
+        assert!(matches!(health[1].1, ProviderHealth::Degraded(_)));
+    }
+
+    #[test]
+    fn test_all_providers_failing_is_an_error() {
+        let mut source = ImageSource::empty(SourceSettings::default());
+        source.register(Arc::new(FakeProvider { name: "down", priority: 10, images: vec![], fail: true }));
+        assert!(source.fetch_images(None, 10, &[]).is_err());
+    }
+
+    #[test]
+    fn test_skipped_provider_is_not_attempted() {
+        let mut source = ImageSource::empty(SourceSettings::default());
+        source.register(Arc::new(IdleProvider));
+        let fetched = source.fetch_images(None, 10, &[]).unwrap();
+        assert!(fetched.images.is_empty());
+        assert!(!fetched.answered("idle"));
+
+        // The only provider that was queried failed
+        source.register(Arc::new(FakeProvider { name: "down", priority: 20, images: vec![], fail: true }));
+        assert!(source.fetch_images(None, 10, &[]).is_err());
+    }
+
+    #[test]
+    fn test_source_settings_persist_in_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut conn = db::establish_connection(&temp_dir.path().join("test.db")).unwrap();
+
+        assert_eq!(commands::get_source_settings_sync(&mut conn).unwrap(), SourceSettings::default());
+
+        let settings = SourceSettings {
+            order: vec![GITHUB_ARCHIVE_PROVIDER.to_string()],
+            disabled: vec![BING_API_PROVIDER.to_string()],
+            ..Default::default()
+        };
+        commands::save_source_settings_sync(&mut conn, &settings).unwrap();
+
+        let source = commands::image_source_from_settings_sync(&mut conn).unwrap();
+        assert_eq!(names(&source), vec![GITHUB_ARCHIVE_PROVIDER]);
+    }
+}
+
+mod archive {
+    use bingtray::db::{self, operations};
+    use bingtray::viewmodel::commands;
+    use bingtray::viewmodel::sources::{archive_row_date, parse_archive_since, ArchiveSyncState};
+    use tempfile::TempDir;
+
+    const ARCHIVE: &str = "\
+## Bing Wallpaper
+2026-06-03 | [Fjord (© A)](https://cn.bing.com/th?id=OHR.Fjord_ZH-CN3_1920x1080.jpg)
+2026-06-02 | [Desert (© B)](https://cn.bing.com/th?id=OHR.Desert_ZH-CN2_1920x1080.jpg)
+2026-06-01 | [Lake (© C)](https://cn.bing.com/th?id=OHR.Lake_ZH-CN1_1920x1080.jpg)
+";
+
+    #[test]
+    fn test_parse_archive_since_watermark() {
+        let (all, newest) = parse_archive_since(ARCHIVE, None);
+        assert_eq!(all.len(), 3);
+        assert_eq!(newest.as_deref(), Some("2026-06-03"));
+
+        let (new_rows, newest) = parse_archive_since(ARCHIVE, Some("2026-06-01"));
+        let titles: Vec<&str> = new_rows.iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(titles, vec!["Fjord", "Desert"]);
+        assert_eq!(newest.as_deref(), Some("2026-06-03"));
+
+        let (none, newest) = parse_archive_since(ARCHIVE, Some("2026-06-03"));
+        assert!(none.is_empty());
+        assert_eq!(newest.as_deref(), Some("2026-06-03"));
+    }
+
+    #[test]
+    fn test_archive_state_due_follows_manifest_timestamp() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut conn = db::establish_connection(&temp_dir.path().join("test.db")).unwrap();
+
+        // Never synced: due, no validators
+        let state = commands::get_archive_state_sync(&mut conn).unwrap();
+        assert!(state.due);
+        assert_eq!(state.etag, None);
+
+        let stored = ArchiveSyncState {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: Some("Wed, 03 Jun 2026 00:00:00 GMT".to_string()),
+            watermark: Some("2026-06-03".to_string()),
+            due: false,
+        };
+        operations::set_config(&mut conn, "github_archive_state", &serde_json::to_string(&stored).unwrap()).unwrap();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        operations::set_last_download_timestamp(&mut conn, commands::GITHUB_ARCHIVE_MANIFEST, now).unwrap();
+
+        // Checked just now: restored but not due
+        let state = commands::get_archive_state_sync(&mut conn).unwrap();
+        assert_eq!(state, stored);
+
+        // A week later the archive is due again (conditional request keeps it cheap)
+        operations::set_last_download_timestamp(&mut conn, commands::GITHUB_ARCHIVE_MANIFEST, now - 8 * 86400).unwrap();
+        let state = commands::get_archive_state_sync(&mut conn).unwrap();
+        assert!(state.due);
+        assert_eq!(state.watermark.as_deref(), Some("2026-06-03"));
+    }
+
+    #[test]
+    fn test_archive_row_date_matches_watermark_format() {
+        let (rows, newest) = parse_archive_since(ARCHIVE, None);
+        assert_eq!(archive_row_date(&rows[0]), newest);
+        assert_eq!(archive_row_date(&rows[2]).as_deref(), Some("2026-06-01"));
+        assert_eq!(archive_row_date(&Default::default()), None);
+    }
+
+    #[test]
+    fn test_import_skips_archive_checked_recently() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut conn = db::establish_connection(&temp_dir.path().join("test.db")).unwrap();
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        operations::set_last_download_timestamp(&mut conn, commands::GITHUB_ARCHIVE_MANIFEST, now).unwrap();
+
+        let source = commands::image_source_from_settings_sync(&mut conn).unwrap();
+        let cancel = std::sync::atomic::AtomicBool::new(false);
+        let mut progress = Vec::new();
+        let import = commands::import_github_archive_sync(&mut conn, &source, &cancel, |current, total| {
+            progress.push((current, total));
+        })
+        .unwrap();
+
+        assert_eq!(import, commands::ArchiveImport::default());
+        assert!(progress.is_empty());
+    }
+}
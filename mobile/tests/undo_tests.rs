@@ -0,0 +1,113 @@
+use bingtray::db::{self, models::*, operations, ImageStatus, StatusLogAction, WallpaperSource};
+use bingtray::viewmodel::commands;
+use bingtray::viewmodel::undo::{self, can_redo_sync, can_undo_sync, redo_sync, undo_last_action_sync};
+use diesel::prelude::*;
+use tempfile::TempDir;
+
+const FJORD: &str = "https://example.com/fjord.jpg";
+const DESERT: &str = "https://example.com/desert.jpg";
+
+fn setup_test_db() -> (SqliteConnection, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let mut conn = db::establish_connection(&db_path).unwrap();
+
+    for url in [FJORD, DESERT] {
+        let image = NewBingImage {
+            url,
+            title: "Test Image",
+            market_code: "en-US",
+            status: ImageStatus::Unprocessed.as_str(),
+            ..Default::default()
+        };
+        operations::upsert_image(&mut conn, &image).unwrap();
+    }
+
+    (conn, temp_dir)
+}
+
+fn status_of(conn: &mut SqliteConnection, url: &str) -> String {
+    operations::get_image(conn, url).unwrap().unwrap().status
+}
+
+#[test]
+fn test_every_transition_is_logged() {
+    let (mut conn, _dir) = setup_test_db();
+
+    operations::update_image_status(&mut conn, FJORD, ImageStatus::KeepFavorite).unwrap();
+    commands::blacklist_image_sync(&mut conn, FJORD, WallpaperSource::Gui).unwrap();
+    // Setting the same status again is not a transition
+    commands::blacklist_image_sync(&mut conn, FJORD, WallpaperSource::Gui).unwrap();
+
+    let log = operations::get_status_log_for_image(&mut conn, FJORD).unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!((log[0].old_status.as_str(), log[0].new_status.as_str()), ("unprocessed", "keepfavorite"));
+    assert_eq!(log[0].source, None);
+    assert_eq!((log[1].old_status.as_str(), log[1].new_status.as_str()), ("keepfavorite", "blacklisted"));
+    assert_eq!(log[1].source.as_deref(), Some("gui"));
+}
+
+#[test]
+fn test_automatic_changes_are_not_undoable() {
+    let (mut conn, _dir) = setup_test_db();
+
+    operations::update_image_status(&mut conn, FJORD, ImageStatus::Blacklisted).unwrap();
+
+    assert!(!can_undo_sync(&mut conn).unwrap());
+    assert!(undo_last_action_sync(&mut conn, WallpaperSource::Cli).unwrap().is_none());
+    assert_eq!(status_of(&mut conn, FJORD), "blacklisted");
+}
+
+#[test]
+fn test_undo_and_redo_walk_the_log() {
+    let (mut conn, _dir) = setup_test_db();
+    // The wallpaper is still the blacklisted image, so undo leaves it in place
+    operations::set_config(&mut conn, "current_wallpaper_url", FJORD).unwrap();
+
+    commands::toggle_favorite_sync(&mut conn, DESERT, WallpaperSource::Gui).unwrap();
+    undo::record_status_change_sync(&mut conn, FJORD, ImageStatus::Blacklisted, WallpaperSource::Tray, Some(FJORD))
+        .unwrap();
+
+    let outcome = undo_last_action_sync(&mut conn, WallpaperSource::Tray).unwrap().unwrap();
+    assert_eq!(outcome.action, StatusLogAction::Undo);
+    assert_eq!(outcome.url, FJORD);
+    assert_eq!(outcome.status, ImageStatus::Unprocessed);
+    assert!(outcome.wallpaper.is_none());
+    assert_eq!(status_of(&mut conn, FJORD), "unprocessed");
+
+    // Undo again reaches the earlier change
+    let outcome = undo_last_action_sync(&mut conn, WallpaperSource::Cli).unwrap().unwrap();
+    assert_eq!(outcome.url, DESERT);
+    assert_eq!(status_of(&mut conn, DESERT), "unprocessed");
+    assert!(!can_undo_sync(&mut conn).unwrap());
+
+    // Redo reapplies in the original order
+    let outcome = redo_sync(&mut conn, WallpaperSource::Cli).unwrap().unwrap();
+    assert_eq!(outcome.action, StatusLogAction::Redo);
+    assert_eq!(outcome.url, DESERT);
+    assert_eq!(status_of(&mut conn, DESERT), "keepfavorite");
+    let outcome = redo_sync(&mut conn, WallpaperSource::Cli).unwrap().unwrap();
+    assert_eq!(outcome.url, FJORD);
+    assert_eq!(status_of(&mut conn, FJORD), "blacklisted");
+    assert!(redo_sync(&mut conn, WallpaperSource::Cli).unwrap().is_none());
+
+    // Undo and redo are appended, the log keeps every step
+    let log = operations::get_status_log_for_image(&mut conn, FJORD).unwrap();
+    let actions: Vec<&str> = log.iter().map(|entry| entry.action.as_str()).collect();
+    assert_eq!(actions, ["change", "undo", "redo"]);
+    assert_eq!(log[1].reverts_id, Some(log[0].id));
+}
+
+#[test]
+fn test_new_change_drops_redo() {
+    let (mut conn, _dir) = setup_test_db();
+
+    commands::blacklist_image_sync(&mut conn, FJORD, WallpaperSource::Gui).unwrap();
+    undo_last_action_sync(&mut conn, WallpaperSource::Gui).unwrap().unwrap();
+    assert!(can_redo_sync(&mut conn).unwrap());
+
+    commands::toggle_favorite_sync(&mut conn, DESERT, WallpaperSource::Gui).unwrap();
+    assert!(!can_redo_sync(&mut conn).unwrap());
+    assert!(redo_sync(&mut conn, WallpaperSource::Gui).unwrap().is_none());
+    assert_eq!(status_of(&mut conn, FJORD), "unprocessed");
+}
@@ -19,7 +19,7 @@ mod cli_tests {
     fn setup_test_db() -> (SqliteConnection, TempDir) {
         let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().join("test.db");
-        let conn = db::establish_connection(&db_path);
+        let conn = db::establish_connection(&db_path).unwrap();
         (conn, temp_dir)
     }
 
@@ -39,6 +39,7 @@ mod cli_tests {
             status: status.as_str(),
             created_at: timestamp,
             updated_at: timestamp,
+            ..Default::default()
         }
     }
 
@@ -218,7 +219,7 @@ mod cli_tests {
         // Note: ViewModel doesn't expose market state methods directly yet
         // These would be internal to download_and_set_next_wallpaper_sync
         // We test them via commands module directly
-        let mut conn = db::establish_connection(&db_path);
+        let mut conn = db::establish_connection(&db_path).unwrap();
         
         // Default state
         let (market, offset) = commands::get_market_state_sync(&mut conn).unwrap();
@@ -284,6 +285,7 @@ mod cli_tests {
             status: "unprocessed",
             created_at: base_time,
             updated_at: base_time,
+            ..Default::default()
         };
         
         let img2 = NewBingImage {
@@ -296,6 +298,7 @@ mod cli_tests {
             status: "unprocessed",
             created_at: base_time + 1000,
             updated_at: base_time + 1000,
+            ..Default::default()
         };
         
         operations::upsert_image(&mut conn, &img1).unwrap();
@@ -326,13 +329,13 @@ mod cli_tests {
         
         // First connection - save state
         {
-            let mut conn = db::establish_connection(&db_path);
+            let mut conn = db::establish_connection(&db_path).unwrap();
             commands::save_market_state_sync(&mut conn, "ja-JP", 24).unwrap();
         }
         
         // Second connection - verify persistence
         {
-            let mut conn = db::establish_connection(&db_path);
+            let mut conn = db::establish_connection(&db_path).unwrap();
             let (market, offset) = commands::get_market_state_sync(&mut conn).unwrap();
             assert_eq!(market, "ja-JP");
             assert_eq!(offset, 24);
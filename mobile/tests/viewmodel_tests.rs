@@ -70,7 +70,7 @@ fn test_unmark_image_command() {
 
     // Insert a test image directly to database BEFORE creating ViewModel
     {
-        let mut conn = establish_connection(&db_path);
+        let mut conn = establish_connection(&db_path).unwrap();
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
@@ -86,6 +86,7 @@ fn test_unmark_image_command() {
             fetched_at: now,
             created_at: now,
             updated_at: now,
+            ..Default::default()
         };
         operations::upsert_image(&mut conn, &test_image).unwrap();
         // Explicitly drop connection
@@ -116,7 +117,7 @@ fn test_unmark_image_command() {
 
     // Verify in database
     {
-        let mut conn = establish_connection(&db_path);
+        let mut conn = establish_connection(&db_path).unwrap();
         let image = operations::get_image(&mut conn, "https://bing.com/test_image.jpg")
             .unwrap()
             .expect("Image should exist");